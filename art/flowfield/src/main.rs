@@ -9,6 +9,7 @@ use plotz_core::{
 };
 use plotz_geometry::{
     crop::Croppable,
+    field::advect,
     obj2::Obj2,
     shapes::{
         curve::CurveArc, multiline::Multiline, point::Point, polygon::Polygon, segment::Segment,
@@ -91,6 +92,14 @@ fn main() -> Result<()> {
         }
     }
 
+    // every arrow pushes along itself, harder the further away it is.
+    let field = |pt: &Point| -> Point {
+        arrows_store
+            .iter()
+            .map(|arrow| (arrow.f - arrow.i) * pt.dist(&arrow.i).sqrt() / MOMENTUM)
+            .fold(Point(0, 0), |acc, x| acc + x)
+    };
+
     dos.extend(
         (0..NUM_CLUSTERS)
             .into_par_iter()
@@ -109,20 +118,8 @@ fn main() -> Result<()> {
                         let ry = thread_rng().gen_range(CLUSTER_RANGE.clone());
                         let pt = cluster_center + (rx, ry);
 
-                        let mut history = vec![pt];
                         let num_steps = thread_rng().gen_range(NUM_STEPS_RANGE.clone());
-                        for _ in 0..=num_steps {
-                            let last = history.last().unwrap();
-                            let del: Point = arrows_store
-                                .iter()
-                                .map(|arrow| {
-                                    let scaling_factor: f64 = last.dist(&arrow.i).sqrt();
-                                    (arrow.f - arrow.i) * scaling_factor / MOMENTUM
-                                })
-                                .fold(Point(0, 0), |acc, x| acc + x);
-                            let next: Point = *last + del;
-                            history.push(next);
-                        }
+                        let history = advect(&field, pt, 1.0, num_steps + 1);
 
                         (
                             Multiline(history).into(),
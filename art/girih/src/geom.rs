@@ -2,6 +2,7 @@ use anyhow::Result;
 use plotz_color::{subway::PURPLE_7, ColorRGB, LIGHTBLUE, LIMEGREEN, ORANGERED, YELLOW};
 use plotz_geometry::{
     bounded::Bounded,
+    containment::{default_tolerance, Locate},
     crop::PointLocation,
     overlaps::{
         opinion::{polygon_opinion::PolygonOp, segment_opinion::SegmentOp},
@@ -181,8 +182,8 @@ impl PlacedTile {
                 let sg_1_f = edge1.midpoint() + PolarPt(0.1, angle_1);
                 let sg_2_f = edge1.midpoint() + PolarPt(0.1, angle_2);
                 match (
-                    self.pg.contains_pt_deprecated(&sg_1_f)?,
-                    self.pg.contains_pt_deprecated(&sg_2_f)?,
+                    self.pg.locate(&sg_1_f, default_tolerance())?,
+                    self.pg.locate(&sg_2_f, default_tolerance())?,
                 ) {
                     (PointLocation::Inside, _) => angle_1,
                     (_, PointLocation::Inside) => angle_2,
@@ -10,13 +10,13 @@ use plotz_core::{
 };
 use plotz_geometry::{
     crop::Croppable,
+    field::{advect, PointCharges},
     obj2::Obj2,
-    shapes::{curve::CurveArc, multiline::Multiline, point::Point, polygon::Polygon},
+    shapes::{multiline::Multiline, point::Point, polygon::Polygon},
     style::Style,
 };
-use plotz_physics::{framework, particle::*};
 use rand::{thread_rng, Rng};
-use std::{f64::consts::TAU, ops::Range};
+use std::ops::Range;
 
 const CHARGE_MAX: f64 = 2.0;
 const CHARGE_RANGE: Range<f64> = -1.0 * CHARGE_MAX..CHARGE_MAX;
@@ -36,11 +36,6 @@ struct Args {
     output_path_prefix: String,
 }
 
-#[derive(Copy, Clone, Debug)]
-struct Metadata {
-    color: ColorRGB,
-}
-
 fn main() -> Result<()> {
     let args: Args = argh::from_env();
     let mut os: Vec<(Obj2, Style)> = vec![];
@@ -60,83 +55,40 @@ fn main() -> Result<()> {
         (margin, margin),
     )?;
 
-    let mut framework: framework::Framework<Metadata> = framework::Framework {
-        config: framework::Config { pow: 1.2 },
-        ..Default::default()
-    };
-
-    // init configuration
-    let particles = {
-        let mut ret = vec![];
-
-        for i in (0..=(1000 / GRID_GRANULARITY)).map(|n| n * GRID_GRANULARITY) {
-            for j in (0..=(800 / GRID_GRANULARITY)).map(|n| n * GRID_GRANULARITY) {
-                // Insert a fixed, invisible high charge particle.
-                let charge = thread_rng().gen_range(CHARGE_RANGE.clone());
-                ret.push(
-                    Particle::builder()
-                        .position((i as f64, j as f64))
-                        .mobility(Mobility::Fixed)
-                        .charge(charge)
-                        .visibility(Visibility::Invisible)
-                        .metadata(Metadata {
-                            color: if charge < 0.0 { RED } else { GREEN },
-                        })
-                        .build(),
-                );
-            }
+    // fixed, invisible charges on a grid.
+    let mut charges = vec![];
+    for i in (0..=(1000 / GRID_GRANULARITY)).map(|n| n * GRID_GRANULARITY) {
+        for j in (0..=(800 / GRID_GRANULARITY)).map(|n| n * GRID_GRANULARITY) {
+            charges.push((
+                Point(i as f64, j as f64),
+                thread_rng().gen_range(CHARGE_RANGE.clone()),
+            ));
         }
+    }
+    let field = PointCharges { charges, pow: 1.2 };
 
-        for _ in 0..NUM_CLUSTERS {
-            let cluster_color = random_color();
-            let cluster_center = Point(
-                thread_rng().gen_range(0..=900),
-                thread_rng().gen_range(0..=700),
-            );
-            for _ in 0..NUM_PARTICLES_PER_CLUSTER {
-                ret.push(
-                    Particle::builder()
-                        .position(
-                            cluster_center
-                                + (
-                                    thread_rng().gen_range(CLUSTER_RANGE.clone()),
-                                    thread_rng().gen_range(CLUSTER_RANGE.clone()),
-                                ),
-                        )
-                        .mobility(Mobility::Mobile)
-                        .visibility(Visibility::Visible)
-                        .metadata(Metadata {
-                            color: cluster_color,
-                        })
-                        .build(),
+    // clusters of particles, each charting its course through the field.
+    for _ in (0..NUM_CLUSTERS).progress_with(make_bar(NUM_CLUSTERS, "running simulation...")) {
+        let cluster_color = random_color();
+        let cluster_center = Point(
+            thread_rng().gen_range(0..=900),
+            thread_rng().gen_range(0..=700),
+        );
+        for _ in 0..NUM_PARTICLES_PER_CLUSTER {
+            let start = cluster_center
+                + (
+                    thread_rng().gen_range(CLUSTER_RANGE.clone()),
+                    thread_rng().gen_range(CLUSTER_RANGE.clone()),
                 );
-            }
+            os.push((
+                Multiline(advect(&field, start, 1.0, NUM_STEPS + 1)).into(),
+                Style {
+                    color: cluster_color,
+                    thickness: 0.1,
+                    ..Default::default()
+                },
+            ));
         }
-        ret
-    };
-
-    for p in particles {
-        framework.add_particle(p);
-    }
-
-    for _ in (0..=NUM_STEPS).progress_with(make_bar(NUM_STEPS, "running simulation...")) {
-        framework.advance();
-    }
-
-    for (_uuid, p) in framework.into_particles_visible() {
-        os.push((
-            match p.history.len() {
-                // If the object has no history, it is a static particle -- a circle.
-                0 => CurveArc(p.position, 0.0..=TAU, 2.0).into(),
-                // Otherwise, chart its course.
-                _ => Multiline(p.history).into(),
-            },
-            Style {
-                color: p.metadata.unwrap().color,
-                thickness: 0.1,
-                ..Default::default()
-            },
-        ));
     }
 
     let frame_pg: Polygon = frame.0.clone().try_into().unwrap();
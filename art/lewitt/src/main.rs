@@ -7,6 +7,7 @@ use plotz_core::{
     svg::Size,
 };
 use plotz_geometry::{
+    containment::{default_tolerance, Locate},
     crop::PointLocation,
     grid::grid_layout::{GridLayout, GridLayoutSettings},
     obj2::Obj2,
@@ -63,7 +64,7 @@ fn main() -> Result<()> {
                         loop {
                             let cand = Point(rng.gen_range(0.0..800.0), rng.gen_range(0.0..1000.0));
                             if !matches!(
-                                bounds.to_polygon().contains_pt_deprecated(&cand),
+                                bounds.locate(&cand, default_tolerance()),
                                 Ok(PointLocation::Inside)
                             ) {
                                 return cand;
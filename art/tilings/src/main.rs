@@ -6,7 +6,8 @@ use plotz_core::{
     svg::Size,
 };
 use plotz_geometry::{
-    crop::PointLocation, obj2::Obj2, shapes::polygon::Polygon, style::Style, Object,
+    containment::default_tolerance, crop::PointLocation, obj2::Obj2, shapes::polygon::Polygon,
+    style::Style, Object,
 };
 
 mod ab_rhomb;
@@ -37,13 +38,10 @@ fn main() -> Result<()> {
     let frame_polygon: Polygon = frame.0.clone().try_into().unwrap();
 
     // drain things not in frame
+    let frame_locator = frame_polygon.locator(default_tolerance())?;
     dos.retain(|(obj, _style)| {
-        obj.iter().all(|pt| {
-            matches!(
-                frame_polygon.contains_pt_deprecated(pt),
-                Ok(PointLocation::Inside)
-            )
-        })
+        obj.iter()
+            .all(|pt| frame_locator.locate(pt) == PointLocation::Inside)
     });
 
     Canvas::builder()
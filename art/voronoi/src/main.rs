@@ -9,8 +9,12 @@ use plotz_core::{
 use plotz_geometry::{
     obj2::Obj2,
     shading::{shade_config::ShadeConfig, shade_polygon},
-    shapes::{point::Point, polygon::Polygon},
+    shapes::{
+        point::Point,
+        polygon::{Polygon, Rect},
+    },
     style::Style,
+    voronoi::{lloyd_relaxation, voronoi},
 };
 use rand::{prelude::SliceRandom, Rng};
 use std::f64::consts::*;
@@ -38,7 +42,7 @@ impl Shade {
             config: ShadeConfig::builder()
                 .gap(3.0)
                 .switchback(true)
-                .slope((rng.gen_range(0.0_f64..360.0_f64)).tan())
+                .angle(rng.gen_range(0.0..PI))
                 .build(),
             color: *palette.choose(&mut rng).ok_or(anyhow!("?"))?,
         })
@@ -63,36 +67,23 @@ fn main() -> Result<()> {
         // &PINK,
     ];
 
-    let sites: Vec<voronoice::Point> = (1..150)
+    let sites: Vec<Point> = (1..150)
         .step_by(1)
         .map(|_| {
             let r: f64 = rng.gen_range(0.0..0.5);
             let theta: f64 = rng.gen_range(0.0..TAU);
 
-            voronoice::Point {
-                x: r * theta.cos() + 0.5,
-                y: r * theta.sin() + 0.5,
-            }
+            Point(r * theta.cos() + 0.5, r * theta.sin() + 0.5)
         })
         .collect();
 
-    let vornoi = voronoice::VoronoiBuilder::default()
-        .set_sites(sites)
-        .set_bounding_box(voronoice::BoundingBox::new(
-            voronoice::Point { x: 0.5, y: 0.5 },
-            1.0,
-            1.0,
-        ))
-        .set_lloyd_relaxation_iterations(10)
-        .build()
-        .ok_or(anyhow!("build voronoi"))?;
+    let frame = Rect((0, 0), (1, 1))?;
+    let sites = lloyd_relaxation(&sites, &frame, 10)?;
 
-    let polygons: Vec<Polygon> = vornoi
-        .iter_cells()
-        .map(|cell| {
-            Polygon(cell.iter_vertices().map(|vertex| (vertex.x, vertex.y))).unwrap() * DIM
-                + (20, 20)
-        })
+    let polygons: Vec<Polygon> = voronoi(&sites, &frame)?
+        .into_iter()
+        .flatten()
+        .map(|cell| cell * DIM + (20, 20))
         .collect();
 
     let mut dos = vec![];
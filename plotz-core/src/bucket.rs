@@ -69,4 +69,5 @@ pub enum Bucket {
     Area(Area),
     Path(Path),
     Color(ColorRGB),
+    Tone(usize),
 }
@@ -7,6 +7,7 @@ pub mod canvas;
 pub mod frame;
 pub mod map;
 pub mod svg;
+pub mod tonal;
 
 mod bucket;
 mod bucketer;
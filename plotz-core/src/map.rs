@@ -15,12 +15,13 @@ use itertools::Itertools;
 use lazy_static::lazy_static;
 use plotz_color::{subway::*, *};
 use plotz_geometry::{
-    bounded::Bounded,
+    bounded::{Bounded, Bounds},
     crop::Croppable,
     obj2::Obj2,
-    shading::{shade_config::ShadeConfig, shade_polygon},
+    shading::{can_shade, shade, shade_config::ShadeConfig},
     shapes::{point::Point, polygon::Polygon, segment::Segment},
     style::Style,
+    tolerance::{self, Tolerance},
 };
 use rand::{thread_rng, Rng};
 use std::{
@@ -68,6 +69,8 @@ fn map_bucket_to_color(bucket: &Bucket) -> Option<ColorRGB> {
 
         Bucket::Color(c) => Some(*c),
 
+        Bucket::Tone(_) => None,
+
         Bucket::Area(area) => match area {
             Area::Beach => Some(TAN),
             Area::Fun => Some(LIGHTCYAN),
@@ -287,38 +290,31 @@ impl Map {
         Ok(())
     }
 
-    fn apply_shading_to_objects(&mut self) {
+    fn apply_shading_to_objects(&mut self) -> Result<()> {
         for (bucket, layers) in self.canvas.dos_by_bucket.iter_mut() {
             if let Some(bucket) = bucket {
                 if let Some((shade_and_outline, ref shade_config)) =
                     map_bucket_to_shadeconfig(bucket)
                 {
                     let mut v: Vec<(Obj2, Style)> = vec![];
-                    // keep the frame, add the crosshatchings.
+                    // keep the frame, add the crosshatchings. open shapes have
+                    // nothing to shade.
                     let crosshatchings: Vec<(Obj2, Style)> = layers
                         .iter()
-                        .filter_map(|(obj, style)| match &obj {
-                            Obj2::Polygon(p) => match shade_polygon(shade_config, p) {
-                                Err(_) => None,
-                                Ok(segments) => Some(
-                                    segments
-                                        .into_iter()
-                                        .map(|s| {
-                                            (
-                                                Obj2::Segment(s),
-                                                Style {
-                                                    thickness: shade_config.thickness,
-                                                    ..*style
-                                                },
-                                            )
-                                        })
-                                        .collect::<Vec<_>>(),
-                                ),
-                            },
-                            _ => None,
+                        .filter(|(obj, _)| can_shade(obj))
+                        .map(|(obj, style)| {
+                            Ok(shade(shade_config, obj)?.into_iter().map(|s| {
+                                (
+                                    Obj2::Segment(s),
+                                    Style {
+                                        thickness: shade_config.thickness,
+                                        ..*style
+                                    },
+                                )
+                            }))
                         })
-                        .flatten()
-                        .collect();
+                        .flatten_ok()
+                        .collect::<Result<Vec<_>>>()?;
                     match shade_and_outline {
                         ShadeAndOutline::JustShade => {
                             v.extend(crosshatchings.iter().map(|so2| (so2.0.clone(), so2.1)));
@@ -332,6 +328,7 @@ impl Map {
                 }
             }
         }
+        Ok(())
     }
 
     /// Adjusts the map for scale/transform issues.
@@ -436,26 +433,37 @@ impl Map {
 
         let () = self.do_all_adjustments(config.scale_factor, &config.size)?;
 
-        // let () = self.randomize_circles();
-        let () = self.apply_shading_to_objects();
-
-        // self.simplify_layers();
-
-        if config.draw_frame {
-            info!("Adding frame.");
-            let margin = 20.0;
-            let frame = make_frame(
-                // yes these are backwards. oops
-                (
-                    config.size.height as f64 - 2.0 * margin,
-                    config.size.width as f64 - 2.0 * margin,
-                ),
-                Point(margin, margin),
-            )?;
-            let frame_pg: Polygon = frame.0.clone().try_into().unwrap();
-            self.canvas.frame = Some(frame);
-            let () = self.crop_to_frame(&frame_pg)?;
-        }
+        // from here on, everything is in page coordinates; compare them at
+        // that scale.
+        let page = Bounds {
+            x_min: 0.0,
+            x_max: config.size.width as f64,
+            y_min: 0.0,
+            y_max: config.size.height as f64,
+        };
+        tolerance::with_tolerance(Tolerance::for_bounds(&page), || -> Result<()> {
+            // let () = self.randomize_circles();
+            let () = self.apply_shading_to_objects()?;
+
+            // self.simplify_layers();
+
+            if config.draw_frame {
+                info!("Adding frame.");
+                let margin = 20.0;
+                let frame = make_frame(
+                    // yes these are backwards. oops
+                    (
+                        config.size.height as f64 - 2.0 * margin,
+                        config.size.width as f64 - 2.0 * margin,
+                    ),
+                    Point(margin, margin),
+                )?;
+                let frame_pg: Polygon = frame.0.clone().try_into().unwrap();
+                self.canvas.frame = Some(frame);
+                let () = self.crop_to_frame(&frame_pg)?;
+            }
+            Ok(())
+        })?;
 
         self.canvas
             .write_to_svg(config.size, config.output_directory.to_str().unwrap())?;
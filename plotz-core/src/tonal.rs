@@ -0,0 +1,309 @@
+//! Tonal hatching: plots a grayscale image as hatched cells, more densely
+//! where the image is darker.
+
+use crate::{bucket::Bucket, canvas::Canvas};
+use anyhow::Result;
+use image::GrayImage;
+use plotz_color::{ColorRGB, BLACK};
+use plotz_geometry::{
+    bounded::{Bounded, Bounds},
+    containment::default_tolerance,
+    crop::PointLocation,
+    grid::grid_layout::GridLayout,
+    obj2::Obj2,
+    shading::{shade_config::ShadeConfig, shade_polygon},
+    shapes::{point::Point, polygon::Polygon},
+    style::Style,
+    voronoi::{lloyd_relaxation, voronoi},
+};
+use rand::Rng;
+use std::{
+    collections::HashMap,
+    f64::consts::{FRAC_PI_4, PI},
+    path::Path,
+};
+use typed_builder::TypedBuilder;
+
+/// How darkness is drawn.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Tone {
+    /// One direction of hatching, whose lines are closer together the darker
+    /// the cell.
+    Density,
+    /// Layers of hatching at evenly spaced angles; the darker the cell, the
+    /// more layers cross it.
+    CrossHatch,
+}
+
+/// Config for tonal hatching.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct TonalConfig {
+    /// How many tones to quantize darkness into. Each is its own layer.
+    #[builder(default = 4)]
+    pub levels: usize,
+
+    /// The gap between lines: in every layer for |Tone::CrossHatch|, and in
+    /// the darkest layer for |Tone::Density|.
+    #[builder(default = 2.0)]
+    pub gap: f64,
+
+    /// The angle of the first layer, in radians.
+    #[builder(default = FRAC_PI_4)]
+    pub angle: f64,
+
+    /// How darkness is drawn.
+    #[builder(default = Tone::CrossHatch)]
+    pub tone: Tone,
+
+    /// The color of every line.
+    #[builder(default = BLACK)]
+    pub color: ColorRGB,
+
+    /// The thickness of every line.
+    #[builder(default = 1.0)]
+    pub thickness: f64,
+}
+
+/// Loads an image (PNG or JPEG) as grayscale.
+pub fn load_grayscale(path: impl AsRef<Path>) -> Result<GrayImage> {
+    Ok(image::open(path)?.to_luma8())
+}
+
+/// The cubbies of a grid layout, of whatever shape, as cells, column by
+/// column (see |GridLayout::get_cubby_polygons|).
+pub fn grid_cells(layout: &GridLayout) -> Result<Vec<Polygon>> {
+    layout.get_cubby_polygons()
+}
+
+/// |count| Voronoi cells over |frame|, from randomly placed sites evened out
+/// by |iterations| of Lloyd relaxation.
+pub fn voronoi_cells(
+    frame: &Polygon,
+    count: usize,
+    iterations: usize,
+    rng: &mut impl Rng,
+) -> Result<Vec<Polygon>> {
+    let bounds = frame.bounds()?;
+    let sites: Vec<Point> = (0..count)
+        .map(|_| {
+            Point(
+                rng.gen_range(bounds.x_min..bounds.x_max),
+                rng.gen_range(bounds.y_min..bounds.y_max),
+            )
+        })
+        .collect();
+    let sites = lloyd_relaxation(&sites, frame, iterations)?;
+    Ok(voronoi(&sites, frame)?.into_iter().flatten().collect())
+}
+
+/// The mean darkness (0 for white, 1 for black) of |image| within |cell|,
+/// where the image is stretched over |frame|. A cell too small to hold the
+/// center of any pixel takes the darkness of the pixel under its center.
+pub fn darkness(image: &GrayImage, frame: &Bounds, cell: &Polygon) -> Result<f64> {
+    let (w, h) = image.dimensions();
+    if w == 0 || h == 0 {
+        return Ok(0.0);
+    }
+    let (sx, sy) = (
+        (frame.x_max - frame.x_min) / w as f64,
+        (frame.y_max - frame.y_min) / h as f64,
+    );
+    let to_px =
+        |x: f64, lo: f64, s: f64, n: u32| ((x - lo) / s).floor().clamp(0.0, n as f64 - 1.0) as u32;
+    let dark = |px: u32, py: u32| 1.0 - image.get_pixel(px, py).0[0] as f64 / 255.0;
+
+    let b = cell.bounds()?;
+    let locator = cell.locator(default_tolerance())?;
+    let (mut total, mut count) = (0.0, 0);
+    for py in to_px(b.y_min, frame.y_min, sy, h)..=to_px(b.y_max, frame.y_min, sy, h) {
+        for px in to_px(b.x_min, frame.x_min, sx, w)..=to_px(b.x_max, frame.x_min, sx, w) {
+            let ctr = Point(
+                frame.x_min + (px as f64 + 0.5) * sx,
+                frame.y_min + (py as f64 + 0.5) * sy,
+            );
+            if locator.locate(&ctr) != PointLocation::Outside {
+                total += dark(px, py);
+                count += 1;
+            }
+        }
+    }
+    if count == 0 {
+        let c = b.center();
+        return Ok(dark(
+            to_px(c.x, frame.x_min, sx, w),
+            to_px(c.y, frame.y_min, sy, h),
+        ));
+    }
+    Ok(total / count as f64)
+}
+
+/// The darkness of |image|, stretched over |frame|, at any point; for
+/// driving |plotz_geometry::stipple::stipple| from a photograph.
+pub fn image_density<'a>(image: &'a GrayImage, frame: &'a Bounds) -> impl Fn(&Point) -> f64 + 'a {
+    move |pt| {
+        let (w, h) = image.dimensions();
+        if w == 0 || h == 0 {
+            return 0.0;
+        }
+        let px = (pt.x - frame.x_min) / (frame.x_max - frame.x_min) * w as f64;
+        let py = (pt.y - frame.y_min) / (frame.y_max - frame.y_min) * h as f64;
+        let (px, py) = (
+            px.floor().clamp(0.0, w as f64 - 1.0) as u32,
+            py.floor().clamp(0.0, h as f64 - 1.0) as u32,
+        );
+        1.0 - image.get_pixel(px, py).0[0] as f64 / 255.0
+    }
+}
+
+/// Plots |image|, stretched over |frame|, as |cells| hatched according to
+/// their darkness. Each tone is a layer of its own, and every cell in a layer
+/// is hatched by the same lines, so hatching runs unbroken from cell to cell.
+pub fn tonal_hatching(
+    image: &GrayImage,
+    frame: &Bounds,
+    cells: &[Polygon],
+    config: &TonalConfig,
+) -> Result<Canvas> {
+    let levels = config.levels.max(1);
+    let style = Style {
+        color: config.color,
+        thickness: config.thickness,
+        ..Default::default()
+    };
+
+    let mut layers: HashMap<Option<Bucket>, Vec<(Obj2, Style)>> = HashMap::new();
+    for cell in cells {
+        let tone = (darkness(image, frame, cell)? * levels as f64).round() as usize;
+        let shadings: Vec<(usize, ShadeConfig)> = match config.tone {
+            Tone::Density if tone > 0 => vec![(
+                tone,
+                ShadeConfig::builder()
+                    .gap(config.gap * levels as f64 / tone as f64)
+                    .angle(config.angle)
+                    .build(),
+            )],
+            Tone::Density => vec![],
+            Tone::CrossHatch => (1..=tone)
+                .map(|k| {
+                    (
+                        k,
+                        ShadeConfig::builder()
+                            .gap(config.gap)
+                            .angle(config.angle + (k - 1) as f64 * PI / levels as f64)
+                            .build(),
+                    )
+                })
+                .collect(),
+        };
+        for (k, shade_config) in shadings {
+            layers.entry(Some(Bucket::Tone(k))).or_default().extend(
+                shade_polygon(&shade_config, cell)?
+                    .into_iter()
+                    .map(|sg| (Obj2::Segment(sg), style)),
+            );
+        }
+    }
+
+    Ok(Canvas::builder()
+        .dos_by_bucket(layers)
+        .frame((Obj2::Polygon(frame.to_polygon()), Style::default()))
+        .build())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_eq::assert_float_eq;
+    use plotz_geometry::{grid::grid_layout::GridLayoutSettings, shapes::polygon::Rect};
+    use rand::{rngs::StdRng, SeedableRng};
+
+    // dark on the left, light on the right.
+    fn gradient() -> GrayImage {
+        GrayImage::from_fn(40, 10, |x, _| image::Luma([(x * 255 / 39) as u8]))
+    }
+
+    fn layout() -> GridLayout {
+        GridLayout::new(
+            GridLayoutSettings::builder()
+                .dims((400, 100))
+                .divisions((4, 1))
+                .object_margin((0, 0))
+                .build(),
+        )
+    }
+
+    #[test]
+    fn test_darkness() -> Result<()> {
+        let frame = Bounds {
+            x_min: 0.0,
+            x_max: 400.0,
+            y_min: 0.0,
+            y_max: 100.0,
+        };
+        let cells = grid_cells(&layout())?;
+        let tones: Vec<f64> = cells
+            .iter()
+            .map(|c| darkness(&gradient(), &frame, c))
+            .collect::<Result<_>>()?;
+        assert!(tones.windows(2).all(|w| w[0] > w[1]));
+        assert_float_eq!(tones[0], 0.88, abs <= 0.01);
+
+        // a cell smaller than a pixel still has a tone.
+        let tiny = Rect((1, 1), (0.5, 0.5))?;
+        assert_float_eq!(darkness(&gradient(), &frame, &tiny)?, 1.0, abs <= 1e-9);
+        Ok(())
+    }
+
+    #[test]
+    fn test_tonal_hatching_layers() -> Result<()> {
+        let frame = Bounds {
+            x_min: 0.0,
+            x_max: 400.0,
+            y_min: 0.0,
+            y_max: 100.0,
+        };
+        let cells = grid_cells(&layout())?;
+        for tone in [Tone::CrossHatch, Tone::Density] {
+            let config = TonalConfig::builder().tone(tone).gap(5.0).build();
+            let canvas = tonal_hatching(&gradient(), &frame, &cells, &config)?;
+            // the darkest cell rounds up to the fourth tone, the lightest down
+            // to nothing.
+            assert!(canvas.dos_by_bucket.contains_key(&Some(Bucket::Tone(4))));
+            for (_, dos) in canvas.dos_by_bucket.iter() {
+                for (obj, _) in dos {
+                    assert!(obj.bounds()?.x_max <= 300.0 + 1e-9);
+                }
+            }
+            assert!(canvas.frame.is_some());
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_image_density() {
+        let frame = Bounds {
+            x_min: 0.0,
+            x_max: 400.0,
+            y_min: 0.0,
+            y_max: 100.0,
+        };
+        let image = gradient();
+        let density = image_density(&image, &frame);
+        assert_float_eq!(density(&Point(1, 50)), 1.0, abs <= 1e-9);
+        assert_float_eq!(density(&Point(399, 50)), 0.0, abs <= 1e-9);
+        // points off of the image take the nearest edge.
+        assert_float_eq!(density(&Point(-10, 500)), 1.0, abs <= 1e-9);
+    }
+
+    #[test]
+    fn test_voronoi_cells_cover_frame() -> Result<()> {
+        let frame = Rect((0, 0), (100, 50))?;
+        let cells = voronoi_cells(&frame, 20, 3, &mut StdRng::seed_from_u64(1))?;
+        assert_float_eq!(
+            cells.iter().map(|c| c.area()).sum::<f64>(),
+            5000.0,
+            abs <= 1e-6
+        );
+        Ok(())
+    }
+}
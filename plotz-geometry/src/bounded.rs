@@ -26,6 +26,40 @@ impl Bounds {
         }
     }
 
+    /// True if these bounds and the other share any point, edges included.
+    pub fn intersects(&self, other: &Bounds) -> bool {
+        self.x_min <= other.x_max
+            && other.x_min <= self.x_max
+            && self.y_min <= other.y_max
+            && other.y_min <= self.y_max
+    }
+
+    /// These bounds, grown by |d| on every side.
+    pub fn padded(&self, d: f64) -> Bounds {
+        Bounds {
+            x_min: self.x_min - d,
+            x_max: self.x_max + d,
+            y_min: self.y_min - d,
+            y_max: self.y_max + d,
+        }
+    }
+
+    /// True if the other bounds lie entirely within these.
+    pub fn contains_bounds(&self, other: &Bounds) -> bool {
+        self.x_min <= other.x_min
+            && other.x_max <= self.x_max
+            && self.y_min <= other.y_min
+            && other.y_max <= self.y_max
+    }
+
+    /// The distance from a point to the nearest point within these bounds.
+    /// Zero if the point is within.
+    pub fn dist_to_point(&self, pt: &Point) -> f64 {
+        let dx = (self.x_min - pt.x).max(pt.x - self.x_max).max(0.0);
+        let dy = (self.y_min - pt.y).max(pt.y - self.y_max).max(0.0);
+        dx.hypot(dy)
+    }
+
     pub fn to_polygon(&self) -> Polygon {
         Polygon([
             self.x_min_y_max(),
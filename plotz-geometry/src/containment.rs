@@ -0,0 +1,364 @@
+//! Point-in-shape queries, by winding number, with an explicit tolerance.
+
+use crate::{
+    bounded::Bounds,
+    crop::PointLocation,
+    shapes::{
+        curve::CurveArc, point::Point, polygon::Polygon, polygon_with_cavity::PolygonWithCavities,
+        segment::Segment,
+    },
+    tolerance,
+};
+use anyhow::{anyhow, Result};
+use std::f64::consts::TAU;
+
+/// The default tolerance for containment queries: the absolute slack of the
+/// current |tolerance::Tolerance|.
+pub fn default_tolerance() -> f64 {
+    tolerance::current().abs
+}
+
+/// Anything which can say whether a point lies inside, outside, or along its
+/// border.
+///
+/// |tolerance| is the distance within which a point is considered to lie on a
+/// vertex or edge.
+pub trait Locate {
+    /// Locates a single point.
+    fn locate(&self, pt: &Point, tolerance: f64) -> Result<PointLocation>;
+
+    /// Locates many points at once. Prefer this over calling |locate()| in a
+    /// loop; implementors may build an index once up front.
+    fn locate_all(&self, pts: &[Point], tolerance: f64) -> Result<Vec<PointLocation>> {
+        pts.iter().map(|pt| self.locate(pt, tolerance)).collect()
+    }
+
+    /// True if the point lies inside or along the border.
+    fn contains(&self, pt: &Point, tolerance: f64) -> Result<bool> {
+        Ok(self.locate(pt, tolerance)? != PointLocation::Outside)
+    }
+
+    /// True if the point lies strictly inside (i.e. not on the border).
+    fn strictly_contains(&self, pt: &Point, tolerance: f64) -> Result<bool> {
+        Ok(self.locate(pt, tolerance)? == PointLocation::Inside)
+    }
+}
+
+/// A point locator over one outer ring and any number of holes, indexed into
+/// horizontal bands so that each query only visits the edges near it.
+///
+/// Vertices and edges are numbered across the outer ring first and then each
+/// hole in turn; |PointLocation::OnPoint(n)| and |PointLocation::OnSegment(n)|
+/// refer to that numbering. For a plain polygon, this is the same numbering as
+/// |Polygon::pts| and |Polygon::to_segments()|.
+pub struct Locator {
+    pts: Vec<Point>,
+    // (ring index, i, f) for each edge, as indices into |pts|.
+    edges: Vec<(usize, usize, usize)>,
+    num_rings: usize,
+    tolerance: f64,
+    y_min: f64,
+    y_max: f64,
+    band_height: f64,
+    bands: Vec<Vec<usize>>,
+}
+
+impl Locator {
+    /// Builds a locator from an outer ring and its holes. Rings need not be
+    /// closed (i.e. the last point should not repeat the first).
+    pub fn new<'a>(
+        rings: impl IntoIterator<Item = &'a [Point]>,
+        tolerance: f64,
+    ) -> Result<Locator> {
+        let mut pts = vec![];
+        let mut edges = vec![];
+        let mut num_rings = 0;
+        for ring in rings {
+            if ring.len() < 3 {
+                return Err(anyhow!("rings must have three or more points"));
+            }
+            let base = pts.len();
+            pts.extend_from_slice(ring);
+            for k in 0..ring.len() {
+                edges.push((num_rings, base + k, base + (k + 1) % ring.len()));
+            }
+            num_rings += 1;
+        }
+        if num_rings == 0 {
+            return Err(anyhow!("no rings"));
+        }
+
+        let y_min = pts.iter().map(|p| p.y).fold(f64::MAX, f64::min) - tolerance;
+        let y_max = pts.iter().map(|p| p.y).fold(f64::MIN, f64::max) + tolerance;
+        let num_bands = ((edges.len() as f64).sqrt().ceil() as usize).max(1);
+        let band_height = ((y_max - y_min) / num_bands as f64).max(f64::MIN_POSITIVE);
+        let mut bands = vec![vec![]; num_bands];
+        for (idx, (_, i, f)) in edges.iter().enumerate() {
+            let lo = pts[*i].y.min(pts[*f].y) - tolerance;
+            let hi = pts[*i].y.max(pts[*f].y) + tolerance;
+            let b_lo = (((lo - y_min) / band_height).floor().max(0.0) as usize).min(num_bands - 1);
+            let b_hi = (((hi - y_min) / band_height).floor().max(0.0) as usize).min(num_bands - 1);
+            for band in &mut bands[b_lo..=b_hi] {
+                band.push(idx);
+            }
+        }
+
+        Ok(Locator {
+            pts,
+            edges,
+            num_rings,
+            tolerance,
+            y_min,
+            y_max,
+            band_height,
+            bands,
+        })
+    }
+
+    /// Locates a point.
+    pub fn locate(&self, pt: &Point) -> PointLocation {
+        if pt.y < self.y_min || pt.y > self.y_max {
+            return PointLocation::Outside;
+        }
+        let band = &self.bands
+            [(((pt.y - self.y_min) / self.band_height) as usize).min(self.bands.len() - 1)];
+
+        // on a vertex?
+        if let Some(i) = band
+            .iter()
+            .flat_map(|e| [self.edges[*e].1, self.edges[*e].2])
+            .filter(|i| self.pts[*i].dist(pt) <= self.tolerance)
+            .min()
+        {
+            return PointLocation::OnPoint(i);
+        }
+
+        // on an edge?
+        if let Some(e) = band
+            .iter()
+            .copied()
+            .filter(|e| {
+                let (_, i, f) = self.edges[*e];
+                Segment(self.pts[i], self.pts[f]).dist_to_point(pt) <= self.tolerance
+            })
+            .min()
+        {
+            return PointLocation::OnSegment(e);
+        }
+
+        // otherwise, by winding number (Sunday's crossing rules), ring by ring.
+        let mut winding = vec![0_i64; self.num_rings];
+        for e in band {
+            let (ring, i, f) = self.edges[*e];
+            let (a, b) = (self.pts[i], self.pts[f]);
+            let is_left = (b - a).cross_z(&(*pt - a));
+            if a.y <= pt.y {
+                if b.y > pt.y && is_left > 0.0 {
+                    winding[ring] += 1;
+                }
+            } else if b.y <= pt.y && is_left < 0.0 {
+                winding[ring] -= 1;
+            }
+        }
+        if winding[0] != 0 && winding[1..].iter().all(|w| *w == 0) {
+            PointLocation::Inside
+        } else {
+            PointLocation::Outside
+        }
+    }
+
+    /// Locates many points.
+    pub fn locate_all(&self, pts: &[Point]) -> Vec<PointLocation> {
+        pts.iter().map(|pt| self.locate(pt)).collect()
+    }
+}
+
+/// Anything which can build a reusable |Locator| over itself. Prefer this to
+/// |Locate| when points arrive one at a time, e.g. from a sampler.
+pub trait ToLocator {
+    /// Builds a locator; see |Locator::new|.
+    fn to_locator(&self, tolerance: f64) -> Result<Locator>;
+}
+
+impl ToLocator for Polygon {
+    fn to_locator(&self, tolerance: f64) -> Result<Locator> {
+        self.locator(tolerance)
+    }
+}
+
+impl ToLocator for PolygonWithCavities {
+    fn to_locator(&self, tolerance: f64) -> Result<Locator> {
+        self.locator(tolerance)
+    }
+}
+
+impl ToLocator for Bounds {
+    /// Vertices and edges are numbered as in |Bounds::to_polygon()|.
+    fn to_locator(&self, tolerance: f64) -> Result<Locator> {
+        self.to_polygon().locator(tolerance)
+    }
+}
+
+impl Locate for Polygon {
+    fn locate(&self, pt: &Point, tolerance: f64) -> Result<PointLocation> {
+        Ok(self.locator(tolerance)?.locate(pt))
+    }
+
+    fn locate_all(&self, pts: &[Point], tolerance: f64) -> Result<Vec<PointLocation>> {
+        Ok(self.locator(tolerance)?.locate_all(pts))
+    }
+}
+
+impl Locate for PolygonWithCavities {
+    fn locate(&self, pt: &Point, tolerance: f64) -> Result<PointLocation> {
+        Ok(self.locator(tolerance)?.locate(pt))
+    }
+
+    fn locate_all(&self, pts: &[Point], tolerance: f64) -> Result<Vec<PointLocation>> {
+        Ok(self.locator(tolerance)?.locate_all(pts))
+    }
+}
+
+impl Locate for Bounds {
+    /// Vertices and edges are numbered as in |Bounds::to_polygon()|.
+    fn locate(&self, pt: &Point, tolerance: f64) -> Result<PointLocation> {
+        self.to_polygon().locate(pt, tolerance)
+    }
+
+    fn locate_all(&self, pts: &[Point], tolerance: f64) -> Result<Vec<PointLocation>> {
+        self.to_polygon().locate_all(pts, tolerance)
+    }
+}
+
+impl Locate for CurveArc {
+    /// Only closed curves (i.e. full circles) have an inside. A point on the
+    /// circle is |OnSegment(0)|.
+    fn locate(&self, pt: &Point, tolerance: f64) -> Result<PointLocation> {
+        if (self.angle_f - self.angle_i).abs() < TAU - f64::EPSILON {
+            return Err(anyhow!("curvearc is not closed; it has no inside."));
+        }
+        let d = pt.dist(&self.ctr);
+        Ok(if (d - self.radius).abs() <= tolerance {
+            PointLocation::OnSegment(0)
+        } else if d < self.radius {
+            PointLocation::Inside
+        } else {
+            PointLocation::Outside
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bounded::Bounded, shapes::polygon::Rect};
+    use test_case::test_case;
+
+    const TOL: f64 = 1e-9;
+
+    #[test_case(Point(1, 1), PointLocation::Inside; "inside")]
+    #[test_case(Point(3, 1), PointLocation::Outside; "outside")]
+    #[test_case(Point(0, 0), PointLocation::OnPoint(0); "on point 0")]
+    #[test_case(Point(2, 2), PointLocation::OnPoint(2); "on point 2")]
+    #[test_case(Point(1, 0), PointLocation::OnSegment(0); "on segment 0")]
+    #[test_case(Point(0, 1), PointLocation::OnSegment(3); "on segment 3")]
+    #[test_case(Point(1.0, 1e-12), PointLocation::OnSegment(0); "within tolerance")]
+    #[test_case(Point(-1, 0), PointLocation::Outside; "collinear with an edge")]
+    #[test_case(Point(3, 0), PointLocation::Outside; "collinear with a vertex")]
+    fn test_locate_square(pt: Point, expected: PointLocation) -> Result<()> {
+        let sq = Rect((0, 0), (2, 2))?;
+        assert_eq!(sq.locate(&pt, TOL)?, expected);
+        Ok(())
+    }
+
+    #[test]
+    fn test_locate_concave() -> Result<()> {
+        // a U-shape; the notch is outside.
+        let u = Polygon([
+            (0, 0),
+            (3, 0),
+            (3, 3),
+            (2, 3),
+            (2, 1),
+            (1, 1),
+            (1, 3),
+            (0, 3),
+        ])?;
+        assert_eq!(u.locate(&Point(1.5, 2), TOL)?, PointLocation::Outside);
+        assert_eq!(u.locate(&Point(0.5, 2), TOL)?, PointLocation::Inside);
+        assert_eq!(u.locate(&Point(2.5, 1), TOL)?, PointLocation::Inside);
+        assert_eq!(u.locate(&Point(1.5, 1), TOL)?, PointLocation::OnSegment(4));
+        Ok(())
+    }
+
+    #[test]
+    fn test_locate_with_cavities() -> Result<()> {
+        let pgc = PolygonWithCavities(Rect((0, 0), (10, 10))?, vec![Rect((2, 2), (2, 2))?])?;
+        assert_eq!(pgc.locate(&Point(1, 1), TOL)?, PointLocation::Inside);
+        assert_eq!(pgc.locate(&Point(3, 3), TOL)?, PointLocation::Outside);
+        assert_eq!(pgc.locate(&Point(11, 3), TOL)?, PointLocation::Outside);
+        // the hole's vertices and edges are numbered after the outer ring's.
+        assert_eq!(pgc.locate(&Point(2, 2), TOL)?, PointLocation::OnPoint(4));
+        assert_eq!(pgc.locate(&Point(3, 2), TOL)?, PointLocation::OnSegment(4));
+        Ok(())
+    }
+
+    #[test]
+    fn test_locate_bounds_and_circles() -> Result<()> {
+        let b = Rect((0, 0), (2, 2))?.bounds()?;
+        assert_eq!(b.locate(&Point(1, 1), TOL)?, PointLocation::Inside);
+        assert_eq!(b.locate(&Point(5, 1), TOL)?, PointLocation::Outside);
+
+        let circle = CurveArc(Point(0, 0), 0.0..=TAU, 1.0);
+        assert_eq!(circle.locate(&Point(0.5, 0), TOL)?, PointLocation::Inside);
+        assert_eq!(
+            circle.locate(&Point(0, 1), TOL)?,
+            PointLocation::OnSegment(0)
+        );
+        assert_eq!(circle.locate(&Point(1, 1), TOL)?, PointLocation::Outside);
+
+        let arc = CurveArc(Point(0, 0), 0.0..=1.0, 1.0);
+        assert!(arc.locate(&Point(0, 0), TOL).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_locate_all() -> Result<()> {
+        // a many-sided polygon exercises the banded index.
+        let n = 100;
+        let circle = Polygon((0..n).map(|k| {
+            let t = TAU * k as f64 / n as f64;
+            Point(t.cos(), t.sin())
+        }))?;
+        let pts: Vec<Point> = (0..50)
+            .flat_map(|i| {
+                (0..50).map(move |j| Point(i as f64 / 20.0 - 1.25, j as f64 / 20.0 - 1.25))
+            })
+            .collect();
+        let actual = circle.locate_all(&pts, TOL)?;
+        for (pt, loc) in pts.iter().zip(actual) {
+            let r = pt.dist(&Point(0, 0));
+            if r < 0.99 {
+                assert_eq!(loc, PointLocation::Inside);
+            } else if r > 1.01 {
+                assert_eq!(loc, PointLocation::Outside);
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_default_tolerance_follows_current() -> Result<()> {
+        use crate::tolerance::{with_tolerance, Tolerance};
+        let sq = Rect((0, 0), (2, 2))?;
+        let pt = Point(1.0, 1e-7);
+        assert_eq!(sq.locate(&pt, default_tolerance())?, PointLocation::Inside);
+        with_tolerance(Tolerance::for_scale(1000.0), || -> Result<()> {
+            assert_eq!(
+                sq.locate(&pt, default_tolerance())?,
+                PointLocation::OnSegment(0)
+            );
+            Ok(())
+        })
+    }
+}
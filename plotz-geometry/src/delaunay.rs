@@ -0,0 +1,140 @@
+//! Delaunay triangulation of a set of points.
+
+use crate::{
+    shapes::{point::Point, polygon::Polygon},
+    tolerance::{incircle, orient2d},
+};
+use anyhow::Result;
+use std::collections::HashMap;
+
+/// Triangulates a set of points such that no point lies within the
+/// circumcircle of any triangle, by Bowyer-Watson insertion. Returns triples
+/// of indices into |pts|, each counterclockwise.
+///
+/// Duplicate points are triangulated once, by their first index. If every
+/// point is collinear there are no triangles.
+pub fn delaunay(pts: &[Point]) -> Vec<[usize; 3]> {
+    let n = pts.len();
+    if n < 3 {
+        return vec![];
+    }
+
+    // a triangle enclosing every point, whose corners are the last three
+    // vertices. it is very large, so that triangles fanning out to it from
+    // the hull don't crowd out triangles along the hull.
+    let (x_min, x_max, y_min, y_max) = pts.iter().fold(
+        (f64::MAX, f64::MIN, f64::MAX, f64::MIN),
+        |(a, b, c, d), p| (a.min(p.x), b.max(p.x), c.min(p.y), d.max(p.y)),
+    );
+    let ctr = Point((x_min + x_max) / 2.0, (y_min + y_max) / 2.0);
+    let m = (x_max - x_min).max(y_max - y_min).max(1.0);
+    let mut vs: Vec<Point> = pts.to_vec();
+    vs.extend([
+        ctr + Point(-1e4 * m, -1e4 * m),
+        ctr + Point(1e4 * m, -1e4 * m),
+        ctr + Point(0.0, 1e4 * m),
+    ]);
+
+    let mut triangles: Vec<[usize; 3]> = vec![[n, n + 1, n + 2]];
+    let mut seen: HashMap<Point, usize> = HashMap::new();
+    for (idx, p) in pts.iter().enumerate() {
+        if seen.insert(*p, idx).is_some() {
+            continue;
+        }
+        let (bad, good): (Vec<[usize; 3]>, Vec<[usize; 3]>) = triangles
+            .into_iter()
+            .partition(|[a, b, c]| incircle(&vs[*a], &vs[*b], &vs[*c], p) > 0.0);
+        triangles = good;
+
+        // the edges of the cavity are those not shared by two bad triangles.
+        let edges: Vec<(usize, usize)> = bad
+            .iter()
+            .flat_map(|[a, b, c]| [(*a, *b), (*b, *c), (*c, *a)])
+            .collect();
+        for (a, b) in &edges {
+            if !edges.contains(&(*b, *a)) {
+                triangles.push([*a, *b, idx]);
+            }
+        }
+    }
+
+    triangles
+        .retain(|t| t.iter().all(|v| *v < n) && orient2d(&vs[t[0]], &vs[t[1]], &vs[t[2]]) > 0.0);
+    triangles
+}
+
+/// The Delaunay triangulation of a set of points, as polygons.
+pub fn delaunay_triangles(pts: &[Point]) -> Result<Vec<Polygon>> {
+    delaunay(pts)
+        .into_iter()
+        .map(|[a, b, c]| Polygon([pts[a], pts[b], pts[c]]))
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::hull::convex_hull;
+    use float_eq::assert_float_eq;
+
+    fn scattered(n: usize) -> Vec<Point> {
+        let mut seed: u64 = 42;
+        let mut next = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 11) as f64 / (1u64 << 53) as f64
+        };
+        (0..n)
+            .map(|_| Point(next() * 10.0, next() * 10.0))
+            .collect()
+    }
+
+    #[test]
+    fn test_square() -> Result<()> {
+        let pts = vec![
+            Point(0, 0),
+            Point(1, 0),
+            Point(1, 1),
+            Point(0, 1),
+            Point(0, 0),
+        ];
+        let triangles = delaunay_triangles(&pts)?;
+        assert_eq!(triangles.len(), 2);
+        assert_float_eq!(
+            triangles.iter().map(|t| t.area()).sum::<f64>(),
+            1.0,
+            abs <= 1e-9
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_collinear() {
+        assert!(delaunay(&[Point(0, 0), Point(1, 1), Point(2, 2)]).is_empty());
+    }
+
+    #[test]
+    fn test_empty_circumcircles() -> Result<()> {
+        let pts = scattered(200);
+        let triangles = delaunay(&pts);
+        // the triangles tile the convex hull.
+        assert_float_eq!(
+            delaunay_triangles(&pts)?
+                .iter()
+                .map(|t| t.area())
+                .sum::<f64>(),
+            convex_hull(&pts)?.area(),
+            abs <= 1e-6
+        );
+        for [a, b, c] in &triangles {
+            for (i, p) in pts.iter().enumerate() {
+                if [*a, *b, *c].contains(&i) {
+                    continue;
+                }
+                assert!(incircle(&pts[*a], &pts[*b], &pts[*c], p) <= 0.0);
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,451 @@
+//! Vector fields, and streamlines through them.
+
+use crate::{
+    bounded::{Bounded, Bounds},
+    containment::{default_tolerance, Locator},
+    crop::PointLocation,
+    shapes::{multiline::Multiline, point::Point, polygon::Polygon, segment::Segment},
+    utils::{splitmix64, PointGrid},
+};
+use anyhow::{anyhow, Result};
+use std::{collections::VecDeque, f64::consts::TAU};
+use typed_builder::TypedBuilder;
+
+/// A vector field: a vector at every point of the plane.
+pub trait Field {
+    /// The vector at |pt|.
+    fn sample(&self, pt: &Point) -> Point;
+}
+
+impl<F: Fn(&Point) -> Point> Field for F {
+    fn sample(&self, pt: &Point) -> Point {
+        self(pt)
+    }
+}
+
+/// The same vector everywhere.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Uniform(pub Point);
+
+impl Field for Uniform {
+    fn sample(&self, _pt: &Point) -> Point {
+        self.0
+    }
+}
+
+/// Unit vectors whose angle wanders smoothly: value noise over a lattice
+/// |scale| units across.
+#[derive(Debug, Copy, Clone, PartialEq, TypedBuilder)]
+pub struct Noise {
+    /// The distance between lattice points; larger is smoother.
+    pub scale: f64,
+    /// The length of every vector.
+    #[builder(default = 1.0)]
+    pub magnitude: f64,
+    /// Seeds the noise.
+    #[builder(default = 0)]
+    pub seed: u64,
+}
+
+impl Noise {
+    // A pseudorandom value in [0, 1) at lattice point (i, j).
+    fn lattice(&self, i: i64, j: i64) -> f64 {
+        let h = splitmix64(splitmix64(self.seed ^ i as u64) ^ j as u64);
+        (h >> 11) as f64 / (1u64 << 53) as f64
+    }
+
+    /// The noise at |pt|, in [0, 1).
+    pub fn value(&self, pt: &Point) -> f64 {
+        let (x, y) = (pt.x / self.scale, pt.y / self.scale);
+        let (i, j) = (x.floor() as i64, y.floor() as i64);
+        let smooth = |t: f64| t * t * (3.0 - 2.0 * t);
+        let (u, v) = (smooth(x - x.floor()), smooth(y - y.floor()));
+        let lerp = |a: f64, b: f64, t: f64| a + (b - a) * t;
+        lerp(
+            lerp(self.lattice(i, j), self.lattice(i + 1, j), u),
+            lerp(self.lattice(i, j + 1), self.lattice(i + 1, j + 1), u),
+            v,
+        )
+    }
+}
+
+impl Field for Noise {
+    fn sample(&self, pt: &Point) -> Point {
+        let angle = self.value(pt) * TAU;
+        Point(angle.cos(), angle.sin()) * self.magnitude
+    }
+}
+
+/// Point charges, each pulling (if positive) or pushing (if negative) with a
+/// strength of charge / distance^(pow - 1). A charge exerts no force on a
+/// point right atop it.
+#[derive(Debug, Clone, PartialEq)]
+pub struct PointCharges {
+    /// Each charge's position and magnitude.
+    pub charges: Vec<(Point, f64)>,
+    /// How quickly force falls off with distance; see above.
+    pub pow: f64,
+}
+
+impl Field for PointCharges {
+    fn sample(&self, pt: &Point) -> Point {
+        self.charges
+            .iter()
+            .filter(|(at, _)| at != pt)
+            .map(|(at, q)| (*at - *pt) * *q / at.dist(pt).powf(self.pow))
+            .fold(Point(0, 0), |acc, v| acc + v)
+    }
+}
+
+/// A vortex, swirling counterclockwise (if |strength| is positive) about
+/// |ctr|. Speed falls off as 1/distance away from a core of radius |core|,
+/// inside of which it turns like a solid body.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Vortex {
+    /// The center.
+    pub ctr: Point,
+    /// The strength; the speed at distance r outside the core is strength / r.
+    pub strength: f64,
+    /// The radius of the core.
+    pub core: f64,
+}
+
+impl Field for Vortex {
+    fn sample(&self, pt: &Point) -> Point {
+        let d = *pt - self.ctr;
+        Point(-d.y, d.x) * self.strength / (d.dot(&d) + self.core * self.core)
+    }
+}
+
+/// The sum of several fields.
+#[derive(Default)]
+pub struct Composite(pub Vec<Box<dyn Field + Send + Sync>>);
+
+impl Field for Composite {
+    fn sample(&self, pt: &Point) -> Point {
+        self.0
+            .iter()
+            .map(|f| f.sample(pt))
+            .fold(Point(0, 0), |acc, v| acc + v)
+    }
+}
+
+/// Samples |field| on a grid |spacing| apart over |bounds|, as arrows scaled
+/// by |scale|. Handy for seeing a field.
+pub fn arrows(field: &impl Field, bounds: &Bounds, spacing: f64, scale: f64) -> Vec<Segment> {
+    let mut arrows = vec![];
+    let mut y = bounds.y_min;
+    while y <= bounds.y_max {
+        let mut x = bounds.x_min;
+        while x <= bounds.x_max {
+            let pt = Point(x, y);
+            arrows.push(Segment(pt, pt + field.sample(&pt) * scale));
+            x += spacing;
+        }
+        y += spacing;
+    }
+    arrows
+}
+
+/// Moves a particle from |start| through |field| for |steps| steps, each of
+/// the field's vector times |dt|. Returns every position, starting with
+/// |start|.
+pub fn advect(field: &impl Field, start: Point, dt: f64, steps: usize) -> Vec<Point> {
+    let mut history = vec![start];
+    let mut pt = start;
+    for _ in 0..steps {
+        pt += field.sample(&pt) * dt;
+        history.push(pt);
+    }
+    history
+}
+
+/// Config for evenly spaced streamlines.
+#[derive(Debug, Copy, Clone, PartialEq, TypedBuilder)]
+pub struct StreamlineConfig {
+    /// The distance between neighboring streamlines.
+    pub separation: f64,
+    /// How close a streamline may come to another before it stops, as a
+    /// fraction of |separation|.
+    #[builder(default = 0.5)]
+    pub test_ratio: f64,
+    /// The step length along a streamline. A quarter of |separation| by
+    /// default.
+    #[builder(default, setter(strip_option))]
+    pub step: Option<f64>,
+    /// The most steps a streamline may take in each direction.
+    #[builder(default = 1000)]
+    pub max_steps: usize,
+    /// Streamlines with fewer points than this are dropped.
+    #[builder(default = 3)]
+    pub min_points: usize,
+}
+
+// Traces streamlines through a field, keeping them apart.
+struct Tracer<'a, F: Field> {
+    field: &'a F,
+    frame: Locator,
+    config: StreamlineConfig,
+    step: f64,
+    // every point of every streamline so far, and a grid over them.
+    pts: Vec<Point>,
+    grid: PointGrid,
+}
+
+impl<F: Field> Tracer<'_, F> {
+    fn inside(&self, pt: &Point) -> bool {
+        self.frame.locate(pt) != PointLocation::Outside
+    }
+
+    // The unit vector along the field at |pt|, if any.
+    fn dir(&self, pt: &Point) -> Option<Point> {
+        let v = self.field.sample(pt);
+        let len = v.dot(&v).sqrt();
+        (len > f64::EPSILON && len.is_finite()).then(|| v / len)
+    }
+
+    // True if |pt| is within |d| of any other streamline.
+    fn crowded(&self, pt: &Point, d: f64) -> bool {
+        let k = (d / self.config.separation).ceil() as usize;
+        self.grid.near(pt, k).any(|i| self.pts[i].dist(pt) < d)
+    }
+
+    // The point where the step from |a| (inside) to |b| (outside) leaves the
+    // frame, by bisection.
+    fn exit(&self, mut a: Point, mut b: Point) -> Point {
+        for _ in 0..32 {
+            let m = a.avg(&b);
+            if self.inside(&m) {
+                a = m;
+            } else {
+                b = m;
+            }
+        }
+        a
+    }
+
+    // Traces from |seed| one way (|sign| 1.0 along the field, -1.0 against).
+    fn trace_one_way(&self, seed: Point, sign: f64) -> Vec<Point> {
+        let d_test = self.config.separation * self.config.test_ratio;
+        // points of this streamline nearer than this (in steps) to its head
+        // don't count as crowding it.
+        let recent = (2.0 * self.config.separation / self.step).ceil() as usize;
+        let mut line = vec![seed];
+        let mut pt = seed;
+        for _ in 0..self.config.max_steps {
+            // a midpoint (RK2) step.
+            let Some(d1) = self.dir(&pt) else { break };
+            let mid = pt + d1 * (sign * self.step / 2.0);
+            let Some(d2) = self.dir(&mid) else { break };
+            let next = pt + d2 * (sign * self.step);
+
+            if !self.inside(&next) {
+                let exit = self.exit(pt, next);
+                if !self.crowded(&exit, d_test) {
+                    line.push(exit);
+                }
+                break;
+            }
+            if self.crowded(&next, d_test)
+                || line
+                    .iter()
+                    .rev()
+                    .skip(recent)
+                    .any(|p| p.dist(&next) < d_test)
+            {
+                break;
+            }
+            line.push(next);
+            pt = next;
+        }
+        line
+    }
+
+    fn trace(&self, seed: Point) -> Vec<Point> {
+        let mut back = self.trace_one_way(seed, -1.0);
+        back.reverse();
+        back.pop();
+        back.extend(self.trace_one_way(seed, 1.0));
+        back
+    }
+
+    fn accept(&mut self, line: &[Point]) {
+        for pt in line {
+            self.grid.insert(pt, self.pts.len());
+            self.pts.push(*pt);
+        }
+    }
+
+    fn seed_ok(&self, pt: &Point) -> bool {
+        self.inside(pt) && self.dir(pt).is_some() && !self.crowded(pt, self.config.separation)
+    }
+}
+
+/// Evenly spaced streamlines through |field| within |frame|, after Jobard and
+/// Lefer, "Creating Evenly-Spaced Streamlines of Arbitrary Density" (1997).
+///
+/// Each streamline is seeded one separation to the side of one already drawn,
+/// and traced both ways until it leaves the frame, meets the field's zeroes,
+/// or comes within |test_ratio| of a separation of another streamline (or of
+/// itself). Once no more fit beside the others, seeds are tried on a grid over
+/// the frame, to fill any regions the first streamlines didn't reach.
+pub fn streamlines(
+    field: &impl Field,
+    frame: &Polygon,
+    config: &StreamlineConfig,
+) -> Result<Vec<Multiline>> {
+    if config.separation <= 0.0 {
+        return Err(anyhow!("separation must be positive"));
+    }
+    let bounds = frame.bounds()?;
+    let mut tracer = Tracer {
+        field,
+        frame: frame.locator(default_tolerance())?,
+        config: *config,
+        step: config.step.unwrap_or(config.separation / 4.0),
+        pts: vec![],
+        grid: PointGrid::new(&bounds, config.separation),
+    };
+
+    // fallback seeds, on a grid over the frame.
+    let mut grid_seeds = vec![];
+    let mut y = bounds.y_min + config.separation / 2.0;
+    while y < bounds.y_max {
+        let mut x = bounds.x_min + config.separation / 2.0;
+        while x < bounds.x_max {
+            grid_seeds.push(Point(x, y));
+            x += config.separation;
+        }
+        y += config.separation;
+    }
+    grid_seeds.reverse();
+
+    let mut lines: Vec<Vec<Point>> = vec![];
+    let mut queue: VecDeque<usize> = VecDeque::new();
+    loop {
+        // seeds beside the oldest unfinished streamline, or else on the grid.
+        let seed = if let Some(&idx) = queue.front() {
+            let line = &lines[idx];
+            let candidate = line.iter().find_map(|pt| {
+                let d = tracer.dir(pt)?;
+                let n = Point(-d.y, d.x) * config.separation;
+                [*pt + n, *pt - n].into_iter().find(|s| tracer.seed_ok(s))
+            });
+            if candidate.is_none() {
+                queue.pop_front();
+                continue;
+            }
+            candidate
+        } else {
+            match grid_seeds.pop() {
+                Some(s) if tracer.seed_ok(&s) => Some(s),
+                Some(_) => continue,
+                None => break,
+            }
+        };
+        let Some(seed) = seed else { break };
+
+        let line = tracer.trace(seed);
+        if line.len() < config.min_points.max(2) {
+            // too short to draw, but mark the spot so it isn't seeded again.
+            tracer.accept(&[seed]);
+            continue;
+        }
+        tracer.accept(&line);
+        queue.push_back(lines.len());
+        lines.push(line);
+    }
+
+    Ok(lines
+        .into_iter()
+        .filter_map(|line| Multiline::try_from(line).ok())
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{containment::Locate, shapes::polygon::Rect};
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_fields() {
+        assert_eq!(Uniform(Point(1, 2)).sample(&Point(5, 5)), Point(1, 2));
+
+        let charges = PointCharges {
+            charges: vec![(Point(0, 0), 2.0)],
+            pow: 2.0,
+        };
+        // pulled toward a positive charge, at charge / distance.
+        assert_eq!(charges.sample(&Point(4, 0)), Point(-0.5, 0));
+        assert_eq!(charges.sample(&Point(0, 0)), Point(0, 0));
+
+        let vortex = Vortex {
+            ctr: Point(0, 0),
+            strength: 1.0,
+            core: 0.0,
+        };
+        let v = vortex.sample(&Point(2, 0));
+        assert_float_eq!(v.x, 0.0, abs <= 1e-12);
+        assert_float_eq!(v.y, 0.5, abs <= 1e-12);
+
+        let composite = Composite(vec![Box::new(Uniform(Point(1, 0))), Box::new(vortex)]);
+        assert_eq!(composite.sample(&Point(2, 0)), v + Point(1, 0));
+
+        let noise = Noise::builder().scale(10.0).seed(4).build();
+        let (a, b) = (noise.sample(&Point(3, 3)), noise.sample(&Point(3.01, 3)));
+        assert_float_eq!(a.dot(&a), 1.0, abs <= 1e-9);
+        assert!(a.dist(&b) < 0.01);
+    }
+
+    #[test]
+    fn test_advect() {
+        let pts = advect(&Uniform(Point(1, 0)), Point(0, 0), 0.5, 4);
+        assert_eq!(pts.len(), 5);
+        assert_eq!(pts[4], Point(2, 0));
+    }
+
+    #[test]
+    fn test_streamlines_of_uniform_field() -> Result<()> {
+        let frame = Rect((0, 0), (10, 10))?;
+        let config = StreamlineConfig::builder().separation(1.0).build();
+        let lines = streamlines(&Uniform(Point(1, 0)), &frame, &config)?;
+        // horizontal lines a separation apart, spanning the frame.
+        assert_eq!(lines.len(), 10);
+        for ml in &lines {
+            assert_float_eq!(ml.pts[0].x, 0.0, abs <= 1e-6);
+            assert_float_eq!(ml.pts[ml.pts.len() - 1].x, 10.0, abs <= 1e-6);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_streamlines_keep_apart() -> Result<()> {
+        let frame = Rect((0, 0), (20, 20))?;
+        let field = Composite(vec![
+            Box::new(Noise::builder().scale(8.0).seed(1).build()),
+            Box::new(Vortex {
+                ctr: Point(10, 10),
+                strength: 5.0,
+                core: 1.0,
+            }),
+        ]);
+        let config = StreamlineConfig::builder().separation(1.0).build();
+        let lines = streamlines(&field, &frame, &config)?;
+        assert!(lines.len() > 10);
+
+        let d_test = config.separation * config.test_ratio;
+        for (i, a) in lines.iter().enumerate() {
+            for pt in &a.pts {
+                assert!(frame.contains(pt, 1e-6)?);
+                for b in &lines[i + 1..] {
+                    for q in &b.pts {
+                        // seeds may sit right at d_test; allow for rounding.
+                        assert!(pt.dist(q) >= d_test * 0.99, "{:?} {:?}", pt, q);
+                    }
+                }
+            }
+        }
+        Ok(())
+    }
+}
@@ -0,0 +1,301 @@
+//! Conversions to and from |geo_types|, behind the |geo-types| feature.
+//!
+//! Orientation follows geo's convention on the way out: exterior rings run
+//! counterclockwise and holes run clockwise. On the way in, rings are
+//! reoriented however they arrive.
+
+use crate::{
+    group::Group,
+    obj2::Obj2,
+    shapes::{
+        multiline::Multiline, point::Point, polygon::Polygon,
+        polygon_with_cavity::PolygonWithCavities, segment::Segment,
+    },
+    style::Style,
+};
+use anyhow::{anyhow, Result};
+
+impl From<Point> for geo_types::Coord<f64> {
+    fn from(pt: Point) -> Self {
+        geo_types::coord! { x: pt.x, y: pt.y }
+    }
+}
+
+impl From<geo_types::Coord<f64>> for Point {
+    fn from(c: geo_types::Coord<f64>) -> Self {
+        Point(c.x, c.y)
+    }
+}
+
+impl From<Point> for geo_types::Point<f64> {
+    fn from(pt: Point) -> Self {
+        geo_types::Point::new(pt.x, pt.y)
+    }
+}
+
+impl From<geo_types::Point<f64>> for Point {
+    fn from(p: geo_types::Point<f64>) -> Self {
+        Point(p.x(), p.y())
+    }
+}
+
+impl From<Segment> for geo_types::Line<f64> {
+    fn from(sg: Segment) -> Self {
+        geo_types::Line::new(sg.i, sg.f)
+    }
+}
+
+impl From<geo_types::Line<f64>> for Segment {
+    fn from(l: geo_types::Line<f64>) -> Self {
+        Segment(l.start, l.end)
+    }
+}
+
+impl From<Multiline> for geo_types::LineString<f64> {
+    fn from(ml: Multiline) -> Self {
+        ml.pts.into_iter().collect()
+    }
+}
+
+impl TryFrom<geo_types::LineString<f64>> for Multiline {
+    type Error = anyhow::Error;
+    /// Fails if the linestring is closed; see |Polygon| instead.
+    fn try_from(ls: geo_types::LineString<f64>) -> Result<Self> {
+        ls.0.into_iter()
+            .map(Point::from)
+            .collect::<Vec<_>>()
+            .try_into()
+    }
+}
+
+// A ring of points, closed, and running clockwise or counterclockwise.
+fn ring(pg: &Polygon, ccw: bool) -> geo_types::LineString<f64> {
+    let mut pts = pg.pts.clone();
+    if (pg.signed_area() > 0.0) != ccw {
+        pts.reverse();
+    }
+    pts.push(pts[0]);
+    pts.into_iter().collect()
+}
+
+// A ring of points, open, as a polygon.
+fn unring(ls: &geo_types::LineString<f64>) -> Result<Polygon> {
+    Polygon(ls.coords().map(|c| Point::from(*c)))
+}
+
+impl From<Polygon> for geo_types::Polygon<f64> {
+    fn from(pg: Polygon) -> Self {
+        geo_types::Polygon::new(ring(&pg, true), vec![])
+    }
+}
+
+impl TryFrom<geo_types::Polygon<f64>> for Polygon {
+    type Error = anyhow::Error;
+    /// Fails if the polygon has holes; see |PolygonWithCavities| instead.
+    fn try_from(pg: geo_types::Polygon<f64>) -> Result<Self> {
+        if !pg.interiors().is_empty() {
+            return Err(anyhow!("polygon has holes; try PolygonWithCavities."));
+        }
+        unring(pg.exterior())
+    }
+}
+
+impl From<PolygonWithCavities> for geo_types::Polygon<f64> {
+    fn from(pgc: PolygonWithCavities) -> Self {
+        geo_types::Polygon::new(
+            ring(&pgc.outer, true),
+            pgc.inner.iter().map(|i| ring(i, false)).collect(),
+        )
+    }
+}
+
+impl TryFrom<geo_types::Polygon<f64>> for PolygonWithCavities {
+    type Error = anyhow::Error;
+    fn try_from(pg: geo_types::Polygon<f64>) -> Result<Self> {
+        PolygonWithCavities(
+            unring(pg.exterior())?,
+            pg.interiors()
+                .iter()
+                .map(unring)
+                .collect::<Result<Vec<_>>>()?,
+        )
+    }
+}
+
+/// Collects polygons with cavities into one multipolygon.
+pub fn to_multipolygon(
+    pgcs: impl IntoIterator<Item = PolygonWithCavities>,
+) -> geo_types::MultiPolygon<f64> {
+    pgcs.into_iter()
+        .map(geo_types::Polygon::from)
+        .collect::<Vec<_>>()
+        .into()
+}
+
+/// Splits a multipolygon into polygons with cavities.
+pub fn from_multipolygon(mp: geo_types::MultiPolygon<f64>) -> Result<Vec<PolygonWithCavities>> {
+    mp.into_iter().map(PolygonWithCavities::try_from).collect()
+}
+
+impl TryFrom<Obj2> for geo_types::Geometry<f64> {
+    type Error = anyhow::Error;
+    /// Groups become geometry collections (and lose their styles). Arcs and
+    /// text have no equivalent.
+    fn try_from(obj: Obj2) -> Result<Self> {
+        Ok(match obj {
+            Obj2::Point(p) => geo_types::Point::from(p).into(),
+            Obj2::Segment(sg) => geo_types::Line::from(sg).into(),
+            Obj2::Multiline(ml) => geo_types::LineString::from(ml).into(),
+            Obj2::Polygon(pg) => geo_types::Polygon::from(pg).into(),
+            Obj2::PolygonWithCavities(pgc) => geo_types::Polygon::from(pgc).into(),
+            Obj2::Group(g) => {
+                geo_types::Geometry::GeometryCollection(geo_types::GeometryCollection::new_from(
+                    g.iter_objects()
+                        .map(|(o, _)| o.clone().try_into())
+                        .collect::<Result<Vec<_>>>()?,
+                ))
+            }
+            Obj2::CurveArc(_) | Obj2::Text(_) => {
+                return Err(anyhow!("no geo_types equivalent for arcs or text."))
+            }
+        })
+    }
+}
+
+// A group of objects, each with the default style.
+fn group(objs: impl IntoIterator<Item = Result<Obj2>>) -> Result<Obj2> {
+    Ok(Obj2::Group(Group::new(
+        objs.into_iter()
+            .map(|o| Ok((o?, Style::default())))
+            .collect::<Result<Vec<_>>>()?,
+    )))
+}
+
+impl TryFrom<geo_types::Geometry<f64>> for Obj2 {
+    type Error = anyhow::Error;
+    /// Closed linestrings become polygons, polygons with holes become
+    /// polygons with cavities, and every multi-geometry becomes a group.
+    fn try_from(g: geo_types::Geometry<f64>) -> Result<Self> {
+        use geo_types::Geometry as G;
+        Ok(match g {
+            G::Point(p) => Obj2::Point(p.into()),
+            G::Line(l) => Obj2::Segment(l.into()),
+            G::LineString(ls) if ls.is_closed() && ls.0.len() > 3 => Obj2::Polygon(unring(&ls)?),
+            G::LineString(ls) => Obj2::Multiline(ls.try_into()?),
+            G::Polygon(pg) if pg.interiors().is_empty() => Obj2::Polygon(pg.try_into()?),
+            G::Polygon(pg) => Obj2::PolygonWithCavities(pg.try_into()?),
+            G::Rect(r) => Obj2::Polygon(r.to_polygon().try_into()?),
+            G::Triangle(t) => Obj2::Polygon(t.to_polygon().try_into()?),
+            G::MultiPoint(mp) => group(mp.into_iter().map(|p| Ok(Obj2::Point(p.into()))))?,
+            G::MultiLineString(mls) => {
+                group(mls.into_iter().map(|ls| G::LineString(ls).try_into()))?
+            }
+            G::MultiPolygon(mp) => group(mp.into_iter().map(|pg| G::Polygon(pg).try_into()))?,
+            G::GeometryCollection(gc) => group(gc.into_iter().map(Obj2::try_from))?,
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::polygon::Rect;
+    use assert_matches::assert_matches;
+
+    // twice the signed area of a closed ring.
+    fn orientation(ls: &geo_types::LineString<f64>) -> f64 {
+        ls.lines()
+            .map(|l| l.start.x * l.end.y - l.end.x * l.start.y)
+            .sum()
+    }
+
+    #[test]
+    fn test_point_segment_multiline_round_trip() -> Result<()> {
+        let p = Point(1.5, -2);
+        assert_eq!(Point::from(geo_types::Coord::from(p)), p);
+        assert_eq!(Point::from(geo_types::Point::from(p)), p);
+
+        let sg = Segment((0, 0), (3, 4));
+        assert_eq!(Segment::from(geo_types::Line::from(sg)), sg);
+
+        let ml = Multiline([(0, 0), (1, 1), (2, 0)]);
+        assert_eq!(
+            Multiline::try_from(geo_types::LineString::from(ml.clone()))?,
+            ml
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_polygon_round_trip_and_orientation() -> Result<()> {
+        let pg = Polygon([(0, 0), (2, 0), (2, 2), (0, 2)])?;
+        let g = geo_types::Polygon::from(pg.clone());
+        assert!(g.exterior().is_closed());
+        assert!(orientation(g.exterior()) > 0.0);
+        assert_eq!(Polygon::try_from(g)?, pg);
+
+        // a clockwise exterior comes back counterclockwise.
+        let cw = geo_types::Polygon::new(
+            vec![(0.0, 0.0), (0.0, 2.0), (2.0, 2.0), (2.0, 0.0), (0.0, 0.0)].into(),
+            vec![],
+        );
+        assert!(Polygon::try_from(cw)?.signed_area() > 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_polygon_with_cavities_round_trip() -> Result<()> {
+        let pgc = PolygonWithCavities(
+            Rect((0, 0), (10, 10))?,
+            vec![Rect((1, 1), (2, 2))?, Rect((5, 5), (3, 3))?],
+        )?;
+        let g = geo_types::Polygon::from(pgc.clone());
+        assert!(orientation(g.exterior()) > 0.0);
+        assert_eq!(g.interiors().len(), 2);
+        for hole in g.interiors() {
+            assert!(orientation(hole) < 0.0);
+        }
+
+        // a polygon with holes isn't a plain polygon.
+        assert!(Polygon::try_from(g.clone()).is_err());
+
+        let back = PolygonWithCavities::try_from(g)?;
+        assert_eq!(back.outer, pgc.outer);
+        assert_eq!(back.inner, pgc.inner);
+
+        let mp = to_multipolygon([pgc.clone(), pgc.clone() + (20, 0)]);
+        let pgcs = from_multipolygon(mp)?;
+        assert_eq!(pgcs.len(), 2);
+        assert_eq!(pgcs[1].outer, Rect((20, 0), (10, 10))?);
+        assert_eq!(pgcs[1].inner[0], Rect((21, 1), (2, 2))?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_obj2_round_trip() -> Result<()> {
+        let objs = vec![
+            Obj2::Point(Point(1, 2)),
+            Obj2::Segment(Segment((0, 0), (1, 0))),
+            Obj2::Multiline(Multiline([(0, 0), (1, 1), (2, 0)])),
+            Obj2::Polygon(Rect((0, 0), (1, 1))?),
+        ];
+        for obj in objs {
+            let g = geo_types::Geometry::try_from(obj.clone())?;
+            assert_eq!(Obj2::try_from(g)?, obj);
+        }
+
+        // closed linestrings are polygons; collections are groups.
+        let closed: geo_types::LineString<f64> =
+            vec![(0.0, 0.0), (1.0, 0.0), (1.0, 1.0), (0.0, 0.0)].into();
+        assert_matches!(
+            Obj2::try_from(geo_types::Geometry::LineString(closed))?,
+            Obj2::Polygon(_)
+        );
+        let mp = geo_types::MultiPoint::from(vec![(0.0, 0.0), (1.0, 1.0)]);
+        assert_matches!(
+            Obj2::try_from(geo_types::Geometry::MultiPoint(mp))?,
+            Obj2::Group(_)
+        );
+        Ok(())
+    }
+}
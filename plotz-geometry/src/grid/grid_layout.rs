@@ -2,15 +2,44 @@
 
 use crate::{
     bounded::{Bounded, Bounds},
+    containment::{default_tolerance, Locate},
     crop::Croppable,
     obj2::Obj2,
-    shapes::point::Point,
+    shapes::{
+        point::Point,
+        polygon::{Polygon, Rect},
+    },
     style::Style,
 };
 use anyhow::Result;
 use float_ord::FloatOrd;
+use std::f64::consts::TAU;
 use typed_builder::TypedBuilder;
 
+/// The shape of the cubbies in a grid layout.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Default)]
+pub enum CellShape {
+    /// Aligned boxes, |divisions.0| across and |divisions.1| down.
+    #[default]
+    Rect,
+    /// Pointy-topped hexagons, |divisions.0| across and |divisions.1| down,
+    /// with every other row shifted right by half a cell. Hexagons are
+    /// stretched to fill |dims|.
+    Hex,
+    /// Triangles, alternately pointing up and down, |divisions.0| across and
+    /// |divisions.1| down.
+    Triangle,
+    /// Sectors of a disc inscribed in |dims|: |divisions.0| around and
+    /// |divisions.1| rings out from the center. The x margin is measured
+    /// around each ring, and the y margin along each radius.
+    Polar,
+}
+
+// The mean of some corners.
+fn centroid(pts: &[Point]) -> Point {
+    pts.iter().fold(Point(0, 0), |acc, pt| acc + *pt) / pts.len().max(1) as f64
+}
+
 #[derive(Debug, TypedBuilder, Copy, Clone)]
 /// Settings struct.
 pub struct GridLayoutSettings {
@@ -23,10 +52,13 @@ pub struct GridLayoutSettings {
     divisions: (usize, usize),
     /// the (x,y) margin around each object.
     object_margin: (u64, u64),
+    /// the shape of each cubby.
+    #[builder(default)]
+    shape: CellShape,
 }
 
 #[derive(Debug)]
-/// A grid layout of aligned boxes.
+/// A grid layout of cubbies; aligned boxes, unless otherwise specified.
 pub struct GridLayout {
     /// the settings. See above.
     settings: GridLayoutSettings,
@@ -69,6 +101,22 @@ impl GridLayout {
 
     /// Get the bounds of a cubby at (i,j).
     pub fn get_cubby_bounds(&self, (i, j): (usize, usize)) -> Bounds {
+        if self.settings.shape != CellShape::Rect {
+            return self.cubby_pts((i, j)).iter().fold(
+                Bounds {
+                    x_min: f64::MAX,
+                    x_max: f64::MIN,
+                    y_min: f64::MAX,
+                    y_max: f64::MIN,
+                },
+                |b, pt| Bounds {
+                    x_min: b.x_min.min(pt.x),
+                    x_max: b.x_max.max(pt.x),
+                    y_min: b.y_min.min(pt.y),
+                    y_max: b.y_max.max(pt.y),
+                },
+            );
+        }
         let (x_divisions, y_divisions) = self.settings.divisions;
         let (total_width, total_height) = self.settings.dims;
         let cubby_width: f64 = (total_width / (x_divisions as u64)) as f64;
@@ -84,9 +132,125 @@ impl GridLayout {
         }
     }
 
+    /// Get the shape of a cubby at (i,j).
+    pub fn get_cubby_polygon(&self, (i, j): (usize, usize)) -> Result<Polygon> {
+        match self.settings.shape {
+            CellShape::Rect => {
+                let b = self.get_cubby_bounds((i, j));
+                Rect((b.x_min, b.y_min), (b.x_span(), b.y_max - b.y_min))
+            }
+            _ => Polygon(self.cubby_pts((i, j))),
+        }
+    }
+
+    /// Get the shapes of every cubby, column by column: (0, 0), (0, 1), ...,
+    /// (1, 0), (1, 1), and so on.
+    pub fn get_cubby_polygons(&self) -> Result<Vec<Polygon>> {
+        (0..self.num_cubbys_x())
+            .flat_map(|i| (0..self.num_cubbys_y()).map(move |j| self.get_cubby_polygon((i, j))))
+            .collect()
+    }
+
+    // The corners of a (non-rectangular) cubby, margins and all.
+    fn cubby_pts(&self, (i, j): (usize, usize)) -> Vec<Point> {
+        let (nx, ny) = (
+            self.settings.divisions.0 as f64,
+            self.settings.divisions.1 as f64,
+        );
+        let (w, h) = (self.settings.dims.0 as f64, self.settings.dims.1 as f64);
+        let (mx, my) = (
+            self.settings.object_margin.0 as f64,
+            self.settings.object_margin.1 as f64,
+        );
+        let init = Point(self.settings.init.0 as f64, self.settings.init.1 as f64);
+        let (fi, fj) = (i as f64, j as f64);
+
+        let pts: Vec<Point> = match self.settings.shape {
+            CellShape::Rect => return self.get_cubby_bounds((i, j)).to_polygon().pts,
+            CellShape::Hex => {
+                let (hw, hh) = (w / (nx + 0.5), h / (0.75 * (ny - 1.0) + 1.0));
+                let shift = if j % 2 == 1 { hw / 2.0 } else { 0.0 };
+                let c = init + Point(hw / 2.0 + fi * hw + shift, hh / 2.0 + fj * 0.75 * hh);
+                vec![
+                    c + Point(0.0, -hh / 2.0),
+                    c + Point(hw / 2.0, -hh / 4.0),
+                    c + Point(hw / 2.0, hh / 4.0),
+                    c + Point(0.0, hh / 2.0),
+                    c + Point(-hw / 2.0, hh / 4.0),
+                    c + Point(-hw / 2.0, -hh / 4.0),
+                ]
+            }
+            CellShape::Triangle => {
+                let (tw, th) = (2.0 * w / (nx + 1.0), h / ny);
+                let tl = init + Point(fi * tw / 2.0, fj * th);
+                if (i + j) % 2 == 0 {
+                    vec![
+                        tl + Point(0.0, th),
+                        tl + Point(tw / 2.0, 0.0),
+                        tl + Point(tw, th),
+                    ]
+                } else {
+                    vec![tl, tl + Point(tw, 0.0), tl + Point(tw / 2.0, th)]
+                }
+            }
+            CellShape::Polar => {
+                let ctr = init + Point(w / 2.0, h / 2.0);
+                let dr = w.min(h) / 2.0 / ny;
+                let (r0, r1) = ((fj * dr + my).min(fj * dr + dr / 2.0), (fj + 1.0) * dr - my);
+                let dt = TAU / nx;
+                let arc = |r: f64| -> Vec<Point> {
+                    // the margin is an arc length, so it subtends more of a
+                    // smaller ring.
+                    let inset = (mx / r).min(dt / 2.0);
+                    let (t0, t1) = (fi * dt + inset, (fi + 1.0) * dt - inset);
+                    let n = ((t1 - t0) / (TAU / 64.0)).ceil().max(1.0) as usize;
+                    (0..=n)
+                        .map(|k| {
+                            let t = t0 + (t1 - t0) * k as f64 / n as f64;
+                            ctr + Point(t.cos(), t.sin()) * r
+                        })
+                        .collect()
+                };
+                let mut pts = arc(r1);
+                if r0 > 0.0 {
+                    pts.extend(arc(r0).into_iter().rev());
+                } else {
+                    pts.push(ctr);
+                }
+                return pts;
+            }
+        };
+
+        // pull each corner in toward the center of the cell by the margin,
+        // as the corners of a box are.
+        let c = centroid(&pts);
+        pts.into_iter()
+            .map(|pt| {
+                Point(
+                    pt.x + (c.x - pt.x).clamp(-mx, mx),
+                    pt.y + (c.y - pt.y).clamp(-my, my),
+                )
+            })
+            .collect()
+    }
+
     /// Returns the center of the cubby.
     pub fn cubby_ctr(&self, (i, j): (usize, usize)) -> Point {
-        self.get_cubby_bounds((i, j)).center()
+        match self.settings.shape {
+            CellShape::Rect | CellShape::Hex => self.get_cubby_bounds((i, j)).center(),
+            CellShape::Triangle => centroid(&self.cubby_pts((i, j))),
+            CellShape::Polar => {
+                let (nx, ny) = (
+                    self.settings.divisions.0 as f64,
+                    self.settings.divisions.1 as f64,
+                );
+                let (w, h) = (self.settings.dims.0 as f64, self.settings.dims.1 as f64);
+                let init = Point(self.settings.init.0 as f64, self.settings.init.1 as f64);
+                let r = w.min(h) / 2.0 / ny * (j as f64 + 0.5);
+                let t = TAU / nx * (i as f64 + 0.5);
+                init + Point(w / 2.0, h / 2.0) + Point(t.cos(), t.sin()) * r
+            }
+        }
     }
 
     /// Returns a list of all inner objects.
@@ -106,7 +270,7 @@ impl GridLayout {
         (i, j): (usize, usize),
         (obj, style): (Obj2, Style),
     ) -> Result<()> {
-        let cropped = obj.crop_to_bounds(self.get_cubby_bounds((i, j)))?;
+        let cropped = obj.crop_to(&self.get_cubby_polygon((i, j))?)?;
 
         self.objs[i][j].extend(cropped.into_iter().map(|o| (o, style)));
         Ok(())
@@ -120,22 +284,58 @@ impl GridLayout {
         buffer: f64,
     ) -> Result<()> {
         let mut obj = obj;
+        let cell = self.get_cubby_polygon((i, j))?;
+        let ctr = self.cubby_ctr((i, j));
         {
             let frame_bounds = self.get_cubby_bounds((i, j));
             let inner_bounds = obj.bounds()?;
 
             let w_scale = frame_bounds.x_span() / inner_bounds.x_span();
             let s_scale = frame_bounds.y_span() / inner_bounds.y_span();
-            let scale = std::cmp::min(FloatOrd(w_scale), FloatOrd(s_scale)).0 * buffer;
+            let mut scale = std::cmp::min(FloatOrd(w_scale), FloatOrd(s_scale)).0;
 
-            obj *= scale;
+            // a box fits its bounds, but other cells are smaller than theirs;
+            // shrink until the object's bounds, centered in the cell, fit.
+            if self.settings.shape != CellShape::Rect {
+                let (w, h) = (
+                    inner_bounds.x_span(),
+                    inner_bounds.y_max - inner_bounds.y_min,
+                );
+                let fits = |s: f64| -> Result<bool> {
+                    let b = Rect(ctr - Point(w, h) * (s / 2.0), (w * s, h * s))?;
+                    for pt in &b.pts {
+                        if !cell.contains(pt, default_tolerance())? {
+                            return Ok(false);
+                        }
+                    }
+                    for pt in &cell.pts {
+                        if b.strictly_contains(pt, default_tolerance())? {
+                            return Ok(false);
+                        }
+                    }
+                    Ok(true)
+                };
+                let (mut lo, mut hi) = (0.0, scale);
+                if !fits(hi)? {
+                    for _ in 0..50 {
+                        let mid = (lo + hi) / 2.0;
+                        if fits(mid)? {
+                            lo = mid;
+                        } else {
+                            hi = mid;
+                        }
+                    }
+                    scale = lo;
+                }
+            }
+
+            obj *= scale * buffer;
         }
 
         {
-            let frame_bounds = self.get_cubby_bounds((i, j));
             let inner_bounds = obj.bounds()?;
 
-            let translate_diff = frame_bounds.center() - inner_bounds.center();
+            let translate_diff = ctr - inner_bounds.center();
 
             obj += translate_diff;
         }
@@ -144,3 +344,137 @@ impl GridLayout {
         Ok(())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_eq::assert_float_eq;
+    use std::f64::consts::PI;
+    use test_case::test_case;
+
+    fn layout(shape: CellShape, margin: u64) -> GridLayout {
+        GridLayout::new(
+            GridLayoutSettings::builder()
+                .init((10, 20))
+                .dims((400, 300))
+                .divisions((6, 4))
+                .object_margin((margin, margin))
+                .shape(shape)
+                .build(),
+        )
+    }
+
+    fn cubbys(gl: &GridLayout) -> Vec<(usize, usize)> {
+        (0..gl.num_cubbys_x())
+            .flat_map(|i| (0..gl.num_cubbys_y()).map(move |j| (i, j)))
+            .collect()
+    }
+
+    #[test_case(CellShape::Rect; "rect")]
+    #[test_case(CellShape::Hex; "hex")]
+    #[test_case(CellShape::Triangle; "triangle")]
+    #[test_case(CellShape::Polar; "polar")]
+    fn test_cells_tile(shape: CellShape) -> Result<()> {
+        let gl = layout(shape, 0);
+        let cells = gl.get_cubby_polygons()?;
+        assert_eq!(cells.len(), 24);
+        // each center lies in its own cell, and in no other.
+        for (k, ij) in cubbys(&gl).into_iter().enumerate() {
+            let ctr = gl.cubby_ctr(ij);
+            for (l, cell) in cells.iter().enumerate() {
+                assert_eq!(cell.strictly_contains(&ctr, default_tolerance())?, k == l);
+            }
+            let b = gl.get_cubby_bounds(ij);
+            assert!(b.x_min >= 10.0 - 1e-9 && b.x_max <= 410.0 + 1e-9);
+            assert!(b.y_min >= 20.0 - 1e-9 && b.y_max <= 320.0 + 1e-9);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_cell_areas() -> Result<()> {
+        let area = |shape| -> Result<f64> {
+            Ok(layout(shape, 0)
+                .get_cubby_polygons()?
+                .iter()
+                .map(|c| c.area())
+                .sum())
+        };
+        // boxes are a whole number of units wide.
+        assert_float_eq!(area(CellShape::Rect)?, 396.0 * 300.0, abs <= 1e-6);
+        // two cells' worth of triangles are lost to the ragged ends of a row.
+        assert_float_eq!(
+            area(CellShape::Triangle)?,
+            400.0 * 300.0 * 6.0 / 7.0,
+            abs <= 1e-6
+        );
+        assert_float_eq!(area(CellShape::Polar)?, PI * 150.0 * 150.0, r2nd <= 0.01);
+        Ok(())
+    }
+
+    #[test]
+    fn test_margins_shrink_cells() -> Result<()> {
+        for shape in [CellShape::Hex, CellShape::Triangle, CellShape::Polar] {
+            let (full, inset) = (layout(shape, 0), layout(shape, 5));
+            for ij in cubbys(&full) {
+                let outer = full.get_cubby_polygon(ij)?;
+                let inner = inset.get_cubby_polygon(ij)?;
+                assert!(inner.area() < outer.area());
+                for pt in &inner.pts {
+                    assert!(outer.strictly_contains(pt, default_tolerance())?);
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test_case(CellShape::Rect; "rect")]
+    #[test_case(CellShape::Hex; "hex")]
+    #[test_case(CellShape::Triangle; "triangle")]
+    #[test_case(CellShape::Polar; "polar")]
+    fn test_insert_and_crop_to_cubby(shape: CellShape) -> Result<()> {
+        let mut gl = layout(shape, 2);
+        let big = Rect((0, 0), (500, 500))?;
+        for ij in cubbys(&gl) {
+            gl.insert_and_crop_to_cubby(ij, (Obj2::Polygon(big.clone()), Style::default()))?;
+            let cell = gl.get_cubby_polygon(ij)?;
+            let area: f64 = gl.objs[ij.0][ij.1]
+                .iter()
+                .map(|(o, _)| match o {
+                    Obj2::Polygon(pg) => pg.area(),
+                    _ => 0.0,
+                })
+                .sum();
+            assert_float_eq!(area, cell.area(), r2nd <= 1e-6);
+        }
+        Ok(())
+    }
+
+    #[test_case(CellShape::Rect; "rect")]
+    #[test_case(CellShape::Hex; "hex")]
+    #[test_case(CellShape::Triangle; "triangle")]
+    #[test_case(CellShape::Polar; "polar")]
+    fn test_insert_and_rescale_to_cubby(shape: CellShape) -> Result<()> {
+        let mut gl = layout(shape, 0);
+        for ij in cubbys(&gl) {
+            gl.insert_and_rescale_to_cubby(
+                ij,
+                (Obj2::Polygon(Rect((0, 0), (3, 1))?), Style::default()),
+                0.9,
+            )?;
+            let cell = gl.get_cubby_polygon(ij)?;
+            let Obj2::Polygon(pg) = &gl.objs[ij.0][ij.1][0].0 else {
+                panic!("not a polygon");
+            };
+            for pt in &pg.pts {
+                assert!(cell.strictly_contains(pt, default_tolerance())?);
+            }
+            let b = pg.bounds()?;
+            assert_float_eq!(b.x_span(), 3.0 * (b.y_max - b.y_min), r2nd <= 1e-9);
+            let ctr = gl.cubby_ctr(ij);
+            assert_float_eq!(b.center().x, ctr.x, abs <= 1e-9);
+            assert_float_eq!(b.center().y, ctr.y, abs <= 1e-9);
+        }
+        Ok(())
+    }
+}
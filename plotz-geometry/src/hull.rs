@@ -0,0 +1,124 @@
+//! Convex hulls and minimum-area bounding rectangles.
+
+use crate::shapes::{point::Point, polygon::Polygon};
+use anyhow::{anyhow, Result};
+
+/// Returns the convex hull of a set of points, computed with Andrew's monotone
+/// chain. Collinear points along the hull are dropped. Returns an error if the
+/// points are all collinear (or there are fewer than three of them).
+pub fn convex_hull<'a>(pts: impl IntoIterator<Item = &'a Point>) -> Result<Polygon> {
+    let mut pts: Vec<Point> = pts.into_iter().copied().collect();
+    pts.sort();
+    pts.dedup();
+
+    // builds one half of the hull, keeping only strict left turns.
+    fn half<'a>(pts: impl Iterator<Item = &'a Point>) -> Vec<Point> {
+        let mut h: Vec<Point> = vec![];
+        for pt in pts {
+            while h.len() >= 2 && {
+                let (a, b) = (h[h.len() - 2], h[h.len() - 1]);
+                (b - a).cross_z(&(*pt - a)) <= 0.0
+            } {
+                h.pop();
+            }
+            h.push(*pt);
+        }
+        h.pop();
+        h
+    }
+
+    let mut hull = half(pts.iter());
+    hull.extend(half(pts.iter().rev()));
+    if hull.len() < 3 {
+        return Err(anyhow!("points are collinear; no hull."));
+    }
+    Polygon(hull)
+}
+
+/// Returns the rectangle of least area which encloses every point, found by
+/// rotating calipers over the edges of their convex hull.
+pub fn min_area_rect<'a>(pts: impl IntoIterator<Item = &'a Point>) -> Result<Polygon> {
+    let hull = convex_hull(pts)?;
+    let mut best: Option<(f64, Vec<Point>)> = None;
+    for sg in hull.to_segments() {
+        let u = (sg.f - sg.i) / sg.length();
+        let v = Point(-u.y, u.x);
+        let (mut u_lo, mut u_hi, mut v_lo, mut v_hi) = (f64::MAX, f64::MIN, f64::MAX, f64::MIN);
+        for pt in &hull.pts {
+            let (pu, pv) = (pt.dot(&u), pt.dot(&v));
+            u_lo = u_lo.min(pu);
+            u_hi = u_hi.max(pu);
+            v_lo = v_lo.min(pv);
+            v_hi = v_hi.max(pv);
+        }
+        let area = (u_hi - u_lo) * (v_hi - v_lo);
+        if best.as_ref().is_none_or(|(a, _)| area < *a) {
+            let corner = |a: f64, b: f64| u * a + v * b;
+            best = Some((
+                area,
+                vec![
+                    corner(u_lo, v_lo),
+                    corner(u_hi, v_lo),
+                    corner(u_hi, v_hi),
+                    corner(u_lo, v_hi),
+                ],
+            ));
+        }
+    }
+    Polygon(best.ok_or_else(|| anyhow!("empty hull"))?.1)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        bounded::Bounded,
+        shapes::{curve::CurveArc, multiline::Multiline, polygon::Rect},
+        Object,
+    };
+    use float_eq::assert_float_eq;
+    use std::f64::consts::{FRAC_PI_4, TAU};
+
+    #[test]
+    fn test_convex_hull() -> Result<()> {
+        let pts = vec![
+            Point(0, 0),
+            Point(2, 0),
+            Point(1, 1),
+            Point(2, 2),
+            Point(1, 0),
+            Point(0, 2),
+            Point(0.5, 1.5),
+        ];
+        assert_eq!(convex_hull(&pts)?, Rect((0, 0), (2, 2))?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_convex_hull_collinear() {
+        let pts = vec![Point(0, 0), Point(1, 1), Point(2, 2)];
+        assert!(convex_hull(&pts).is_err());
+    }
+
+    #[test]
+    fn test_convex_hull_of_objects() -> Result<()> {
+        let ml = Multiline([(0, 0), (1, 3), (2, 0), (1, 1)]);
+        assert_eq!(ml.convex_hull()?, Polygon([(0, 0), (2, 0), (1, 3)])?);
+
+        let arc = CurveArc(Point(0, 0), 0.0..=TAU, 1.0);
+        let b = arc.convex_hull()?.bounds()?;
+        assert_float_eq!(b.x_min, -1.0, abs <= 1e-9);
+        assert_float_eq!(b.x_max, 1.0, abs <= 1e-9);
+        Ok(())
+    }
+
+    #[test]
+    fn test_min_area_rect() -> Result<()> {
+        // a diamond's tightest box is itself, not its axis-aligned bounds.
+        let mut sq = Rect((0, 0), (1, 2))?;
+        sq.rotate(&Point(0, 0), FRAC_PI_4);
+        let rect = min_area_rect(&sq.pts)?;
+        assert_float_eq!(rect.area(), 2.0, abs <= 1e-9);
+        Ok(())
+    }
+}
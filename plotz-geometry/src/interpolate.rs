@@ -1,5 +1,5 @@
 //! General 1D and 2D interpolation and extrapolation algorithms.
-use crate::{shapes::point::Point, utils::Percent};
+use crate::{shapes::point::Point, tolerance, utils::Percent};
 use anyhow::{anyhow, Result};
 use float_cmp::approx_eq;
 
@@ -19,8 +19,9 @@ fn interpolate_checked(a: f64, b: f64, i: f64) -> Result<f64> {
 /// Given the line |ab| defined by points |a| and |b|, and another point |i|
 /// which lies along it, return the percent along |ab| which |i| lies.
 pub fn interpolate_2d_checked(a: Point, b: Point, i: Point) -> Result<Percent> {
-    let x_same = approx_eq!(f64, a.x, b.x);
-    let y_same = approx_eq!(f64, a.y, b.y);
+    let tol = tolerance::current();
+    let x_same = tol.eq(a.x, b.x);
+    let y_same = tol.eq(a.y, b.y);
     match (x_same, y_same) {
         (true, true) => Err(anyhow!("points are the same")),
         (false, true) => {
@@ -3,23 +3,41 @@
 #![deny(missing_docs)]
 
 pub mod bounded;
+pub mod containment;
 pub mod crop;
+pub mod delaunay;
+pub mod field;
+#[cfg(feature = "geo-types")]
+pub mod geo;
 pub mod grid;
 pub mod group;
+pub mod hull;
 pub mod interpolate;
 pub mod intersection;
 pub mod obj2;
+pub mod offset;
 pub mod overlaps;
+pub mod quadtree;
 pub mod shading;
 pub mod shapes;
+pub mod stipple;
 pub mod style;
+pub mod sweep;
+pub mod tolerance;
+pub mod triangulate;
+pub mod voronoi;
 
 #[macro_use]
 pub mod macros;
 
 mod utils;
 
-use crate::{obj2::Obj2, shapes::point::Point, style::Style};
+use crate::{
+    obj2::Obj2,
+    shapes::{point::Point, polygon::Polygon},
+    style::Style,
+};
+use anyhow::Result;
 use enum_dispatch::enum_dispatch;
 use obj2::ObjType2d;
 use shapes::text::Text;
@@ -85,4 +103,9 @@ pub trait Object {
 
     /// Mutable iterator
     fn iter_mut(&mut self) -> Box<dyn Iterator<Item = &mut Point> + '_>;
+
+    /// The convex hull of this object.
+    fn convex_hull(&self) -> Result<Polygon> {
+        hull::convex_hull(self.iter())
+    }
 }
@@ -3,7 +3,8 @@
 
 use crate::{
     bounded::{Bounded, Bounds},
-    crop::{CropType, Croppable},
+    containment::{default_tolerance, Locate},
+    crop::{CropType, Croppable, PointLocation},
     group::Group,
     overlaps::{polygon_overlaps_multiline, polygon_overlaps_point, polygon_overlaps_segment},
     shapes::{
@@ -50,27 +51,72 @@ impl Croppable for Obj2 {
     type Output = Obj2;
     fn crop(&self, frame: &Polygon, crop_type: CropType) -> Result<Vec<Self::Output>> {
         match &self {
-            Obj2::Point(p) => match polygon_overlaps_point(frame, p)? {
-                Some((_, p)) => Ok(vec![Obj2::Point(p)]),
-                None => Ok(vec![]),
+            Obj2::Point(p) | Obj2::Text(Text { pt: p, .. }) => {
+                let kept = match crop_type {
+                    CropType::Inclusive => polygon_overlaps_point(frame, p)?.is_some(),
+                    CropType::Exclusive => {
+                        frame.locate(p, default_tolerance())? == PointLocation::Outside
+                    }
+                };
+                Ok(if kept { vec![self.clone()] } else { vec![] })
+            }
+            Obj2::Segment(sg) => match crop_type {
+                CropType::Inclusive => match polygon_overlaps_segment(frame, sg)? {
+                    Some((_, sgops)) => Ok(sgops.into_iter().map(|sgop| sgop.to_obj(sg)).collect()),
+                    None => Ok(vec![]),
+                },
+                CropType::Exclusive => Ok(sg
+                    .crop_excluding(frame)?
+                    .into_iter()
+                    .map(Obj2::from)
+                    .collect()),
             },
-            Obj2::Segment(sg) => match polygon_overlaps_segment(frame, sg)? {
-                Some((_, sgops)) => Ok(sgops.into_iter().map(|sgop| sgop.to_obj(sg)).collect()),
-                None => Ok(vec![]),
-            },
-            Obj2::Multiline(ml) => match polygon_overlaps_multiline(frame, ml)? {
-                Some((_, mlops)) => Ok(mlops.into_iter().map(|mlop| mlop.to_obj(ml)).collect()),
-                None => Ok(vec![]),
+            Obj2::Multiline(ml) => match crop_type {
+                CropType::Inclusive => match polygon_overlaps_multiline(frame, ml)? {
+                    Some((_, mlops)) => Ok(mlops.into_iter().map(|mlop| mlop.to_obj(ml)).collect()),
+                    None => Ok(vec![]),
+                },
+                CropType::Exclusive => Ok(ml
+                    .crop_excluding(frame)?
+                    .into_iter()
+                    .map(Obj2::from)
+                    .collect()),
             },
+            // cutting a hole out of the middle of a polygon leaves a polygon
+            // with a cavity, which |Polygon| itself can't be.
+            Obj2::Polygon(pg) if crop_type == CropType::Exclusive && pg.surrounds(frame)? => {
+                Ok(vec![Obj2::PolygonWithCavities(PolygonWithCavities {
+                    outer: pg.clone(),
+                    inner: vec![frame.clone()],
+                })])
+            }
             Obj2::Polygon(pg) => Ok(pg
                 .crop(frame, crop_type)?
                 .into_iter()
                 .map(Obj2::from)
                 .collect::<Vec<_>>()),
-            Obj2::PolygonWithCavities(_) => todo!(),
-            Obj2::CurveArc(_) => todo!(),
-            Obj2::Text(_) => todo!(),
-            Obj2::Group(_) => todo!(),
+            Obj2::PolygonWithCavities(pwc) => Ok(pwc
+                .crop(frame, crop_type)?
+                .into_iter()
+                .map(|pwc| {
+                    if pwc.inner.is_empty() {
+                        Obj2::Polygon(pwc.outer)
+                    } else {
+                        Obj2::PolygonWithCavities(pwc)
+                    }
+                })
+                .collect()),
+            Obj2::CurveArc(ca) => Ok(ca
+                .crop(frame, crop_type)?
+                .into_iter()
+                .map(Obj2::from)
+                .collect()),
+            Obj2::Group(g) => Ok(g
+                .crop(frame, crop_type)?
+                .into_iter()
+                .filter(|g| g.iter_objects().next().is_some())
+                .map(Obj2::Group)
+                .collect()),
         }
     }
 }
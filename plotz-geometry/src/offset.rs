@@ -0,0 +1,377 @@
+//! Offsetting (insetting and outsetting) of polygons and multilines.
+
+use crate::{
+    shapes::{point::Point, polygon::Polygon, segment::Segment},
+    tolerance,
+};
+use std::f64::consts::PI;
+
+/// The angular step (in radians) used when approximating round joins.
+const ROUND_JOIN_STEP: f64 = PI / 32.0;
+
+/// How the corners of an offset outline are joined together.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum JoinStyle {
+    /// Extend the offset edges until they meet. If the miter would be longer
+    /// than |limit| times the offset distance, falls back to a bevel.
+    Miter(f64),
+    /// Join offset edges with a circular arc about the original vertex.
+    Round,
+    /// Join offset edges with a straight line.
+    Bevel,
+}
+
+// the outward (right-hand) unit normal of a segment, for a positively
+// oriented polygon.
+fn normal(i: Point, f: Point) -> Point {
+    let d = f - i;
+    let l = d.norm();
+    Point(d.y / l, -d.x / l)
+}
+
+// Drops consecutive (and wraparound) duplicate points from a closed ring.
+fn dedup_ring(pts: &[Point]) -> Vec<Point> {
+    let tol = tolerance::current();
+    let mut r: Vec<Point> = vec![];
+    for pt in pts {
+        if r.last().is_none_or(|l| !tol.pts_eq(l, pt)) {
+            r.push(*pt);
+        }
+    }
+    while r.len() > 1 && tol.pts_eq(&r[0], r.last().unwrap()) {
+        r.pop();
+    }
+    r
+}
+
+// Drops vertices which lie along a straight run of a closed ring.
+fn drop_collinear(pts: Vec<Point>) -> Vec<Point> {
+    let n = pts.len();
+    let keep: Vec<Point> = (0..n)
+        .filter(|k| {
+            let (a, b, c) = (pts[(k + n - 1) % n], pts[*k], pts[(k + 1) % n]);
+            let (d_in, d_out) = (b - a, c - b);
+            d_in.cross_z(&d_out).abs() > 1e-12 * d_in.norm() * d_out.norm()
+                || d_in.dot(&d_out) < 0.0
+        })
+        .map(|k| pts[k])
+        .collect();
+    if keep.len() < n {
+        drop_collinear(keep)
+    } else {
+        keep
+    }
+}
+
+// Builds the raw (possibly self-intersecting) offset outline of a closed ring.
+// Positive distances grow a positively oriented ring, negative distances
+// shrink it.
+fn raw_offset_ring(pts: &[Point], distance: f64, join: JoinStyle) -> Vec<Point> {
+    let n = pts.len();
+    let normals: Vec<Point> = (0..n).map(|k| normal(pts[k], pts[(k + 1) % n])).collect();
+    let mut r: Vec<Point> = vec![];
+
+    for k in 0..n {
+        let prev = (k + n - 1) % n;
+        let vtx = pts[k];
+        let (n_in, n_out) = (normals[prev], normals[k]);
+        let end_prev = vtx + n_in * distance;
+        let start_next = vtx + n_out * distance;
+
+        let d_in = vtx - pts[prev];
+        let d_out = pts[(k + 1) % n] - vtx;
+        let turn = d_in.cross_z(&d_out) / (d_in.norm() * d_out.norm());
+        let is_reversal = turn.abs() < 1e-12 && d_in.dot(&d_out) < 0.0;
+
+        if turn.abs() < 1e-12 && !is_reversal {
+            // collinear, no join necessary.
+            r.push(start_next);
+        } else if is_reversal || turn * distance > 0.0 {
+            // the offset edges leave a gap here which must be joined.
+            match join {
+                JoinStyle::Miter(limit) => {
+                    let c = n_in.dot(&n_out);
+                    let ratio = (2.0 / (1.0 + c)).sqrt();
+                    if is_reversal || !ratio.is_finite() || ratio > limit {
+                        r.extend([end_prev, start_next]);
+                    } else {
+                        r.push(vtx + (n_in + n_out) * (distance / (1.0 + c)));
+                    }
+                }
+                JoinStyle::Bevel => {
+                    r.extend([end_prev, start_next]);
+                }
+                JoinStyle::Round => {
+                    let (v0, v1) = (n_in * distance, n_out * distance);
+                    let sweep = if is_reversal {
+                        PI * distance.signum()
+                    } else {
+                        v0.cross_z(&v1).atan2(v0.dot(&v1))
+                    };
+                    let steps = (sweep.abs() / ROUND_JOIN_STEP).ceil().max(1.0) as usize;
+                    r.push(end_prev);
+                    for s in 1..steps {
+                        r.push(vtx + v0.rotate(&Point(0, 0), sweep * s as f64 / steps as f64));
+                    }
+                    r.push(start_next);
+                }
+            }
+        } else {
+            // the offset edges overlap here. route the outline through the
+            // original vertex; the resulting inverted loop is removed during
+            // cleanup.
+            r.extend([end_prev, vtx, start_next]);
+        }
+    }
+    dedup_ring(&r)
+}
+
+// Parameters (t, u) along |a| and |b| at which they cross, if they do. Both
+// parameters are half-open on [0, 1) so that shared endpoints of consecutive
+// segments are only counted once.
+fn crossing(a: &Segment, b: &Segment) -> Option<(f64, f64)> {
+    let (r, s) = (a.f - a.i, b.f - b.i);
+    let denom = r.cross_z(&s);
+    if denom.abs() <= 1e-12 * r.norm() * s.norm() {
+        return None;
+    }
+    let q = b.i - a.i;
+    let t = q.cross_z(&s) / denom;
+    let u = q.cross_z(&r) / denom;
+    ((0.0..1.0).contains(&t) && (0.0..1.0).contains(&u)).then_some((t, u))
+}
+
+// Splits a closed, possibly self-intersecting ring into loops at each of its
+// self-intersections.
+fn split_into_loops(ring: &[Point]) -> Vec<Vec<Point>> {
+    let n = ring.len();
+    let sgs: Vec<Segment> = (0..n)
+        .map(|k| Segment(ring[k], ring[(k + 1) % n]))
+        .collect();
+    let bounds: Vec<(Point, Point)> = sgs
+        .iter()
+        .map(|sg| {
+            (
+                Point(sg.i.x.min(sg.f.x), sg.i.y.min(sg.f.y)),
+                Point(sg.i.x.max(sg.f.x), sg.i.y.max(sg.f.y)),
+            )
+        })
+        .collect();
+
+    // (t, crossing id) for each segment.
+    let mut cuts: Vec<Vec<(f64, usize)>> = vec![vec![]; n];
+    let mut crossing_pts: Vec<Point> = vec![];
+    for i in 0..n {
+        for j in (i + 1)..n {
+            let ((a_lo, a_hi), (b_lo, b_hi)) = (bounds[i], bounds[j]);
+            if a_hi.x < b_lo.x || b_hi.x < a_lo.x || a_hi.y < b_lo.y || b_hi.y < a_lo.y {
+                continue;
+            }
+            if let Some((t, u)) = crossing(&sgs[i], &sgs[j]) {
+                let id = crossing_pts.len();
+                crossing_pts.push(sgs[i].i + (sgs[i].f - sgs[i].i) * t);
+                cuts[i].push((t, id));
+                cuts[j].push((u, id));
+            }
+        }
+    }
+
+    let mut sequence: Vec<(Point, Option<usize>)> = vec![];
+    for (k, mut ks) in cuts.into_iter().enumerate() {
+        sequence.push((ring[k], None));
+        ks.sort_by(|a, b| a.0.total_cmp(&b.0));
+        sequence.extend(ks.into_iter().map(|(_, id)| (crossing_pts[id], Some(id))));
+    }
+
+    // walk the sequence; whenever a crossing is revisited, the stretch since
+    // its first visit is a closed loop.
+    let mut loops: Vec<Vec<Point>> = vec![];
+    let mut current: Vec<(Point, Option<usize>)> = vec![];
+    for entry in sequence {
+        if let Some(id) = entry.1 {
+            if let Some(pos) = current.iter().position(|(_, x)| *x == Some(id)) {
+                loops.push(current.drain(pos..).map(|(pt, _)| pt).collect());
+            }
+        }
+        current.push(entry);
+    }
+    loops.push(current.into_iter().map(|(pt, _)| pt).collect());
+    loops
+}
+
+// Offsets a closed ring by |distance| and keeps only those loops of the
+// outline which are positively oriented and at least |distance| away from
+// every segment in |original|.
+fn offset_and_clean(
+    ring: &[Point],
+    original: &[Segment],
+    distance: f64,
+    join: JoinStyle,
+) -> Vec<Polygon> {
+    let raw = raw_offset_ring(ring, distance, join);
+    if raw.len() < 3 {
+        return vec![];
+    }
+    let min_dist = distance.abs() * (1.0 - 1e-6);
+    let min_area = distance * distance * 1e-9;
+    split_into_loops(&raw)
+        .into_iter()
+        .map(|l| Polygon {
+            pts: drop_collinear(dedup_ring(&l)),
+        })
+        .filter(|l| l.pts.len() >= 3 && l.signed_area() > min_area)
+        .filter(|l| {
+            l.pts
+                .iter()
+                .all(|pt| original.iter().all(|sg| sg.dist_to_point(pt) >= min_dist))
+        })
+        .filter_map(|l| Polygon(l.pts).ok())
+        .collect()
+}
+
+/// Offsets a polygon by |distance|. See [`Polygon::offset`].
+pub(crate) fn offset_polygon(pg: &Polygon, distance: f64, join: JoinStyle) -> Vec<Polygon> {
+    if distance == 0.0 {
+        return vec![pg.clone()];
+    }
+    let mut ring = dedup_ring(&pg.pts);
+    if pg.signed_area() < 0.0 {
+        ring.reverse();
+    }
+    offset_and_clean(&ring, &pg.to_segments(), distance, join)
+}
+
+/// Outlines a chain of points at |distance|. See [`Multiline::offset`].
+///
+/// [`Multiline::offset`]: crate::shapes::multiline::Multiline::offset
+pub(crate) fn offset_chain(pts: &[Point], distance: f64, join: JoinStyle) -> Vec<Polygon> {
+    let tol = tolerance::current();
+    let mut chain: Vec<Point> = vec![];
+    for pt in pts {
+        if chain.last().is_none_or(|l| !tol.pts_eq(l, pt)) {
+            chain.push(*pt);
+        }
+    }
+    if chain.len() < 2 || distance == 0.0 {
+        return vec![];
+    }
+    // walk out along the chain and back again; the two ends become
+    // 180-degree turns, which are capped according to |join|.
+    let mut ring = chain.clone();
+    ring.extend(chain.iter().rev().skip(1).take(chain.len() - 2));
+    let original: Vec<Segment> = chain.windows(2).map(|w| Segment(w[0], w[1])).collect();
+    offset_and_clean(&ring, &original, distance.abs(), join)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::{multiline::Multiline, polygon::Rect};
+    use float_eq::assert_float_eq;
+    use test_case::test_case;
+
+    #[test_case(JoinStyle::Miter(4.0), 4; "miter")]
+    #[test_case(JoinStyle::Bevel, 8; "bevel")]
+    #[test_case(JoinStyle::Miter(1.1), 8; "miter over limit")]
+    #[test_case(JoinStyle::Round, 4 * 17; "round")]
+    fn test_outset_square(join: JoinStyle, expected_pts: usize) {
+        let sq = Rect((0, 0), (10, 10)).unwrap();
+        let actual = sq.offset(1.0, join);
+        assert_eq!(actual.len(), 1);
+        assert_eq!(actual[0].pts.len(), expected_pts);
+        for pt in &actual[0].pts {
+            assert!(pt.x >= -1.0 - 1e-9 && pt.x <= 11.0 + 1e-9);
+            assert!(pt.y >= -1.0 - 1e-9 && pt.y <= 11.0 + 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_outset_square_miter_exact() {
+        let sq = Rect((0, 0), (10, 10)).unwrap();
+        assert_eq!(
+            sq.offset(1.0, JoinStyle::Miter(2.0)),
+            vec![Rect((-1, -1), (12, 12)).unwrap()]
+        );
+    }
+
+    #[test_case(JoinStyle::Miter(2.0); "miter")]
+    #[test_case(JoinStyle::Round; "round")]
+    #[test_case(JoinStyle::Bevel; "bevel")]
+    fn test_inset_square(join: JoinStyle) {
+        let sq = Rect((0, 0), (10, 10)).unwrap();
+        let actual = sq.offset(-2.0, join);
+        assert_eq!(actual.len(), 1);
+        assert_eq!(actual[0], Rect((2, 2), (6, 6)).unwrap());
+    }
+
+    #[test]
+    fn test_inset_collapses() {
+        let sq = Rect((0, 0), (10, 10)).unwrap();
+        assert!(sq.offset(-6.0, JoinStyle::Round).is_empty());
+    }
+
+    #[test]
+    fn test_inset_pinches_off() {
+        // a dumbbell: two 10x10 squares joined by a 2-wide bridge.
+        let dumbbell = Polygon([
+            (0, 0),
+            (10, 0),
+            (10, 4),
+            (20, 4),
+            (20, 0),
+            (30, 0),
+            (30, 10),
+            (20, 10),
+            (20, 6),
+            (10, 6),
+            (10, 10),
+            (0, 10),
+        ])
+        .unwrap();
+
+        let mut actual = dumbbell.offset(-2.0, JoinStyle::Miter(2.0));
+        actual.sort();
+        assert_eq!(
+            actual,
+            vec![
+                Rect((2, 2), (6, 6)).unwrap(),
+                Rect((22, 2), (6, 6)).unwrap()
+            ]
+        );
+
+        // a small inset keeps the bridge.
+        assert_eq!(dumbbell.offset(-0.5, JoinStyle::Miter(2.0)).len(), 1);
+    }
+
+    #[test]
+    fn test_outset_concave() {
+        // an L-shape; the reflex corner must not leave a loop behind.
+        let ell = Polygon([(0, 0), (10, 0), (10, 2), (2, 2), (2, 10), (0, 10)]).unwrap();
+        let actual = ell.offset(1.0, JoinStyle::Miter(2.0));
+        assert_eq!(
+            actual,
+            vec![Polygon([(-1, -1), (11, -1), (11, 3), (3, 3), (3, 11), (-1, 11)]).unwrap()]
+        );
+    }
+
+    #[test_case(JoinStyle::Miter(2.0), 4; "butt caps")]
+    #[test_case(JoinStyle::Bevel, 4; "bevel caps")]
+    #[test_case(JoinStyle::Round, 2 * 33; "round caps")]
+    fn test_multiline_outline(join: JoinStyle, expected_pts: usize) {
+        let ml = Multiline([(0, 0), (10, 0)]);
+        let actual = ml.offset(1.0, join);
+        assert_eq!(actual.len(), 1);
+        assert_eq!(actual[0].pts.len(), expected_pts);
+        if expected_pts == 4 {
+            assert_eq!(actual[0], Rect((0, -1), (10, 2)).unwrap());
+        }
+    }
+
+    #[test]
+    fn test_multiline_outline_bent() {
+        let ml = Multiline([(0, 0), (10, 0), (10, 10)]);
+        let actual = ml.offset(1.0, JoinStyle::Miter(2.0));
+        assert_eq!(actual.len(), 1);
+        assert_float_eq!(actual[0].signed_area(), 40.0, abs <= 1e-9);
+    }
+}
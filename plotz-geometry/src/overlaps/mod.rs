@@ -3,6 +3,9 @@
 pub mod opinion;
 
 use crate::{
+    bounded::{Bounded, Bounds},
+    containment::default_tolerance,
+    crop::PointLocation,
     interpolate::interpolate_2d_checked,
     obj2::Obj2,
     overlaps::opinion::{
@@ -10,16 +13,12 @@ use crate::{
         polygon_opinion::{PolygonOp, PolygonOpSet},
         segment_opinion::{SegmentOp, SegmentOpSet},
     },
-    shapes::{
-        multiline::Multiline,
-        point::Point,
-        polygon::{abp, Polygon},
-        segment::Segment,
-    },
+    quadtree::QuadTree,
+    shapes::{multiline::Multiline, point::Point, polygon::Polygon, segment::Segment},
+    tolerance::{self, orient2d},
     utils::Percent::{One, Zero},
 };
 use anyhow::{anyhow, Result};
-use float_cmp::approx_eq;
 use nonempty::{nonempty, NonEmpty};
 
 //           || pt | sg | ml | ca |
@@ -101,6 +100,17 @@ pub fn totally_covers(o1: &Obj2, o2: &Obj2) -> Result<bool> {
     }
 }
 
+// True if |a| and |b| come within the current tolerance of each other, so that
+// a box test never rules out what the tolerant checks would call touching.
+fn boxes_touch(a: &Bounds, b: &Bounds) -> bool {
+    let scale = [
+        a.x_min, a.x_max, a.y_min, a.y_max, b.x_min, b.x_max, b.y_min, b.y_max,
+    ]
+    .iter()
+    .fold(0.0_f64, |m, v| m.max(v.abs()));
+    a.padded(tolerance::current().dist(scale)).intersects(b)
+}
+
 pub fn point_overlaps_point(a: &Point, b: &Point) -> Result<Option<Point>> {
     if a == b {
         Ok(Some(*a))
@@ -114,15 +124,16 @@ pub fn segment_overlaps_point(s: &Segment, p: &Point) -> Result<Option<(SegmentO
         Ok(Some((SegmentOp::Point(*p, Zero), *p)))
     } else if s.f == *p {
         Ok(Some((SegmentOp::Point(*p, One), *p)))
-    } else if approx_eq!(
-        f64,
-        s.length(),
-        Segment(s.i, *p).length() + Segment(*p, s.f).length()
-    ) {
-        Ok(Some((
-            SegmentOp::Point(*p, interpolate_2d_checked(s.i, s.f, *p)?),
-            *p,
-        )))
+    } else if tolerance::current().collinear(&s.i, &s.f, p)
+        && s.bounds()?.dist_to_point(p) <= tolerance::current().abs
+    {
+        let pct = match interpolate_2d_checked(s.i, s.f, *p) {
+            Ok(pct) => pct,
+            // close enough to be on the segment, but a hair past one end.
+            Err(_) if s.i.dist(p) <= s.f.dist(p) => Zero,
+            Err(_) => One,
+        };
+        Ok(Some((SegmentOp::Point(*p, pct), *p)))
     } else {
         Ok(None)
     }
@@ -132,8 +143,28 @@ pub fn segment_overlaps_segment(
     sa: &Segment,
     sb: &Segment,
 ) -> Result<Option<(SegmentOp, SegmentOp)>> {
+    // Segments whose boxes don't touch can't overlap.
+    if !boxes_touch(&sa.bounds()?, &sb.bounds()?) {
+        return Ok(None);
+    }
+
     // NB: sa and sb are _not_ guaranteed to point the same way.
-    if approx_eq!(f64, sa.slope(), sb.slope()) || approx_eq!(f64, sa.slope(), sb.flip().slope()) {
+    let tol = tolerance::current();
+
+    // a segment with no length is just a point.
+    if tol.pts_eq(&sa.i, &sa.f) {
+        return Ok(segment_overlaps_point(sb, &sa.i)?.map(|(op, _)| (SegmentOp::Entire, op)));
+    }
+    if tol.pts_eq(&sb.i, &sb.f) {
+        return Ok(segment_overlaps_point(sa, &sb.i)?.map(|(op, _)| (op, SegmentOp::Entire)));
+    }
+
+    let (o_sbi, o_sbf) = (orient2d(&sa.i, &sa.f, &sb.i), orient2d(&sa.i, &sa.f, &sb.f));
+    if tol.eq(sa.slope(), sb.slope())
+        || tol.eq(sa.slope(), sb.flip().slope())
+        || (o_sbi == 0.0 && o_sbf == 0.0)
+        || (tol.collinear(&sa.i, &sa.f, &sb.i) && tol.collinear(&sa.i, &sa.f, &sb.f))
+    {
         let isxn_segment: Option<Segment> = match (
             segment_overlaps_point(sb, &sa.i)?,
             segment_overlaps_point(sb, &sa.f)?,
@@ -266,10 +297,15 @@ pub fn segment_overlaps_segment(
     let s2_x = p3_x - p2_x;
     let s2_y = p3_y - p2_y;
 
-    let s = (-s1_y * (p0_x - p2_x) + s1_x * (p0_y - p2_y)) / (-s2_x * s1_y + s1_x * s2_y);
     let t = (s2_x * (p0_y - p2_y) - s2_y * (p0_x - p2_x)) / (-s2_x * s1_y + s1_x * s2_y);
 
-    if (0_f64..=1_f64).contains(&s) && (0_f64..=1_f64).contains(&t) {
+    // the segments meet i.f.f. each one's ends don't lie strictly to one side
+    // of the other. these orientations are exact, so the answer doesn't
+    // depend on how |t| rounds.
+    let (o_sai, o_saf) = (orient2d(&sb.i, &sb.f, &sa.i), orient2d(&sb.i, &sb.f, &sa.f));
+    let straddles = |p: f64, q: f64| (p <= 0.0 || q <= 0.0) && (p >= 0.0 || q >= 0.0);
+    if straddles(o_sbi, o_sbf) && straddles(o_sai, o_saf) {
+        let t = t.clamp(0.0, 1.0);
         let pt = Point(p0_x + (t * s1_x), p0_y + (t * s1_y));
         return Ok(Some((
             SegmentOp::Point(pt, interpolate_2d_checked(sa.i, sa.f, pt)?),
@@ -277,6 +313,19 @@ pub fn segment_overlaps_segment(
         )));
     }
 
+    // an end of one which lies a hair to the wrong side of the other still
+    // meets it.
+    for (pt, pct) in [(sb.i, Zero), (sb.f, One)] {
+        if let Some((op, _)) = segment_overlaps_point(sa, &pt)? {
+            return Ok(Some((op, SegmentOp::Point(pt, pct))));
+        }
+    }
+    for (pt, pct) in [(sa.i, Zero), (sa.f, One)] {
+        if let Some((op, _)) = segment_overlaps_point(sb, &pt)? {
+            return Ok(Some((SegmentOp::Point(pt, pct), op)));
+        }
+    }
+
     Ok(None)
 }
 
@@ -324,8 +373,11 @@ pub fn multiline_overlaps_multiline(
     let mut ml1_op_set = MultilineOpSet::new(/*original=*/ ml1);
     let mut ml2_op_set = MultilineOpSet::new(/*original=*/ ml2);
 
+    // only compare segments whose boxes intersect.
+    let ml2_sgs = QuadTree::from_bounded(ml2.to_segments())?;
     for (ml_sg1_idx, ml_sg1) in ml1.to_segments().iter().enumerate() {
-        for (ml_sg2_idx, ml_sg2) in ml2.to_segments().iter().enumerate() {
+        for ml_sg2_idx in ml2_sgs.query(&ml_sg1.bounds()?) {
+            let (_, ml_sg2) = &ml2_sgs.get(ml_sg2_idx).unwrap();
             if let Some((ml_sg1_op, ml_sg2_op)) = segment_overlaps_segment(ml_sg1, ml_sg2)? {
                 ml1_op_set.add(MultilineOp::from_segment_opinion(ml_sg1_idx, ml_sg1_op))?;
                 ml2_op_set.add(MultilineOp::from_segment_opinion(ml_sg2_idx, ml_sg2_op))?;
@@ -361,17 +413,12 @@ pub fn polygon_overlaps_point(
         }
     }
 
-    // PolygonOp::PointWithinArea or None.
-    // https://en.wikipedia.org/wiki/Point_in_polygon#Winding_number_algorithm
-    let theta: f64 = (polygon.pts.iter())
-        .zip(polygon.pts.iter().cycle().skip(1))
-        .map(|(i, j)| abp(point, i, j))
-        .sum();
-
-    if approx_eq!(f64, theta, 0_f64, epsilon = 0.00001) {
-        Ok(None)
-    } else {
-        Ok(Some((PolygonOp::AreaPoint(*point), *point)))
+    // PolygonOp::PointWithinArea or None. The locator counts windings
+    // exactly, and treats anything within the current tolerance of an edge as
+    // on it.
+    match polygon.locator(default_tolerance())?.locate(point) {
+        PointLocation::Outside => Ok(None),
+        _ => Ok(Some((PolygonOp::AreaPoint(*point), *point))),
     }
 }
 
@@ -379,6 +426,12 @@ pub fn polygon_overlaps_segment(
     polygon: &Polygon,
     segment: &Segment,
 ) -> Result<Option<(NonEmpty<PolygonOp>, NonEmpty<SegmentOp>)>> {
+    // a segment which lies entirely outside of the polygon's box can't
+    // overlap it at all.
+    if !boxes_touch(&polygon.bounds()?, &segment.bounds()?) {
+        return Ok(None);
+    }
+
     let mut pg_op_set = PolygonOpSet::new(/*original=*/ polygon);
     let mut sg_op_set = SegmentOpSet::new(/*original=*/ segment);
     for (pg_sg_idx, pg_sg) in polygon.to_segments().iter().enumerate() {
@@ -610,6 +663,32 @@ mod tests {
         Ok(())
     }
 
+    #[test]
+    fn test_segment_overlaps_segment_within_tolerance() -> Result<()> {
+        // |b| starts a hair past the end of |a|: their boxes are apart, but
+        // by less than the tolerance.
+        let a = Segment((0, 0), (1, 0));
+        let b = Segment((1.0 + 1e-12, 0.0), (2.0, 0.0));
+        let pg = Polygon([(0, 0), (1, 0), (1, -1), (0, -1)])?;
+        tolerance::with_tolerance(tolerance::Tolerance::for_scale(1.0), || {
+            assert!(segment_overlaps_segment(&a, &b)?.is_some());
+            assert!(segment_overlaps_segment(&b, &a)?.is_some());
+            assert!(polygon_overlaps_segment(&pg, &b)?.is_some());
+
+            // |c| starts a hair to the wrong side of |a|, and still meets it.
+            let c = Segment((0.5, 1e-12), (0.5, 1.0));
+            assert!(matches!(
+                segment_overlaps_segment(&a, &c)?,
+                Some((SegmentOp::Point(_, Val(_)), SegmentOp::Point(_, Zero)))
+            ));
+            assert!(segment_overlaps_segment(&c, &a)?.is_some());
+            // but not from further off.
+            let d = Segment((0.5, 1e-6), (0.5, 1.0));
+            assert!(segment_overlaps_segment(&a, &d)?.is_none());
+            Ok(())
+        })
+    }
+
     #[test_case(Multiline([*A, *C, *E, *O, *Y]), *A, Some((ne![MultilineOp::Point(0, *A)], *A)); "multiline point at index 0")]
     #[test_case(Multiline([*A, *C, *E, *O, *Y]), *E, Some((ne![MultilineOp::Point(2, *E)], *E)); "multiline point at index 2")]
     #[test_case(Multiline([*A, *C, *E, *O, *Y]), *Y, Some((ne![MultilineOp::Point(4, *Y)], *Y)); "multiline point at index 4")]
@@ -0,0 +1,371 @@
+//! A quadtree over bounding boxes, for window, nearest-neighbour and
+//! pair-candidate queries.
+
+use crate::{
+    bounded::{Bounded, Bounds},
+    shapes::point::Point,
+};
+use anyhow::Result;
+use float_ord::FloatOrd;
+use std::{cmp::Reverse, collections::BinaryHeap};
+
+// Nodes with more than this many items split into quadrants.
+const MAX_ITEMS_PER_NODE: usize = 8;
+// ...unless they are already this deep.
+const MAX_DEPTH: usize = 16;
+
+#[derive(Debug, Clone)]
+struct Node {
+    bounds: Bounds,
+    depth: usize,
+    // indices into |QuadTree::items| which fit here but not in any one child.
+    items: Vec<usize>,
+    children: Option<Box<[Node; 4]>>,
+}
+
+impl Node {
+    fn new(bounds: Bounds, depth: usize) -> Node {
+        Node {
+            bounds,
+            depth,
+            items: vec![],
+            children: None,
+        }
+    }
+
+    fn quadrants(&self) -> [Node; 4] {
+        let Bounds {
+            x_min,
+            x_max,
+            y_min,
+            y_max,
+        } = self.bounds;
+        let (x_mid, y_mid) = ((x_min + x_max) / 2.0, (y_min + y_max) / 2.0);
+        let d = self.depth + 1;
+        [
+            Node::new(
+                Bounds {
+                    x_min,
+                    x_max: x_mid,
+                    y_min,
+                    y_max: y_mid,
+                },
+                d,
+            ),
+            Node::new(
+                Bounds {
+                    x_min: x_mid,
+                    x_max,
+                    y_min,
+                    y_max: y_mid,
+                },
+                d,
+            ),
+            Node::new(
+                Bounds {
+                    x_min,
+                    x_max: x_mid,
+                    y_min: y_mid,
+                    y_max,
+                },
+                d,
+            ),
+            Node::new(
+                Bounds {
+                    x_min: x_mid,
+                    x_max,
+                    y_min: y_mid,
+                    y_max,
+                },
+                d,
+            ),
+        ]
+    }
+
+    fn insert<T>(&mut self, idx: usize, all: &[(Bounds, T)]) {
+        let b = &all[idx].0;
+        if let Some(children) = &mut self.children {
+            if let Some(c) = children.iter_mut().find(|c| c.bounds.contains_bounds(b)) {
+                c.insert(idx, all);
+                return;
+            }
+        }
+        self.items.push(idx);
+
+        if self.children.is_none()
+            && self.items.len() > MAX_ITEMS_PER_NODE
+            && self.depth < MAX_DEPTH
+        {
+            self.children = Some(Box::new(self.quadrants()));
+            for idx in std::mem::take(&mut self.items) {
+                self.insert(idx, all);
+            }
+        }
+    }
+
+    fn query<T>(&self, window: &Bounds, all: &[(Bounds, T)], out: &mut Vec<usize>) {
+        if !self.bounds.intersects(window) {
+            return;
+        }
+        out.extend(self.items.iter().filter(|i| all[**i].0.intersects(window)));
+        if let Some(children) = &self.children {
+            for c in children.iter() {
+                c.query(window, all, out);
+            }
+        }
+    }
+}
+
+/// A loose quadtree over items keyed by their bounding boxes. Items are stored
+/// in the smallest node which wholly contains them.
+///
+/// Indices returned from queries are insertion order, so callers can keep
+/// their own parallel vectors.
+#[derive(Debug, Clone)]
+pub struct QuadTree<T> {
+    items: Vec<(Bounds, T)>,
+    root: Option<Node>,
+}
+
+impl<T> Default for QuadTree<T> {
+    fn default() -> Self {
+        QuadTree {
+            items: vec![],
+            root: None,
+        }
+    }
+}
+
+impl<T> QuadTree<T> {
+    /// An empty quadtree.
+    pub fn new() -> QuadTree<T> {
+        QuadTree::default()
+    }
+
+    /// Builds a quadtree from items and their bounds all at once.
+    pub fn from_items(items: impl IntoIterator<Item = (Bounds, T)>) -> QuadTree<T> {
+        let mut qt = QuadTree {
+            items: items.into_iter().collect(),
+            root: None,
+        };
+        qt.rebuild();
+        qt
+    }
+
+    // Re-roots the tree around every item and reinserts them all.
+    fn rebuild(&mut self) {
+        let Some(first) = self.items.first() else {
+            self.root = None;
+            return;
+        };
+        let b = self.items.iter().fold(first.0, |acc, (b, _)| acc.join(b));
+        // pad the root out a little, so that the next few inserts probably
+        // fit without another rebuild.
+        let pad = (b.x_max - b.x_min).max(b.y_max - b.y_min).max(1.0) / 4.0;
+        let mut root = Node::new(
+            Bounds {
+                x_min: b.x_min - pad,
+                x_max: b.x_max + pad,
+                y_min: b.y_min - pad,
+                y_max: b.y_max + pad,
+            },
+            0,
+        );
+        for idx in 0..self.items.len() {
+            root.insert(idx, &self.items);
+        }
+        self.root = Some(root);
+    }
+
+    /// Inserts an item with the given bounds. Returns its index.
+    pub fn insert(&mut self, bounds: Bounds, t: T) -> usize {
+        let idx = self.items.len();
+        self.items.push((bounds, t));
+        match &mut self.root {
+            Some(root) if root.bounds.contains_bounds(&bounds) => root.insert(idx, &self.items),
+            _ => self.rebuild(),
+        }
+        idx
+    }
+
+    /// The number of items in the tree.
+    pub fn len(&self) -> usize {
+        self.items.len()
+    }
+
+    /// True if there are no items in the tree.
+    pub fn is_empty(&self) -> bool {
+        self.items.is_empty()
+    }
+
+    /// The item at |idx|, if there is one.
+    pub fn get(&self, idx: usize) -> Option<&(Bounds, T)> {
+        self.items.get(idx)
+    }
+
+    /// Every item, in insertion order.
+    pub fn iter(&self) -> impl Iterator<Item = &(Bounds, T)> {
+        self.items.iter()
+    }
+
+    /// The indices of every item whose bounds intersect |window| (edges
+    /// included), in ascending order.
+    pub fn query(&self, window: &Bounds) -> Vec<usize> {
+        let mut out = vec![];
+        if let Some(root) = &self.root {
+            root.query(window, &self.items, &mut out);
+        }
+        out.sort_unstable();
+        out
+    }
+
+    /// The index of the item nearest to |pt|, by the distance to its bounds.
+    pub fn nearest(&self, pt: &Point) -> Option<usize> {
+        self.nearest_by(pt, |b, _| b.dist_to_point(pt))
+    }
+
+    /// The index of the item nearest to |pt| by some exact distance function.
+    /// |dist| must never be less than the distance from |pt| to the item's
+    /// bounds, which is what prunes the search.
+    pub fn nearest_by(&self, pt: &Point, dist: impl Fn(&Bounds, &T) -> f64) -> Option<usize> {
+        let root = self.root.as_ref()?;
+        // a best-first search over nodes and items, keyed on distance.
+        enum Entry<'a> {
+            Node(&'a Node),
+            Item(usize),
+        }
+        let mut entries: Vec<Entry> = vec![Entry::Node(root)];
+        let mut heap: BinaryHeap<Reverse<(FloatOrd<f64>, usize)>> = BinaryHeap::new();
+        heap.push(Reverse((FloatOrd(root.bounds.dist_to_point(pt)), 0)));
+
+        while let Some(Reverse((_, e))) = heap.pop() {
+            match entries[e] {
+                Entry::Item(idx) => return Some(idx),
+                Entry::Node(node) => {
+                    for idx in &node.items {
+                        let (b, t) = &self.items[*idx];
+                        entries.push(Entry::Item(*idx));
+                        heap.push(Reverse((FloatOrd(dist(b, t)), entries.len() - 1)));
+                    }
+                    if let Some(children) = &node.children {
+                        for c in children.iter() {
+                            entries.push(Entry::Node(c));
+                            heap.push(Reverse((
+                                FloatOrd(c.bounds.dist_to_point(pt)),
+                                entries.len() - 1,
+                            )));
+                        }
+                    }
+                }
+            }
+        }
+        None
+    }
+
+    /// Every pair of indices (i, j), i < j, whose bounds intersect. These are
+    /// the only pairs which could possibly overlap.
+    pub fn candidate_pairs(&self) -> Vec<(usize, usize)> {
+        let mut pairs = vec![];
+        for (i, (b, _)) in self.items.iter().enumerate() {
+            pairs.extend(self.query(b).into_iter().filter(|j| *j > i).map(|j| (i, j)));
+        }
+        pairs
+    }
+}
+
+impl<T: Bounded> QuadTree<T> {
+    /// Builds a quadtree of bounded objects, keyed on their own bounds.
+    pub fn from_bounded(items: impl IntoIterator<Item = T>) -> Result<QuadTree<T>> {
+        Ok(QuadTree::from_items(
+            items
+                .into_iter()
+                .map(|t| Ok((t.bounds()?, t)))
+                .collect::<Result<Vec<_>>>()?,
+        ))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::{polygon::Rect, segment::Segment};
+
+    fn bruteforce_query(sgs: &[Segment], window: &Bounds) -> Vec<usize> {
+        (0..sgs.len())
+            .filter(|i| sgs[*i].bounds().unwrap().intersects(window))
+            .collect()
+    }
+
+    fn grid_of_segments() -> Vec<Segment> {
+        (0..30)
+            .flat_map(|i| {
+                (0..30).map(move |j| {
+                    let p = Point(i as f64 * 3.0, j as f64 * 2.0);
+                    Segment(p, p + Point((i % 3) as f64, (j % 4) as f64))
+                })
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_query_matches_bruteforce() -> Result<()> {
+        let sgs = grid_of_segments();
+        let qt = QuadTree::from_bounded(sgs.clone())?;
+        assert_eq!(qt.len(), 900);
+        for window in [
+            Rect((10, 10), (5, 5))?.bounds()?,
+            Rect((-5, -5), (6, 6))?.bounds()?,
+            Rect((0, 0), (100, 100))?.bounds()?,
+            Rect((200, 200), (1, 1))?.bounds()?,
+        ] {
+            assert_eq!(qt.query(&window), bruteforce_query(&sgs, &window));
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_incremental_insert_grows_root() -> Result<()> {
+        let mut qt: QuadTree<usize> = QuadTree::new();
+        for (i, sg) in grid_of_segments().iter().enumerate().rev() {
+            assert_eq!(qt.insert(sg.bounds()?, i), 899 - i);
+        }
+        let window = Rect((0, 0), (1, 1))?.bounds()?;
+        let found: Vec<usize> = qt
+            .query(&window)
+            .iter()
+            .map(|i| qt.get(*i).unwrap().1)
+            .collect();
+        assert!(found.contains(&0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_nearest() -> Result<()> {
+        let sgs = grid_of_segments();
+        let qt = QuadTree::from_bounded(sgs.clone())?;
+        let pt = Point(40.1, -7);
+        let expected = (0..sgs.len())
+            .min_by_key(|i| FloatOrd(sgs[*i].bounds().unwrap().dist_to_point(&pt)))
+            .unwrap();
+        let actual = qt.nearest(&pt).unwrap();
+        assert_eq!(
+            sgs[actual].bounds()?.dist_to_point(&pt),
+            sgs[expected].bounds()?.dist_to_point(&pt)
+        );
+        assert_eq!(QuadTree::<()>::new().nearest(&pt), None);
+        Ok(())
+    }
+
+    #[test]
+    fn test_candidate_pairs() -> Result<()> {
+        let sgs = vec![
+            Segment((0, 0), (1, 1)),
+            Segment((1, 1), (2, 0)),
+            Segment((5, 5), (6, 6)),
+            Segment((0.5, 0), (0.5, 2)),
+        ];
+        let qt = QuadTree::from_bounded(sgs)?;
+        assert_eq!(qt.candidate_pairs(), vec![(0, 1), (0, 3)]);
+        Ok(())
+    }
+}
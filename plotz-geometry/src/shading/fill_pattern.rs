@@ -0,0 +1,447 @@
+//! Fill patterns beyond parallel hatching.
+
+use super::{clip_polyline, clip_to, dist_to_boundary, hatch, inside, project, steps, Boundary};
+use crate::{
+    offset::{offset_polygon, JoinStyle},
+    shading::shade_config::ShadeConfig,
+    shapes::{point::Point, polygon::Polygon, segment::Segment},
+    utils::splitmix64,
+};
+use std::f64::consts::{FRAC_PI_2, PI, TAU};
+
+/// What to draw when shading a shape. Every pattern is clipped to the shape,
+/// and spaced according to the |ShadeConfig|'s gap.
+#[derive(Debug, Copy, Clone, PartialEq, Default)]
+pub enum FillPattern {
+    /// Parallel lines along the config's angle.
+    #[default]
+    Lines,
+
+    /// Parallel lines in |n| directions, evenly spaced around the half-turn,
+    /// starting from the config's angle.
+    CrossHatch {
+        /// How many directions.
+        n: usize,
+    },
+
+    /// Rings following the outline of the shape (and of its holes), each one
+    /// gap further inside.
+    Concentric,
+
+    /// An Archimedean spiral out from the middle of the shape, whose turns
+    /// are one gap apart.
+    Spiral,
+
+    /// Parallel sine waves along the config's angle.
+    Wavy {
+        /// The height of a crest above the midline.
+        amplitude: f64,
+        /// The distance from crest to crest.
+        wavelength: f64,
+    },
+
+    /// Parallel zig-zags along the config's angle.
+    ZigZag {
+        /// The height of a peak above the midline.
+        amplitude: f64,
+        /// The distance from peak to peak.
+        wavelength: f64,
+    },
+
+    /// A grid of dots, one gap apart, aligned with the config's angle and
+    /// anchor. Each dot is a short dash along the angle.
+    Dots {
+        /// The length of each dash.
+        size: f64,
+    },
+
+    /// A Hilbert curve whose steps are at most one gap long.
+    Hilbert,
+
+    /// A Peano curve whose steps are at most one gap long.
+    Peano,
+
+    /// Truchet tiles one gap across, cornered on the config's anchor, each
+    /// holding two quarter circles in one of two orientations, picked
+    /// pseudorandomly from |seed|.
+    Truchet {
+        /// Seeds the choice of orientations.
+        seed: u64,
+    },
+}
+
+// Space-filling curves get no finer than this, however small the gap.
+const MAX_HILBERT_ORDER: u32 = 9;
+const MAX_PEANO_ORDER: u32 = 6;
+
+impl FillPattern {
+    // Fills the region inside |bs|.
+    pub(super) fn fill(&self, config: &ShadeConfig, bs: &[Boundary]) -> Vec<Segment> {
+        let gap = config.gap;
+        match *self {
+            FillPattern::Lines => hatch(config.direction(), gap, config.anchor, bs),
+            FillPattern::CrossHatch { n } => (0..n.max(1))
+                .flat_map(|k| {
+                    let turned = ShadeConfig {
+                        angle: Some(config.angle_radians() + k as f64 * PI / n.max(1) as f64),
+                        ..*config
+                    };
+                    hatch(turned.direction(), gap, config.anchor, bs)
+                })
+                .collect(),
+            FillPattern::Concentric => concentric(gap, bs),
+            FillPattern::Spiral => spiral(gap, bs),
+            FillPattern::Wavy {
+                amplitude,
+                wavelength,
+            } => waves(
+                config,
+                bs,
+                amplitude,
+                |u| amplitude * (TAU * u / wavelength).sin(),
+                wavelength / 16.0,
+            ),
+            FillPattern::ZigZag {
+                amplitude,
+                wavelength,
+            } => waves(
+                config,
+                bs,
+                amplitude,
+                |u| {
+                    // a triangle wave, peaking at odd quarter-wavelengths.
+                    let phase = (u / wavelength + 0.25).rem_euclid(1.0);
+                    amplitude * (1.0 - 4.0 * (phase - 0.5).abs())
+                },
+                wavelength / 4.0,
+            ),
+            FillPattern::Dots { size } => dots(config, bs, size),
+            FillPattern::Hilbert => space_filling(gap, bs, 2, MAX_HILBERT_ORDER, hilbert_curve),
+            FillPattern::Peano => space_filling(gap, bs, 3, MAX_PEANO_ORDER, peano_curve),
+            FillPattern::Truchet { seed } => truchet(gap, config.anchor, bs, seed),
+        }
+    }
+}
+
+// The axis-aligned bounds of |bs|, as (x_min, x_max, y_min, y_max).
+fn extent(bs: &[Boundary]) -> (f64, f64, f64, f64) {
+    let (x_min, x_max) = project(bs, &Point(1, 0));
+    let (y_min, y_max) = project(bs, &Point(0, 1));
+    (x_min, x_max, y_min, y_max)
+}
+
+fn concentric(gap: f64, bs: &[Boundary]) -> Vec<Segment> {
+    let rings: Vec<(Polygon, bool)> = bs
+        .iter()
+        .enumerate()
+        .filter_map(|(i, b)| {
+            let ring = b.to_ring();
+            // a ring inside of an odd number of the others is a hole.
+            let others: Vec<Boundary> = bs
+                .iter()
+                .enumerate()
+                .filter(|(j, _)| *j != i)
+                .map(|(_, b)| Boundary::Ring(b.to_ring()))
+                .collect();
+            let is_hole = inside(&ring[0], &others);
+            Polygon(ring).ok().map(|pg| (pg, is_hole))
+        })
+        .collect();
+
+    let mut segments = vec![];
+    for k in 1.. {
+        let d = k as f64 * gap;
+        let mut found = false;
+        for (pg, is_hole) in &rings {
+            let offset = if *is_hole { d } else { -d };
+            for contour in offset_polygon(pg, offset, JoinStyle::Miter(4.0)) {
+                let mut pts = contour.pts.clone();
+                pts.push(pts[0]);
+                // contours of one ring may cross into another's margin.
+                let kept = clip_polyline(&pts, bs, |pt| {
+                    inside(pt, bs) && dist_to_boundary(pt, bs) >= d * (1.0 - 1e-6)
+                });
+                // contours around holes grow forever; stop once none are kept.
+                found |= !kept.is_empty();
+                segments.extend(kept);
+            }
+        }
+        if !found {
+            break;
+        }
+    }
+    segments
+}
+
+fn spiral(gap: f64, bs: &[Boundary]) -> Vec<Segment> {
+    let (x_min, x_max, y_min, y_max) = extent(bs);
+    let ctr = Point((x_min + x_max) / 2.0, (y_min + y_max) / 2.0);
+    let r_max = Point(x_min, y_min).dist(&ctr);
+
+    // r = gap * theta / TAU; step so that no chord is longer than a quarter
+    // gap, or a sixty-fourth of a turn.
+    let mut pts = vec![ctr];
+    let mut theta: f64 = 0.0;
+    loop {
+        let r = gap * theta / TAU;
+        if r > r_max {
+            break;
+        }
+        theta += (TAU / 64.0).min(gap / 4.0 / r.max(gap));
+        let r = gap * theta / TAU;
+        pts.push(ctr + Point(theta.cos(), theta.sin()) * r);
+    }
+    clip_to(&pts, bs)
+}
+
+// Lines along the config's angle, one gap apart, displaced across themselves
+// by |wave| of the distance along them, sampled every |step|.
+fn waves(
+    config: &ShadeConfig,
+    bs: &[Boundary],
+    amplitude: f64,
+    wave: impl Fn(f64) -> f64,
+    step: f64,
+) -> Vec<Segment> {
+    let d = config.direction();
+    let n = Point(-d.y, d.x);
+    let (u_min, u_max) = project(bs, &d);
+    let (s_min, s_max) = project(bs, &n);
+    if step <= 0.0 || u_min > u_max {
+        return vec![];
+    }
+    let (s_lo, s_hi) = (s_min - amplitude.abs(), s_max + amplitude.abs());
+
+    let mut segments = vec![];
+    for s in steps(s_lo, s_hi, config.gap, config.anchor.dot(&n)).rev() {
+        let u0 = config.anchor.dot(&d);
+        let pts: Vec<Point> = steps(u_min - step, u_max + step, step, u0)
+            .map(|u| d * u + n * (s + wave(u - u0)))
+            .collect();
+        segments.extend(clip_to(&pts, bs));
+    }
+    segments
+}
+
+fn dots(config: &ShadeConfig, bs: &[Boundary], size: f64) -> Vec<Segment> {
+    let gap = config.gap;
+    let d = config.direction();
+    let n = Point(-d.y, d.x);
+    let (u_min, u_max) = project(bs, &d);
+    let (s_min, s_max) = project(bs, &n);
+
+    let mut segments = vec![];
+    for s in steps(s_min, s_max, gap, config.anchor.dot(&n)).rev() {
+        for u in steps(u_min, u_max, gap, config.anchor.dot(&d)) {
+            let pt = d * u + n * s;
+            if inside(&pt, bs) {
+                segments.extend(clip_to(&[pt - d * (size / 2.0), pt + d * (size / 2.0)], bs));
+            }
+        }
+    }
+    segments
+}
+
+// The |i|th cell visited by the Hilbert curve of order |order|.
+fn hilbert_curve(order: u32, i: usize) -> (usize, usize) {
+    let (mut x, mut y, mut t) = (0, 0, i);
+    let mut s = 1;
+    while s < 1 << order {
+        let rx = 1 & (t / 2);
+        let ry = 1 & (t ^ rx);
+        if ry == 0 {
+            if rx == 1 {
+                x = s - 1 - x;
+                y = s - 1 - y;
+            }
+            std::mem::swap(&mut x, &mut y);
+        }
+        x += s * rx;
+        y += s * ry;
+        t /= 4;
+        s *= 2;
+    }
+    (x, y)
+}
+
+// The |i|th cell visited by the Peano curve of order |order|.
+fn peano_curve(order: u32, i: usize) -> (usize, usize) {
+    // read |i| in base 3, most significant first, as pairs of digits (a, b).
+    let digits: Vec<usize> = (0..2 * order)
+        .rev()
+        .map(|p| (i / 3usize.pow(p)) % 3)
+        .collect();
+    let (mut x, mut y) = (0, 0);
+    let (mut sum_a, mut sum_b) = (0, 0);
+    for pair in digits.chunks_exact(2) {
+        let (a, b) = (pair[0], pair[1]);
+        // each digit is reflected by the parity of the others before it.
+        x = 3 * x + if sum_b % 2 == 0 { a } else { 2 - a };
+        sum_a += a;
+        y = 3 * y + if sum_a % 2 == 0 { b } else { 2 - b };
+        sum_b += b;
+    }
+    (x, y)
+}
+
+// A space-filling curve over a square covering |bs|, of the least order whose
+// cells are at most |gap| across.
+fn space_filling(
+    gap: f64,
+    bs: &[Boundary],
+    base: usize,
+    max_order: u32,
+    curve: fn(u32, usize) -> (usize, usize),
+) -> Vec<Segment> {
+    let (x_min, x_max, y_min, y_max) = extent(bs);
+    let side = (x_max - x_min).max(y_max - y_min);
+    let mut order = 1;
+    while order < max_order && side / (base.pow(order) as f64) > gap {
+        order += 1;
+    }
+    let cells = base.pow(order);
+    let cell = side / cells as f64;
+    let pts: Vec<Point> = (0..cells * cells)
+        .map(|i| {
+            let (x, y) = curve(order, i);
+            Point(
+                x_min + (x as f64 + 0.5) * cell,
+                y_min + (y as f64 + 0.5) * cell,
+            )
+        })
+        .collect();
+    clip_to(&pts, bs)
+}
+
+fn truchet(gap: f64, anchor: Point, bs: &[Boundary], seed: u64) -> Vec<Segment> {
+    let (x_min, x_max, y_min, y_max) = extent(bs);
+    let r = gap / 2.0;
+    // a quarter circle about |ctr|, starting at |angle|.
+    let quarter = |ctr: Point, angle: f64| -> Vec<Point> {
+        (0..=8)
+            .map(|k| {
+                let a = angle + FRAC_PI_2 * k as f64 / 8.0;
+                ctr + Point(a.cos(), a.sin()) * r
+            })
+            .collect()
+    };
+
+    let mut segments = vec![];
+    for j in ((y_min - anchor.y) / gap).floor() as i64..((y_max - anchor.y) / gap).ceil() as i64 {
+        for i in ((x_min - anchor.x) / gap).floor() as i64..((x_max - anchor.x) / gap).ceil() as i64
+        {
+            let o = anchor + Point(i as f64 * gap, j as f64 * gap);
+            let flip = splitmix64(splitmix64(seed ^ i as u64) ^ j as u64) & 1 == 1;
+            let arcs = if flip {
+                [quarter(o, 0.0), quarter(o + Point(gap, gap), PI)]
+            } else {
+                [
+                    quarter(o + Point(gap, 0.0), FRAC_PI_2),
+                    quarter(o + Point(0.0, gap), -FRAC_PI_2),
+                ]
+            };
+            for arc in arcs {
+                segments.extend(clip_to(&arc, bs));
+            }
+        }
+    }
+    segments
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        obj2::Obj2,
+        shading::shade,
+        shapes::{curve::CurveArc, polygon::Rect, polygon_with_cavity::PolygonWithCavities},
+    };
+    use anyhow::Result;
+    use float_eq::assert_float_eq;
+    use test_case::test_case;
+
+    fn total_length(sgs: &[Segment]) -> f64 {
+        sgs.iter().map(|sg| sg.length()).sum()
+    }
+
+    fn config(pattern: FillPattern) -> ShadeConfig {
+        ShadeConfig::builder()
+            .gap(0.5)
+            .slope(0.3)
+            .pattern(pattern)
+            .build()
+    }
+
+    #[test_case(FillPattern::CrossHatch { n: 3 }; "crosshatch")]
+    #[test_case(FillPattern::Concentric; "concentric")]
+    #[test_case(FillPattern::Spiral; "spiral")]
+    #[test_case(FillPattern::Wavy { amplitude: 0.2, wavelength: 1.0 }; "wavy")]
+    #[test_case(FillPattern::ZigZag { amplitude: 0.2, wavelength: 1.0 }; "zigzag")]
+    #[test_case(FillPattern::Dots { size: 0.1 }; "dots")]
+    #[test_case(FillPattern::Hilbert; "hilbert")]
+    #[test_case(FillPattern::Peano; "peano")]
+    #[test_case(FillPattern::Truchet { seed: 3 }; "truchet")]
+    fn test_pattern_stays_inside(pattern: FillPattern) -> Result<()> {
+        let hole = Rect((3, 3), (3, 3))?;
+        let pgc = PolygonWithCavities(
+            Polygon([(0, 0), (10, 0), (10, 10), (5, 7), (0, 10)])?,
+            vec![hole.clone()],
+        )?;
+        let bs = vec![
+            Boundary::Ring(pgc.outer.pts.clone()),
+            Boundary::Ring(hole.pts.clone()),
+        ];
+        let sgs = shade(&config(pattern), &Obj2::PolygonWithCavities(pgc))?;
+        assert!(!sgs.is_empty());
+        for sg in &sgs {
+            assert!(inside(&sg.midpoint(), &bs), "{:?}", sg);
+        }
+
+        let circle = Obj2::CurveArc(CurveArc(Point(3, 4), 0.0..=TAU, 2.0));
+        let bs = vec![Boundary::Circle(Point(3, 4), 2.0)];
+        for sg in shade(&config(pattern), &circle)? {
+            assert!(inside(&sg.midpoint(), &bs), "{:?}", sg);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_crosshatch_draws_each_direction() -> Result<()> {
+        let square = Obj2::Polygon(Rect((0, 0), (10, 10))?);
+        let lines = total_length(&shade(&config(FillPattern::Lines), &square)?);
+        let crossed = total_length(&shade(&config(FillPattern::CrossHatch { n: 3 }), &square)?);
+        assert_float_eq!(crossed, 3.0 * lines, rmax <= 0.05);
+        Ok(())
+    }
+
+    #[test]
+    fn test_concentric_rings() -> Result<()> {
+        // a 10x10 square holds squares 9x9, 8x8, ... 1x1 at gap 0.5.
+        let square = Obj2::Polygon(Rect((0, 0), (10, 10))?);
+        let sgs = shade(&config(FillPattern::Concentric), &square)?;
+        let expected: f64 = (1..10).map(|k| 4.0 * (10.0 - k as f64)).sum();
+        assert_float_eq!(total_length(&sgs), expected, rmax <= 1e-6);
+        Ok(())
+    }
+
+    #[test_case(2, MAX_HILBERT_ORDER, hilbert_curve; "hilbert")]
+    #[test_case(3, MAX_PEANO_ORDER, peano_curve; "peano")]
+    fn test_space_filling_curves_are_continuous(
+        base: usize,
+        order: u32,
+        curve: fn(u32, usize) -> (usize, usize),
+    ) {
+        for order in 1..=order.min(4) {
+            let cells = base.pow(order);
+            let visited: Vec<(usize, usize)> =
+                (0..cells * cells).map(|i| curve(order, i)).collect();
+            for (a, b) in visited.iter().zip(visited.iter().skip(1)) {
+                assert_eq!(a.0.abs_diff(b.0) + a.1.abs_diff(b.1), 1, "{:?} {:?}", a, b);
+            }
+            let mut unique = visited.clone();
+            unique.sort();
+            unique.dedup();
+            assert_eq!(unique.len(), cells * cells);
+        }
+    }
+}
@@ -1,71 +1,502 @@
 //! Shading and crosshatching algorithms. Updated version.
 
+pub mod fill_pattern;
 pub mod shade_config;
 
 use crate::{
-    bounded::Bounded,
-    crop::Croppable,
-    shading::shade_config::ShadeConfig,
-    shapes::{point::Point, polygon::Polygon, segment::Segment},
+    obj2::Obj2,
+    shading::{fill_pattern::FillPattern, shade_config::ShadeConfig},
+    shapes::{curve::CurveArc, point::Point, polygon::Polygon, segment::Segment},
 };
-use anyhow::Result;
-use float_ord::FloatOrd;
+use anyhow::{anyhow, Result};
+use std::f64::consts::TAU;
 
-fn compute_vertical_step(gap: f64, slope: f64) -> f64 {
-    gap * (slope.powi(2) + 1.0).sqrt()
+// The boundary of a closed region: rings of points, and whole circles.
+enum Boundary {
+    Ring(Vec<Point>),
+    Circle(Point, f64),
 }
 
+impl Boundary {
+    // the least and greatest extent of this boundary along |d|.
+    fn project(&self, d: &Point) -> (f64, f64) {
+        match self {
+            Boundary::Ring(pts) => pts.iter().fold((f64::MAX, f64::MIN), |(lo, hi), pt| {
+                (lo.min(pt.dot(d)), hi.max(pt.dot(d)))
+            }),
+            Boundary::Circle(ctr, r) => (ctr.dot(d) - r, ctr.dot(d) + r),
+        }
+    }
+
+    // this boundary as a ring of points; circles are sampled.
+    fn to_ring(&self) -> Vec<Point> {
+        match self {
+            Boundary::Ring(pts) => pts.clone(),
+            Boundary::Circle(ctr, r) => (0..256)
+                .map(|k| {
+                    *ctr + Point(
+                        (k as f64 / 256.0 * TAU).cos(),
+                        (k as f64 / 256.0 * TAU).sin(),
+                    ) * *r
+                })
+                .collect(),
+        }
+    }
+}
+
+fn is_full_circle(ca: &CurveArc) -> bool {
+    (ca.angle_f - ca.angle_i).abs() >= TAU - f64::EPSILON
+}
+
+// The boundaries of a closed object, or an error if it isn't closed.
+fn boundaries(obj: &Obj2) -> Result<Vec<Boundary>> {
+    match obj {
+        Obj2::Polygon(pg) => Ok(vec![Boundary::Ring(pg.pts.clone())]),
+        Obj2::PolygonWithCavities(pgc) => Ok(std::iter::once(&pgc.outer)
+            .chain(pgc.inner.iter())
+            .map(|pg| Boundary::Ring(pg.pts.clone()))
+            .collect()),
+        Obj2::CurveArc(ca) if is_full_circle(ca) => Ok(vec![Boundary::Circle(ca.ctr, ca.radius)]),
+        _ => Err(anyhow!("can't shade an open shape")),
+    }
+}
+
+// The least and greatest extent of all of |bs| along |d|.
+fn project(bs: &[Boundary], d: &Point) -> (f64, f64) {
+    bs.iter()
+        .map(|b| b.project(d))
+        .fold((f64::MAX, f64::MIN), |(lo, hi), (l, h)| {
+            (lo.min(l), hi.max(h))
+        })
+}
+
+// True if |pt| is inside of |bs|, by the even-odd rule.
+fn inside(pt: &Point, bs: &[Boundary]) -> bool {
+    let mut inside = false;
+    for b in bs {
+        match b {
+            Boundary::Ring(pts) => {
+                for (p, q) in pts.iter().zip(pts.iter().cycle().skip(1)) {
+                    if (p.y > pt.y) != (q.y > pt.y)
+                        && pt.x < p.x + (pt.y - p.y) / (q.y - p.y) * (q.x - p.x)
+                    {
+                        inside = !inside;
+                    }
+                }
+            }
+            Boundary::Circle(ctr, r) => {
+                if pt.dist(ctr) < *r {
+                    inside = !inside;
+                }
+            }
+        }
+    }
+    inside
+}
+
+// The distance from |pt| to the nearest boundary.
+fn dist_to_boundary(pt: &Point, bs: &[Boundary]) -> f64 {
+    bs.iter()
+        .map(|b| match b {
+            Boundary::Ring(pts) => pts
+                .iter()
+                .zip(pts.iter().cycle().skip(1))
+                .map(|(p, q)| {
+                    let d = *q - *p;
+                    let t = ((*pt - *p).dot(&d) / d.dot(&d)).clamp(0.0, 1.0);
+                    pt.dist(&(*p + d * t))
+                })
+                .fold(f64::MAX, f64::min),
+            Boundary::Circle(ctr, r) => (pt.dist(ctr) - r).abs(),
+        })
+        .fold(f64::MAX, f64::min)
+}
+
+// The fractions along |p|->|q| at which it crosses any of |bs|.
+fn crossings(p: &Point, q: &Point, bs: &[Boundary]) -> Vec<f64> {
+    let r = *q - *p;
+    let mut ts = vec![];
+    for b in bs {
+        match b {
+            Boundary::Ring(pts) => {
+                for (a, b) in pts.iter().zip(pts.iter().cycle().skip(1)) {
+                    let e = *b - *a;
+                    let denom = r.cross_z(&e);
+                    if denom == 0.0 {
+                        continue;
+                    }
+                    let t = (*a - *p).cross_z(&e) / denom;
+                    let u = (*a - *p).cross_z(&r) / denom;
+                    if (0.0..=1.0).contains(&u) && t > 0.0 && t < 1.0 {
+                        ts.push(t);
+                    }
+                }
+            }
+            Boundary::Circle(ctr, rad) => {
+                // |p + t r - ctr|^2 = rad^2
+                let f = *p - *ctr;
+                let (a, b, c) = (r.dot(&r), 2.0 * f.dot(&r), f.dot(&f) - rad * rad);
+                let disc = b * b - 4.0 * a * c;
+                if a > 0.0 && disc > 0.0 {
+                    for t in [
+                        (-b - disc.sqrt()) / (2.0 * a),
+                        (-b + disc.sqrt()) / (2.0 * a),
+                    ] {
+                        if t > 0.0 && t < 1.0 {
+                            ts.push(t);
+                        }
+                    }
+                }
+            }
+        }
+    }
+    ts
+}
+
+// Clips a polyline to the parts of it for which |keep| holds (judged at the
+// midpoint of each piece), cutting wherever it crosses |cuts|.
+fn clip_polyline(pts: &[Point], cuts: &[Boundary], keep: impl Fn(&Point) -> bool) -> Vec<Segment> {
+    let mut segments = vec![];
+    for (p, q) in pts.iter().zip(pts.iter().skip(1)) {
+        let mut ts = crossings(p, q, cuts);
+        ts.extend([0.0, 1.0]);
+        ts.sort_by(f64::total_cmp);
+        for (t0, t1) in ts.iter().zip(ts.iter().skip(1)) {
+            if t1 > t0 {
+                let (a, b) = (*p + (*q - *p) * *t0, *p + (*q - *p) * *t1);
+                if keep(&a.avg(&b)) {
+                    segments.push(Segment(a, b));
+                }
+            }
+        }
+    }
+    segments
+}
+
+// Clips a polyline to the inside of |bs|.
+fn clip_to(pts: &[Point], bs: &[Boundary]) -> Vec<Segment> {
+    clip_polyline(pts, bs, |pt| inside(pt, bs))
+}
+
+// The values offset + k * gap within [lo, hi], least first.
+fn steps(lo: f64, hi: f64, gap: f64, offset: f64) -> impl DoubleEndedIterator<Item = f64> {
+    let (k_lo, k_hi) = (
+        ((lo - offset) / gap).ceil() as i64,
+        ((hi - offset) / gap).floor() as i64,
+    );
+    (k_lo..=k_hi).map(move |k| offset + k as f64 * gap)
+}
+
+// Hatches the region inside |bs| with lines along |d|, |gap| apart and one
+// of them through |anchor|, by the even-odd rule, so that holes are skipped.
+// Lines run top to bottom, each left to right.
+fn hatch(d: Point, gap: f64, anchor: Point, bs: &[Boundary]) -> Vec<Segment> {
+    // |n| runs across the lines.
+    let n = Point(-d.y, d.x);
+    let (s_min, s_max) = project(bs, &n);
+    if s_min > s_max {
+        return vec![];
+    }
+
+    let mut segments = vec![];
+    for s in steps(s_min, s_max, gap, anchor.dot(&n)).rev() {
+        // where this line crosses each boundary, as distances along |d|.
+        let mut us: Vec<f64> = vec![];
+        for b in bs {
+            match b {
+                Boundary::Ring(pts) => {
+                    for (p, q) in pts.iter().zip(pts.iter().cycle().skip(1)) {
+                        let (sp, sq) = (p.dot(&n), q.dot(&n));
+                        if (sp <= s) != (sq <= s) {
+                            let t = (s - sp) / (sq - sp);
+                            us.push((*p + (*q - *p) * t).dot(&d));
+                        }
+                    }
+                }
+                Boundary::Circle(ctr, r) => {
+                    let h = s - ctr.dot(&n);
+                    if h.abs() < *r {
+                        let w = (r * r - h * h).sqrt();
+                        us.extend([ctr.dot(&d) - w, ctr.dot(&d) + w]);
+                    }
+                }
+            }
+        }
+        us.sort_by(f64::total_cmp);
+        for pair in us.chunks_exact(2) {
+            if pair[1] > pair[0] {
+                segments.push(Segment(n * s + d * pair[0], n * s + d * pair[1]));
+            }
+        }
+    }
+    segments
+}
+
+// True if all of |sg| lies inside |bs|, but for pieces within |slack| of the
+// boundary (as where it meets the boundary at its ends).
+fn stays_inside(sg: &Segment, bs: &[Boundary], slack: f64) -> bool {
+    clip_polyline(&[sg.i, sg.f], bs, |pt| {
+        !inside(pt, bs) && dist_to_boundary(pt, bs) > slack
+    })
+    .is_empty()
+}
+
+// Joins each hatch to the next, zigzagging, wherever the join stays inside
+// |bs|. Hatches which can't be joined to either neighbor are kept as they are.
+fn switchback(segments: Vec<Segment>, bs: &[Boundary], gap: f64) -> Vec<Segment> {
+    let slack = gap * 1e-6;
+    let mut out = vec![];
+    // whether the last hatch seen was already drawn as part of a join.
+    let mut joined = false;
+    for (k, (sa, sb)) in segments.iter().zip(segments.iter().skip(1)).enumerate() {
+        let join = if k % 2 == 0 {
+            Segment(sa.i, sb.f)
+        } else {
+            Segment(sb.i, sa.f)
+        };
+        if stays_inside(&join, bs, slack) {
+            out.push(join);
+            joined = true;
+        } else {
+            if !joined {
+                out.push(*sa);
+            }
+            joined = false;
+        }
+    }
+    if let (false, Some(last)) = (joined, segments.last()) {
+        out.push(*last);
+    }
+    out
+}
+
+/// True if |shade| can shade |obj|: if it is closed, or is a group (whose
+/// open members are skipped).
+pub fn can_shade(obj: &Obj2) -> bool {
+    matches!(obj, Obj2::Group(_)) || boundaries(obj).is_ok()
+}
+
+/// Shades any closed object: polygons, polygons with cavities (whose cavities
+/// are left unshaded), and whole circles. Groups shade each of their closed
+/// members and skip the rest. Anything else is an error.
+///
 /// Gap controls how far to step between crosshatched lines
-/// Slope controls the angle of the lines.
-// TODO(jbuckland): shade Pgc?
+/// Angle (or slope) controls the angle of the lines.
+/// Anchor fixes where the lines fall, so that shapes shaded with the same
+/// config share the same lines.
+/// Pattern controls what is drawn; see |FillPattern|.
+pub fn shade(config: &ShadeConfig, obj: &Obj2) -> Result<Vec<Segment>> {
+    if let Obj2::Group(g) = obj {
+        let mut segments = vec![];
+        for (o, _) in g.iter_objects() {
+            if can_shade(o) {
+                segments.extend(shade(config, o)?);
+            }
+        }
+        return Ok(segments);
+    }
+    if config.gap <= 0.0 {
+        return Err(anyhow!("gap must be positive"));
+    }
+    let bs = boundaries(obj)?;
+    Ok(match config.pattern {
+        FillPattern::Lines => {
+            let segments = hatch(config.direction(), config.gap, config.anchor, &bs);
+            if config.switchback {
+                switchback(segments, &bs, config.gap)
+            } else {
+                segments
+            }
+        }
+        pattern => pattern.fill(config, &bs),
+    })
+}
+
+/// Shades a polygon. See |shade|.
 pub fn shade_polygon(config: &ShadeConfig, polygon: &Polygon) -> Result<Vec<Segment>> {
-    let bounds = polygon.bounds()?;
-    let mut segments: Vec<Segment> = vec![];
-
-    let xnudge = Point(1, -1);
-    let ynudge = Point(-1, 1);
-    let mut line = if config.slope > 0.0 {
-        Segment(
-            bounds.x_min_y_max() - xnudge,
-            bounds.x_min_y_max() + (bounds.x_span(), bounds.x_span() * config.slope) + xnudge,
-        )
-    } else {
-        Segment(
-            bounds.x_max_y_max() - ynudge,
-            bounds.x_max_y_max()
-                + (
-                    -1.0 * bounds.x_span(),
-                    -1.0 * bounds.x_span() * config.slope,
-                )
-                + ynudge,
-        )
+    shade(config, &Obj2::Polygon(polygon.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        containment::{default_tolerance, Locate},
+        crop::PointLocation,
+        group::Group,
+        shapes::{polygon::Rect, polygon_with_cavity::PolygonWithCavities},
+        style::Style,
     };
+    use float_eq::assert_float_eq;
+
+    fn total_length(sgs: &[Segment]) -> f64 {
+        sgs.iter().map(|sg| sg.length()).sum()
+    }
 
-    let step = compute_vertical_step(config.gap, config.slope);
+    #[test]
+    fn test_shade_polygon() -> Result<()> {
+        let config = ShadeConfig::builder().gap(1.0).slope(0.0).build();
+        let sgs = shade_polygon(&config, &Rect((0.5, 0.5), (10, 10))?)?;
+        assert_eq!(sgs.len(), 10);
+        assert_eq!(sgs[0], Segment((0.5, 10), (10.5, 10)));
+        assert_eq!(sgs[9], Segment((0.5, 1), (10.5, 1)));
 
-    while FloatOrd(line.i.y) > FloatOrd(bounds.y_min) || FloatOrd(line.f.y) > FloatOrd(bounds.y_min)
-    {
-        let cropped_strokes = line.crop_to(polygon)?;
-        segments.extend(cropped_strokes.iter());
-        // segments.push(line);
+        // diagonal and vertical hatches cover the same area.
+        for slope in [1.0, -0.5, f64::INFINITY] {
+            let config = ShadeConfig::builder().gap(0.1).slope(slope).build();
+            let sgs = shade_polygon(&config, &Rect((0, 0), (10, 10))?)?;
+            assert_float_eq!(total_length(&sgs) * 0.1, 100.0, rmax <= 0.02);
+        }
+        Ok(())
+    }
 
-        line -= (0, step);
+    #[test]
+    fn test_shade_polygon_with_cavities_skips_holes() -> Result<()> {
+        let pgc = PolygonWithCavities(Rect((0, 0), (10, 10))?, vec![Rect((2, 2), (4, 4))?])?;
+        let config = ShadeConfig::builder().gap(0.1).slope(0.3).build();
+        let sgs = shade(&config, &Obj2::PolygonWithCavities(pgc))?;
+        assert_float_eq!(total_length(&sgs) * 0.1, 100.0 - 16.0, rmax <= 0.02);
+        let hole = Rect((2, 2), (4, 4))?;
+        for sg in sgs {
+            assert!(!hole.strictly_contains(&sg.midpoint(), default_tolerance())?);
+        }
+        Ok(())
     }
 
-    if config.switchback {
-        Ok(segments
-            .iter()
-            .zip(segments.iter().skip(1))
-            .zip([true, false].iter().cycle())
-            .map(|((sa, sb), should_alternate)| {
-                if *should_alternate {
-                    Segment(sa.i, sb.f)
-                } else {
-                    Segment(sb.i, sa.f)
+    #[test]
+    fn test_switchback_stays_inside() -> Result<()> {
+        let config = ShadeConfig::builder()
+            .gap(0.5)
+            .slope(0.2)
+            .switchback(true)
+            .build();
+
+        // a square has nothing to go around: every hatch joins the next.
+        let square = Obj2::Polygon(Rect((0, 0), (10, 10))?);
+        let hatches = shade(
+            &ShadeConfig {
+                switchback: false,
+                ..config
+            },
+            &square,
+        )?;
+        assert_eq!(shade(&config, &square)?.len(), hatches.len() - 1);
+
+        // a square with a hole, and a U.
+        let outer = Rect((0, 0), (10, 10))?;
+        let hole = Rect((2, 2), (4, 4))?;
+        let u = Polygon([
+            (0, 0),
+            (10, 0),
+            (10, 10),
+            (7, 10),
+            (7, 3),
+            (3, 3),
+            (3, 10),
+            (0, 10),
+        ])?;
+        for (obj, outside) in [
+            (
+                Obj2::PolygonWithCavities(PolygonWithCavities(outer.clone(), vec![hole.clone()])?),
+                vec![hole],
+            ),
+            (Obj2::Polygon(u), vec![Rect((3, 3), (4, 7))?]),
+        ] {
+            let sgs = shade(&config, &obj)?;
+            assert!(!sgs.is_empty());
+            for sg in sgs {
+                for k in 0..=10 {
+                    let pt = sg.i + (sg.f - sg.i) * (k as f64 / 10.0);
+                    // hatches end on the boundary, give or take rounding.
+                    for pg in &outside {
+                        assert!(!pg.strictly_contains(&pt, 1e-9)?);
+                    }
+                    assert!(outer.locate(&pt, 1e-9)? != PointLocation::Outside);
                 }
-            })
-            .collect())
-    } else {
-        Ok(segments)
+            }
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_shade_circle() -> Result<()> {
+        let config = ShadeConfig::builder().gap(0.01).slope(2.0).build();
+        let circle = CurveArc(Point(3, 4), 0.0..=TAU, 2.0);
+        let sgs = shade(&config, &Obj2::CurveArc(circle))?;
+        assert_float_eq!(total_length(&sgs) * 0.01, TAU * 2.0, rmax <= 0.01);
+
+        let arc = CurveArc(Point(3, 4), 0.0..=1.0, 2.0);
+        assert!(shade(&config, &Obj2::CurveArc(arc)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_shade_group() -> Result<()> {
+        let config = ShadeConfig::builder().gap(1.0).slope(0.0).build();
+        let group = Group::new([
+            (Obj2::Polygon(Rect((0.5, 0.5), (10, 10))?), Style::default()),
+            (Obj2::Segment(Segment((0, 0), (1, 1))), Style::default()),
+            (
+                Obj2::Polygon(Rect((20.5, 0.5), (10, 10))?),
+                Style::default(),
+            ),
+        ]);
+        assert_eq!(shade(&config, &Obj2::Group(group))?.len(), 20);
+        assert!(shade(&config, &Obj2::Point(Point(0, 0))).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_shade_by_angle() -> Result<()> {
+        let config = ShadeConfig::builder()
+            .gap(1.0)
+            .angle(90f64.to_radians())
+            .build();
+        let sgs = shade_polygon(&config, &Rect((0.5, 0.5), (10, 10))?)?;
+        assert_eq!(sgs.len(), 10);
+        for sg in &sgs {
+            assert_eq!(sg.i.x, sg.f.x);
+        }
+
+        // an angle overrides the slope.
+        let by_slope = ShadeConfig::builder().gap(0.5).slope(1.0).build();
+        let by_angle = ShadeConfig::builder()
+            .gap(0.5)
+            .slope(-3.0)
+            .angle(45f64.to_radians())
+            .build();
+        let pg = Rect((0, 0), (3, 7))?;
+        assert_eq!(
+            shade_polygon(&by_slope, &pg)?.len(),
+            shade_polygon(&by_angle, &pg)?.len()
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_hatches_line_up_across_neighbors() -> Result<()> {
+        let config = ShadeConfig::builder()
+            .gap(0.7)
+            .angle(30f64.to_radians())
+            .anchor(Point(0.3, 0.1))
+            .build();
+        let left = shade_polygon(&config, &Rect((0, 0), (5, 5))?)?;
+        let right = shade_polygon(&config, &Rect((5, 0), (5, 5))?)?;
+
+        // every line leaving the left square carries on into the right one.
+        let leaving: Vec<Point> = left.iter().map(|sg| sg.f).filter(|p| p.x == 5.0).collect();
+        assert!(!leaving.is_empty());
+        for pt in leaving {
+            assert!(right
+                .iter()
+                .any(|sg| sg.i.x == 5.0 && (sg.i.y - pt.y).abs() < 1e-9));
+        }
+
+        // and one of them passes through the anchor.
+        let d = config.direction();
+        assert!(left
+            .iter()
+            .any(|sg| (sg.i - Point(0.3, 0.1)).cross_z(&d).abs() < 1e-9));
+        Ok(())
     }
 }
@@ -1,5 +1,7 @@
 //! A config for shading.
 
+use crate::{shading::fill_pattern::FillPattern, shapes::point::Point};
+use std::f64::consts::FRAC_PI_2;
 use typed_builder::TypedBuilder;
 
 /// Config for controlling crosshatching.
@@ -13,8 +15,21 @@ pub struct ShadeConfig {
     /// 1.0 is diagonal northeast (southwest).
     /// -1.0 is diagonal northwest (southeast).
     /// infinity is straight up-and-down.
+    /// Ignored if |angle| is set.
+    #[builder(default = 0.0)]
     pub slope: f64,
 
+    /// The angle of a line, in radians counterclockwise from flat. Takes
+    /// precedence over |slope|. For degrees, use |f64::to_radians|.
+    #[builder(default, setter(strip_option))]
+    pub angle: Option<f64>,
+
+    /// A point which one of the lines passes through. Shapes shaded with the
+    /// same config share the same lines, so hatching runs unbroken across
+    /// neighboring shapes.
+    #[builder(default = Point(0, 0))]
+    pub anchor: Point,
+
     /// The thickness of a line (SVG only.)
     #[builder(default = 1.0)]
     pub thickness: f64,
@@ -26,4 +41,27 @@ pub struct ShadeConfig {
     /// If in 3d, draws the shading along the face instead of along the camera plane.
     #[builder(default = false)]
     pub along_face: bool,
+
+    /// What to fill with. Parallel lines by default.
+    #[builder(default)]
+    pub pattern: FillPattern,
+}
+
+impl ShadeConfig {
+    /// The angle of a line, in radians, from |angle| or else |slope|.
+    pub fn angle_radians(&self) -> f64 {
+        match self.angle {
+            Some(angle) => angle,
+            None if self.slope.is_infinite() => FRAC_PI_2,
+            None => self.slope.atan(),
+        }
+    }
+
+    /// The unit vector along a line. Angles which are multiples of a right
+    /// angle (to within rounding) give exactly horizontal or vertical lines.
+    pub fn direction(&self) -> Point {
+        let (sin, cos) = self.angle_radians().sin_cos();
+        let snap = |v: f64| if v.abs() < 1e-15 { 0.0 } else { v };
+        Point(snap(cos), snap(sin))
+    }
 }
@@ -3,6 +3,7 @@
 
 use crate::{
     bounded::{Bounded, Bounds},
+    containment::{default_tolerance, Locate},
     crop::{CropType, Croppable, PointLocation},
     interpolate::interpolate_2d_checked,
     obj2::ObjType2d,
@@ -367,8 +368,6 @@ impl Croppable for CurveArc {
     where
         Self: Sized,
     {
-        assert_eq!(crop_type, CropType::Inclusive);
-
         let mut isxns: Vec<PtLoc> = vec![];
         for frame_segment in frame.to_segments() {
             let discovered = match intersections_of_line_and_curvearc(&frame_segment, self)? {
@@ -379,12 +378,16 @@ impl Croppable for CurveArc {
             isxns.extend(discovered);
         }
 
+        if crop_type == CropType::Exclusive {
+            return self.crop_excluding_at(frame, isxns);
+        }
+
         // either the curve is totally within and has no overlaps, or is totally
         // without and has no overlaps.
         if isxns.is_empty() {
             //
-            let contains_i = frame.contains_pt_deprecated(&self.pt_i());
-            let contains_f = frame.contains_pt_deprecated(&self.pt_f());
+            let contains_i = frame.locate(&self.pt_i(), default_tolerance());
+            let contains_f = frame.locate(&self.pt_f(), default_tolerance());
             if let (
                 Ok(PointLocation::Inside | PointLocation::OnSegment(_) | PointLocation::OnPoint(_)),
                 Ok(PointLocation::Inside | PointLocation::OnSegment(_) | PointLocation::OnPoint(_)),
@@ -401,13 +404,13 @@ impl Croppable for CurveArc {
             })
             .collect::<Vec<_>>();
         if !matches!(
-            frame.contains_pt_deprecated(&self.pt_i()),
+            frame.locate(&self.pt_i(), default_tolerance()),
             Ok(PointLocation::Outside)
         ) {
             isxns_angles.insert(0, FloatOrd(self.angle_i));
         }
         if !matches!(
-            frame.contains_pt_deprecated(&self.pt_f()),
+            frame.locate(&self.pt_f(), default_tolerance()),
             Ok(PointLocation::Outside)
         ) {
             isxns_angles.insert(0, FloatOrd(self.angle_f));
@@ -429,7 +432,7 @@ impl Croppable for CurveArc {
         {
             let mdpt = self.ctr + PolarPt(self.radius, (a1 + a2) / 2.0);
             if !matches!(
-                frame.contains_pt_deprecated(&mdpt),
+                frame.locate(&mdpt, default_tolerance()),
                 Ok(PointLocation::Outside)
             ) {
                 r.push(CurveArc(self.ctr, a1..=a2, self.radius));
@@ -440,6 +443,35 @@ impl Croppable for CurveArc {
     }
 }
 
+impl CurveArc {
+    // The pieces of this arc outside of |frame|, which it crosses at |isxns|.
+    fn crop_excluding_at(&self, frame: &Polygon, isxns: Vec<PtLoc>) -> Result<Vec<CurveArc>> {
+        let mut angles: Vec<FloatOrd<f64>> = isxns
+            .into_iter()
+            .map(|PtLoc(_, _, cl)| {
+                FloatOrd(self.angle_i + (self.angle_f - self.angle_i) * cl.as_f64())
+            })
+            .chain([FloatOrd(self.angle_i), FloatOrd(self.angle_f)])
+            .collect();
+        angles.sort();
+        angles.dedup_by(|a, b| approx_eq!(f64, a.0, b.0));
+
+        let mut r: Vec<CurveArc> = vec![];
+        for (a1, a2) in angles.iter().zip(angles.iter().skip(1)) {
+            let mdpt = self.ctr + PolarPt(self.radius, (a1.0 + a2.0) / 2.0);
+            if frame.locate(&mdpt, default_tolerance())? != PointLocation::Outside {
+                continue;
+            }
+            // pieces which meet are one piece.
+            match r.last_mut() {
+                Some(last) if approx_eq!(f64, last.angle_f, a1.0) => last.angle_f = a2.0,
+                _ => r.push(CurveArc(self.ctr, a1.0..=a2.0, self.radius)),
+            }
+        }
+        Ok(r)
+    }
+}
+
 impl Object for CurveArc {
     fn objtype(&self) -> ObjType2d {
         ObjType2d::CurveArc2d
@@ -454,6 +486,16 @@ impl Object for CurveArc {
         // NB: this can't impl. mutable transforms, it's not the whole picture.
         Box::new(std::iter::once(&mut self.ctr))
     }
+
+    fn convex_hull(&self) -> Result<Polygon> {
+        // |iter()| only knows about the center, so sample along the arc.
+        let (i, f) = (self.angle_i, self.angle_f);
+        let n = ((f - i).abs() / (TAU / 256.0)).ceil().max(2.0) as usize;
+        let pts: Vec<Point> = (0..=n)
+            .map(|k| self.ctr + PolarPt(self.radius, i + (f - i) * k as f64 / n as f64))
+            .collect();
+        crate::hull::convex_hull(&pts)
+    }
 }
 
 #[cfg(test)]
@@ -671,4 +713,23 @@ mod test {
             assert_approx_eq!(f64, *actual.end(), *expected.end());
         }
     }
+
+    #[test]
+    fn test_crop_excluding() -> Result<()> {
+        let frame = Polygon([(0, 0), (2, 0), (2, 2), (0, 2)])?;
+        // a circle about a corner of the frame keeps all but a quarter of it.
+        let circle = CurveArc(Point(0, 0), 0.0..=TAU, 1.0);
+        let pieces = circle.crop_excluding(&frame)?;
+        assert_eq!(pieces.len(), 1);
+        assert_approx_eq!(f64, pieces[0].angle_i, FRAC_PI_2, epsilon = 1e-9);
+        assert_approx_eq!(f64, pieces[0].angle_f, TAU, epsilon = 1e-9);
+
+        // a circle within the frame is hidden, and one without is not.
+        assert!(CurveArc(Point(1, 1), 0.0..=TAU, 0.5)
+            .crop_excluding(&frame)?
+            .is_empty());
+        let far = CurveArc(Point(5, 5), 0.0..=TAU, 0.5);
+        assert_eq!(far.crop_excluding(&frame)?, vec![far]);
+        Ok(())
+    }
 }
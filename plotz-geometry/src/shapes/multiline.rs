@@ -6,6 +6,7 @@ use crate::{
     bounded::{Bounded, Bounds},
     crop::{CropType, Croppable},
     obj2::ObjType2d,
+    offset::{offset_chain, JoinStyle},
     Object,
 };
 use anyhow::{anyhow, Result};
@@ -118,21 +119,57 @@ impl Multiline {
             .iter_mut()
             .for_each(|pt| pt.rotate_inplace(about, by_rad))
     }
+
+    /// Returns the closed outline of this multiline, |distance| away on
+    /// either side. The ends are capped round for |JoinStyle::Round|, and
+    /// otherwise cut off flat at the end points (butt caps).
+    pub fn offset(&self, distance: f64, join: JoinStyle) -> Vec<Polygon> {
+        offset_chain(&self.pts, distance, join)
+    }
 }
 
 impl Croppable for Multiline {
     type Output = Multiline;
 
-    fn crop(&self, _other: &Polygon, crop_type: CropType) -> Result<Vec<Self::Output>> {
-        match crop_type {
-            // the bits of |self| which are in |frame|.
-            CropType::Inclusive => {
-                // match polygon_overlaps_multiline(..)
-                todo!()
+    fn crop(&self, frame: &Polygon, crop_type: CropType) -> Result<Vec<Self::Output>> {
+        // crop each segment in turn, and chain together the pieces which meet.
+        let mut chains: Vec<Vec<Point>> = vec![];
+        for sg in self.to_segments() {
+            let d = sg.f - sg.i;
+            let mut pieces: Vec<Segment> = sg
+                .crop(frame, crop_type)?
+                .into_iter()
+                .map(|piece| {
+                    if (piece.f - piece.i).dot(&d) < 0.0 {
+                        piece.flip()
+                    } else {
+                        piece
+                    }
+                })
+                .collect();
+            pieces.sort_by_key(|piece| FloatOrd((piece.i - sg.i).dot(&d)));
+            for piece in pieces {
+                match chains.last_mut() {
+                    Some(chain) if chain.last() == Some(&piece.i) => chain.push(piece.f),
+                    _ => chains.push(vec![piece.i, piece.f]),
+                }
             }
-            // the bits of |self| which are _not_ in |frame|.
-            CropType::Exclusive => todo!(),
         }
+        Ok(chains
+            .into_iter()
+            .flat_map(|mut pts| {
+                // a chain which closes on itself can't be a multiline, so
+                // split off its last segment.
+                let n = pts.len();
+                if n > 2 && pts[0] == pts[n - 1] {
+                    let tail = vec![pts[n - 2], pts[n - 1]];
+                    pts.pop();
+                    vec![Multiline { pts }, Multiline { pts: tail }]
+                } else {
+                    vec![Multiline { pts }]
+                }
+            })
+            .collect())
     }
 }
 
@@ -252,4 +289,24 @@ mod tests {
 
         Ok(())
     }
+
+    #[test]
+    fn test_crop_excluding() -> Result<()> {
+        use crate::shapes::polygon::Polygon;
+        let frame = Polygon([(0, 0), (2, 0), (2, 2), (0, 2)])?;
+        // a hook which dips into the frame and out again.
+        let ml = Multiline([(-1, 1), (1, 1), (1, 3), (3, 3)]);
+        assert_eq!(
+            ml.crop_excluding(&frame)?,
+            vec![
+                Multiline([(-1, 1), (0, 1)]),
+                Multiline([(1, 2), (1, 3), (3, 3)]),
+            ]
+        );
+        // and wholly within.
+        assert!(Multiline([(0.5, 0.5), (1.5, 1.5), (0.5, 1.5)])
+            .crop_excluding(&frame)?
+            .is_empty());
+        Ok(())
+    }
 }
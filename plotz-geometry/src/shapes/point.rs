@@ -176,6 +176,16 @@ impl Point {
         (self.x * other.x) + (self.y * other.y)
     }
 
+    /// Z component of the cross product (origin, self) x (origin, other).
+    pub fn cross_z(&self, other: &Point) -> f64 {
+        (self.x * other.y) - (self.y * other.x)
+    }
+
+    /// Length of (origin, self).
+    pub fn norm(&self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
     /// Distance between two points.
     pub fn dist(&self, other: &Point) -> f64 {
         Segment(*self, *other).length()
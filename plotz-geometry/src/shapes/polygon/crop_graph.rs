@@ -1,17 +1,17 @@
 //! Crop graph for polygons.
 
 use crate::{
+    bounded::Bounded,
     crop::{CropType, PointLocation},
     intersection::{Intersection, IntersectionResult},
-    overlaps::{opinion::polygon_opinion::PolygonOp, polygon_overlaps_point},
     shapes::{
         point::{is_colinear_n, Point},
         polygon::Polygon,
     },
+    tolerance::{self, Tolerance},
     utils::{Pair, Which},
 };
 use anyhow::{anyhow, Context, Result};
-use approx::*;
 use float_ord::FloatOrd;
 use itertools::Itertools;
 use petgraph::{
@@ -43,6 +43,23 @@ pub struct CropGraph<'a> {
     // known,
     #[builder(default)]
     known_pts: Vec<Point>,
+
+    // how close two points must be to be one node. see |crop_tolerance|.
+    #[builder(default)]
+    tolerance: Tolerance,
+}
+
+// Intersections computed along different edges rarely agree to the ulp, so
+// crop graph nodes merge within a billionth of the size of the polygons being
+// cropped (see |Tolerance::for_bounds|), or within the current tolerance if
+// that is looser.
+fn crop_tolerance(a: &Polygon, b: &Polygon) -> Result<Tolerance> {
+    let current = tolerance::current();
+    let scaled = Tolerance::for_bounds(&a.bounds()?.join(&b.bounds()?));
+    Ok(Tolerance {
+        abs: current.abs.max(scaled.abs),
+        rel: current.rel.max(scaled.rel),
+    })
 }
 
 impl<'a> CropGraph<'a> {
@@ -51,39 +68,43 @@ impl<'a> CropGraph<'a> {
         b: &Polygon,
         crop_type: CropType,
     ) -> Result<(Vec<Polygon>, DiGraphMap<Point, ()>)> {
-        let mut crop_graph = CropGraph::builder().a(a).b(b).build();
-        crop_graph.build_from_polygons(crop_type);
-        crop_graph.remove_nodes_outside_polygon(Which::A);
-        match crop_type {
-            CropType::Inclusive => {
-                crop_graph.remove_nodes_outside_polygon(Which::B);
-                crop_graph.remove_edges_outside(Which::A);
-            }
-            CropType::Exclusive => {
-                crop_graph.remove_nodes_inside_polygon(Which::B);
-                crop_graph.remove_edges_inside(Which::B);
+        let tolerance = crop_tolerance(a, b)?;
+        // nodes merge within |tolerance|, so everything else about the graph --
+        // where edges cross, which side of a polygon a node is on -- had
+        // better be decided within it too.
+        tolerance::with_tolerance(tolerance, || {
+            let mut crop_graph = CropGraph::builder().a(a).b(b).tolerance(tolerance).build();
+            crop_graph.build_from_polygons(crop_type);
+            crop_graph.remove_nodes_outside_polygon(Which::A)?;
+            match crop_type {
+                CropType::Inclusive => {
+                    crop_graph.remove_nodes_outside_polygon(Which::B)?;
+                    crop_graph.remove_edges_outside(Which::A)?;
+                }
+                CropType::Exclusive => {
+                    crop_graph.remove_nodes_inside_polygon(Which::B)?;
+                    crop_graph.remove_edges_inside(Which::B)?;
+                }
             }
-        }
-        crop_graph.remove_stubs();
-        crop_graph.remove_dual_edges();
-        crop_graph.remove_nodes_with_no_neighbors_of_any_kind();
-        let graph = crop_graph.graph.clone();
-        Ok((
-            crop_graph
-                .trim_and_create_resultant_polygons()
-                .context("trim and create resultant polygons")?,
-            graph,
-        ))
+            crop_graph.remove_stubs();
+            crop_graph.remove_dual_edges();
+            crop_graph.remove_nodes_with_no_neighbors_of_any_kind();
+            let graph = crop_graph.graph.clone();
+            Ok((
+                crop_graph
+                    .trim_and_create_resultant_polygons()
+                    .context("trim and create resultant polygons")?,
+                graph,
+            ))
+        })
     }
 
     fn normalize_pt(&mut self, pt: &Point) -> Point {
         // if something in self.known_pts matches, return that instead.
         // otherwise insert pt into known_pts and return it.
 
-        if let Some(extant) = self.known_pts.iter().find(|extant| {
-            let e = f64::EPSILON * 1_000_000_000.0;
-            relative_eq!(extant.x, pt.x, epsilon = e) && relative_eq!(extant.y, pt.y, epsilon = e)
-        }) {
+        let tol = self.tolerance;
+        if let Some(extant) = self.known_pts.iter().find(|extant| tol.pts_eq(extant, pt)) {
             *extant
         } else {
             self.known_pts.push(*pt);
@@ -187,25 +208,28 @@ impl<'a> CropGraph<'a> {
         }
     }
 
-    fn remove_nodes_inside_polygon(&mut self, which: Which) {
-        while let Some(node) = self.graph.nodes().find(|node| {
-            matches!(
-                polygon_overlaps_point(self.get(which), node).unwrap(),
-                Some((PolygonOp::AreaPoint(_), _))
-            )
-        }) {
-            self.graph.remove_node(node);
-        }
+    fn remove_nodes_inside_polygon(&mut self, which: Which) -> Result<()> {
+        self.remove_nodes_where(which, PointLocation::Inside)
+    }
+
+    fn remove_nodes_outside_polygon(&mut self, which: Which) -> Result<()> {
+        self.remove_nodes_where(which, PointLocation::Outside)
     }
 
-    fn remove_nodes_outside_polygon(&mut self, which: Which) {
-        while let Some(node) = self
+    // removes every node at |location| relative to one of the polygons. nodes
+    // were merged within |tolerance|, so those on a polygon's boundary may be
+    // that far off it.
+    fn remove_nodes_where(&mut self, which: Which, location: PointLocation) -> Result<()> {
+        let locator = self.get(which).locator(self.tolerance.abs)?;
+        let nodes: Vec<Point> = self
             .graph
             .nodes()
-            .find(|node| matches!(polygon_overlaps_point(self.get(which), node).unwrap(), None))
-        {
+            .filter(|node| locator.locate(node) == location)
+            .collect();
+        for node in nodes {
             self.graph.remove_node(node);
         }
+        Ok(())
     }
 
     fn remove_nodes_with_no_neighbors_of_kind(&mut self, direction: Direction) {
@@ -262,25 +286,28 @@ impl<'a> CropGraph<'a> {
         }
     }
 
-    fn remove_edges_outside(&mut self, which: Which) {
-        while let Some((i, j, ())) = self.graph.all_edges().find(|edge| {
-            matches!(
-                self.get(which).contains_pt_deprecated(&edge.0.avg(&edge.1)),
-                Ok(PointLocation::Outside),
-            )
-        }) {
-            self.graph.remove_edge(i, j);
-        }
+    fn remove_edges_outside(&mut self, which: Which) -> Result<()> {
+        self.remove_edges_where(which, PointLocation::Outside)
     }
-    fn remove_edges_inside(&mut self, which: Which) {
-        while let Some((i, j, ())) = self.graph.all_edges().find(|edge| {
-            matches!(
-                self.get(which).contains_pt_deprecated(&edge.0.avg(&edge.1)),
-                Ok(PointLocation::Inside)
-            )
-        }) {
-            self.graph.remove_edge(i, j);
+    fn remove_edges_inside(&mut self, which: Which) -> Result<()> {
+        self.remove_edges_where(which, PointLocation::Inside)
+    }
+
+    // removes every edge whose midpoint lies at |location| relative to one of
+    // the polygons, likewise.
+    fn remove_edges_where(&mut self, which: Which, location: PointLocation) -> Result<()> {
+        let edges: Vec<(Point, Point)> = self.graph.all_edges().map(|(i, j, ())| (i, j)).collect();
+        let midpoints: Vec<Point> = edges.iter().map(|(i, j)| i.avg(j)).collect();
+        let locations = self
+            .get(which)
+            .locator(self.tolerance.abs)?
+            .locate_all(&midpoints);
+        for ((i, j), loc) in edges.into_iter().zip(locations) {
+            if loc == location {
+                self.graph.remove_edge(i, j);
+            }
         }
+        Ok(())
     }
 
     // Returns a polygon, if possible. An error state here represents some vailed invariant.
@@ -400,9 +427,11 @@ impl<'a> CropGraph<'a> {
 }
 
 #[cfg(test)]
+#[allow(deprecated)]
 mod test {
     use super::*;
     use crate::{crop::Croppable, interpolate::extrapolate_2d, shapes::polygon::Rect};
+    use float_eq::assert_float_eq;
     use itertools::iproduct;
     use test_case::test_case;
 
@@ -528,4 +557,41 @@ mod test {
 
         Ok(())
     }
+
+    #[test]
+    fn test_crop_tolerance_scales_with_the_polygons() -> Result<()> {
+        let a = Rect((0, 0), (100, 100))?;
+        let b = Rect((50, 50), (100, 100))?;
+        let mut crop_graph = CropGraph::builder()
+            .a(&a)
+            .b(&b)
+            .tolerance(crop_tolerance(&a, &b)?)
+            .build();
+        let p = crop_graph.normalize_pt(&Point(50, 50));
+        // a hair off, for polygons this size.
+        assert_eq!(crop_graph.normalize_pt(&Point(50.0 + 1e-9, 50.0)), p);
+        // but not close enough to merge under the default tolerance alone.
+        assert!(!tolerance::current().pts_eq(&Point(50.0 + 1e-9, 50.0), &p));
+        Ok(())
+    }
+
+    #[test]
+    fn test_crop_out_a_sliver_a_hair_inside() -> Result<()> {
+        // |b| runs along |a|'s left edge, a rounding error to the right of it,
+        // and its third corner lies on |a|'s lower edge.
+        let a = Polygon([
+            (-3.1819805153394634, 2.725368844341965),
+            (-2.9992866268052905, 3.231112885229961),
+            (-3.1819805153394634, 3.22968515154997),
+        ])?;
+        let b = Polygon([
+            (-3.181980515339463, 2.7890851578270452),
+            (-3.1819805153394634, 2.725368844341965),
+            (-3.1678184285192024, 2.7645731717190447),
+        ])?;
+        let cropped = a.crop(&b, CropType::Exclusive)?;
+        assert_eq!(cropped.len(), 1);
+        assert_float_eq!(cropped[0].area(), a.area() - b.area(), abs <= 1e-12);
+        Ok(())
+    }
 }
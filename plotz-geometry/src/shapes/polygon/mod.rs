@@ -7,11 +7,16 @@ mod crop_graph;
 use self::{annotated_isxn_result::*, crop_graph::*};
 use crate::{
     bounded::{Bounded, Bounds},
+    containment::{default_tolerance, Locator},
     crop::{CropType, Croppable, PointLocation},
-    intersection::IntersectionResult,
+    hull::min_area_rect,
+    intersection::{Intersection, IntersectionResult},
     obj2::ObjType2d,
+    offset::{offset_polygon, JoinStyle},
     overlaps::{opinion::polygon_opinion::PolygonOp, polygon_overlaps_point},
     shapes::{point::Point, segment::Segment},
+    triangulate::triangulate,
+    utils::Percent,
     *,
 };
 use anyhow::{anyhow, Context, Result};
@@ -20,6 +25,7 @@ use float_ord::FloatOrd;
 use itertools::iproduct;
 use std::{
     cmp::{Eq, PartialEq},
+    f64::consts::TAU,
     fmt::Debug,
     iter::zip,
     ops::*,
@@ -165,8 +171,15 @@ impl Polygon {
             .collect::<Vec<_>>()
     }
 
+    /// A reusable point locator for this polygon. Build one of these when
+    /// asking about many points.
+    pub fn locator(&self, tolerance: f64) -> Result<Locator> {
+        Locator::new([&self.pts[..]], tolerance)
+    }
+
     /// Calculates whether a point is within, without, or along a closed polygon
     /// using the https://en.wikipedia.org/wiki/Winding_number method.
+    #[deprecated(note = "use containment::Locate::locate instead")]
     pub fn contains_pt_deprecated(&self, other: &Point) -> Result<PointLocation> {
         match polygon_overlaps_point(self, other)? {
             None => Ok(PointLocation::Outside),
@@ -178,6 +191,7 @@ impl Polygon {
     }
 
     /// True if the area or points/edges of this polygon contain a point.
+    #[deprecated(note = "use containment::Locate::contains instead")]
     pub fn point_is_inside_or_on_border_deprecated(&self, other: &Point) -> bool {
         matches!(
             polygon_overlaps_point(self, other).unwrap(),
@@ -188,6 +202,7 @@ impl Polygon {
     }
 
     /// True if the area of this polygon contains a point.
+    #[deprecated(note = "use containment::Locate::strictly_contains instead")]
     pub fn point_is_inside_deprecated(&self, other: &Point) -> bool {
         matches!(
             polygon_overlaps_point(self, other).unwrap(),
@@ -230,22 +245,151 @@ impl Polygon {
         Point(sum_x / num, sum_y / num)
     }
 
+    /// Offsets this polygon by |distance|, joining corners according to
+    /// |join|. Positive distances grow the polygon, negative distances shrink
+    /// it. Self-intersections are cleaned up, so an inset which pinches off
+    /// returns several polygons, and one which collapses returns none.
+    pub fn offset(&self, distance: f64, join: JoinStyle) -> Vec<Polygon> {
+        offset_polygon(self, distance, join)
+    }
+
+    /// The signed area of this polygon, by the shoelace formula. Positive if
+    /// the points are stored counterclockwise, which (by construction) they
+    /// usually are.
+    pub fn signed_area(&self) -> f64 {
+        self.to_segments()
+            .iter()
+            .map(|sg| sg.i.cross_z(&sg.f))
+            .sum::<f64>()
+            / 2.0
+    }
+
+    /// The (unsigned) area of this polygon.
+    pub fn area(&self) -> f64 {
+        self.signed_area().abs()
+    }
+
+    /// The total length of this polygon's edges.
+    pub fn perimeter(&self) -> f64 {
+        self.to_segments().iter().map(|sg| sg.length()).sum()
+    }
+
+    /// The centroid (center of mass) of this polygon. Unlike
+    /// [`Polygon::average`], this is not biased by where the points happen to
+    /// be. Returns an error for polygons without area.
+    pub fn centroid(&self) -> Result<Point> {
+        let a = self.signed_area();
+        if approx_eq!(f64, a, 0.0) {
+            return Err(anyhow!("polygon has no area; no centroid."));
+        }
+        let (cx, cy) = self
+            .to_segments()
+            .iter()
+            .map(|sg| {
+                let c = sg.i.cross_z(&sg.f);
+                ((sg.i.x + sg.f.x) * c, (sg.i.y + sg.f.y) * c)
+            })
+            .fold((0.0, 0.0), |(ax, ay), (x, y)| (ax + x, ay + y));
+        Ok(Point(cx / (6.0 * a), cy / (6.0 * a)))
+    }
+
+    /// True if this polygon is convex, i.e. it turns the same way at every
+    /// vertex and winds around exactly once. Collinear vertices are allowed.
+    pub fn is_convex(&self) -> bool {
+        let sgs = self.to_segments();
+        let (mut lefts, mut rights, mut winding) = (0, 0, 0.0);
+        for (a, b) in zip(sgs.iter(), sgs.iter().cycle().skip(1)) {
+            let (da, db) = (a.f - a.i, b.f - b.i);
+            let c = da.cross_z(&db);
+            if c > 0.0 {
+                lefts += 1;
+            } else if c < 0.0 {
+                rights += 1;
+            }
+            winding += c.atan2(da.dot(&db));
+        }
+        (lefts == 0 || rights == 0) && (winding.abs() - TAU).abs() < 1e-6
+    }
+
+    /// True if this polygon is simple, i.e. none of its edges cross or touch
+    /// except where consecutive edges share a vertex.
+    pub fn is_simple(&self) -> bool {
+        let sgs = self.to_segments();
+        let n = sgs.len();
+        for i in 0..n {
+            for j in (i + 1)..n {
+                let adjacent = j == i + 1 || (i == 0 && j == n - 1);
+                let (a, b) = (&sgs[i], &sgs[j]);
+                if adjacent {
+                    // consecutive edges may only double back on themselves.
+                    let (da, db) = if j == i + 1 {
+                        (a.f - a.i, b.f - b.i)
+                    } else {
+                        (b.f - b.i, a.f - a.i)
+                    };
+                    if da.cross_z(&db) == 0.0 && da.dot(&db) < 0.0 {
+                        return false;
+                    }
+                } else if segments_touch(a, b) {
+                    return false;
+                }
+            }
+        }
+        true
+    }
+
+    /// The rectangle of least area which encloses this polygon. Not
+    /// necessarily axis-aligned.
+    pub fn min_area_rect(&self) -> Result<Polygon> {
+        min_area_rect(&self.pts)
+    }
+
+    /// Triangulates this polygon by ear clipping.
+    pub fn triangulate(&self) -> Result<Vec<Polygon>> {
+        triangulate(self, &[])
+    }
+
     // check if this polygon totally contains another.
     // assumes no intersections.
     fn totally_contains(&self, other: &Polygon) -> Result<bool> {
+        Ok(self
+            .locator(default_tolerance())?
+            .locate_all(&other.pts)
+            .into_iter()
+            .all(|loc| loc != PointLocation::Outside))
+    }
+
+    // check if the other polygon lies within this one without crossing it, so
+    // that cropping it out would leave a cavity. it may touch this one at its
+    // corners, but no edge of it may run along or through this one's edges.
+    pub(crate) fn surrounds(&self, other: &Polygon) -> Result<bool> {
+        let only_corners_touch = self.annotated_intersects_detailed(other).iter().all(|a| {
+            matches!(
+                a.isxn_result,
+                IntersectionResult::Ok(Intersection {
+                    b_pct: Percent::Zero | Percent::One,
+                    ..
+                })
+            )
+        });
+        if !only_corners_touch || other.totally_contains(self)? || !self.totally_contains(other)? {
+            return Ok(false);
+        }
+        let locator = self.locator(default_tolerance())?;
         Ok(other
-            .pts
+            .to_segments()
             .iter()
-            .all(|pt| !matches!(self.contains_pt_deprecated(pt), Ok(PointLocation::Outside))))
+            .all(|sg| locator.locate(&sg.midpoint()) == PointLocation::Inside))
     }
 
     // check if the other polygon isn't inside of or intersecting this one at all.
     // assumes no intersections.
     fn contains_not_at_all(&self, other: &Polygon) -> Result<bool> {
-        Ok(other
-            .pts
-            .iter()
-            .all(|pt| matches!(self.contains_pt_deprecated(pt), Ok(PointLocation::Outside))))
+        Ok(self
+            .locator(default_tolerance())?
+            .locate_all(&other.pts)
+            .into_iter()
+            .all(|loc| loc == PointLocation::Outside))
     }
 }
 
@@ -261,7 +405,10 @@ impl Croppable for Polygon {
         let a: &Polygon = self;
 
         if a == b {
-            return Ok(vec![a.clone()]);
+            return Ok(match crop_type {
+                CropType::Inclusive => vec![a.clone()],
+                CropType::Exclusive => vec![],
+            });
         }
 
         // scenario with no intersections.
@@ -286,6 +433,12 @@ impl Croppable for Polygon {
                 }
                 CropType::Exclusive => {
                     // if exclusive, then we want the bit of |a| _not_ in |b|.
+                    if b.totally_contains(a)? {
+                        // if |b| totally contains |a|, then there's no part of
+                        // |a| we want. (this goes first, since if |a| and |b|
+                        // are the same shape then each contains the other.)
+                        return Ok(vec![]);
+                    }
                     if a.totally_contains(b)? {
                         // TODO(https://github.com/ambuc/plotz_rs/issues/4):
                         // must begin to support polygons with cavities !!!
@@ -293,11 +446,6 @@ impl Croppable for Polygon {
                             "we want a polygon with a cavity here - not yet supported. See https://github.com/ambuc/plotz_rs/issues/4."
                         ));
                     }
-                    if b.totally_contains(a)? {
-                        // if |b| totally contains |a|, then there's no part of
-                        // |a| we want.
-                        return Ok(vec![]);
-                    }
                     if b.contains_not_at_all(a)? {
                         // if |b| doesn't contain any part of |a| (and there are
                         // no intersections) then return A unchanged.
@@ -313,6 +461,25 @@ impl Croppable for Polygon {
     }
 }
 
+// true if the closed segments |a| and |b| share any point.
+fn segments_touch(a: &Segment, b: &Segment) -> bool {
+    let o = |p: &Point, q: &Point, r: &Point| (*q - *p).cross_z(&(*r - *p));
+    let on = |p: &Point, q: &Point, r: &Point| {
+        r.x >= p.x.min(q.x) && r.x <= p.x.max(q.x) && r.y >= p.y.min(q.y) && r.y <= p.y.max(q.y)
+    };
+    let (d1, d2) = (o(&a.i, &a.f, &b.i), o(&a.i, &a.f, &b.f));
+    let (d3, d4) = (o(&b.i, &b.f, &a.i), o(&b.i, &b.f, &a.f));
+    if ((d1 > 0.0 && d2 < 0.0) || (d1 < 0.0 && d2 > 0.0))
+        && ((d3 > 0.0 && d4 < 0.0) || (d3 < 0.0 && d4 > 0.0))
+    {
+        return true;
+    }
+    (d1 == 0.0 && on(&a.i, &a.f, &b.i))
+        || (d2 == 0.0 && on(&a.i, &a.f, &b.f))
+        || (d3 == 0.0 && on(&b.i, &b.f, &a.i))
+        || (d4 == 0.0 && on(&b.i, &b.f, &a.f))
+}
+
 /// Angle between points. Projects OI onto OJ and finds the angle IOJ.
 pub fn abp(o: &Point, i: &Point, j: &Point) -> f64 {
     let a: Point = *i - *o;
@@ -390,6 +557,7 @@ impl Object for Polygon {
 }
 
 #[cfg(test)]
+#[allow(deprecated)]
 mod tests {
     use super::*;
     use float_eq::assert_float_eq;
@@ -1063,4 +1231,46 @@ mod tests {
         }
         Ok(())
     }
+
+    #[test]
+    fn test_area_perimeter_centroid() -> Result<()> {
+        let sq = Rect((1, 1), (2, 4))?;
+        assert_float_eq!(sq.signed_area(), 8.0, abs <= 1e-9);
+        assert_float_eq!(sq.area(), 8.0, abs <= 1e-9);
+        assert_float_eq!(sq.perimeter(), 12.0, abs <= 1e-9);
+        assert_eq!(sq.centroid()?, Point(2, 3));
+
+        // the average is pulled toward the bunched-up points, the centroid isn't.
+        let tri = Polygon([
+            (0.0, 0.0),
+            (3.0, 0.0),
+            (3.0, 0.5),
+            (3.0, 1.0),
+            (3.0, 2.0),
+            (3.0, 3.0),
+        ])?;
+        assert_eq!(tri.centroid()?, Point(2, 1));
+        assert_ne!(tri.average(), Point(2, 1));
+        Ok(())
+    }
+
+    #[test]
+    fn test_is_convex_is_simple() -> Result<()> {
+        let sq = Rect((0, 0), (1, 1))?;
+        assert!(sq.is_convex());
+        assert!(sq.is_simple());
+
+        let ell = Polygon([(0, 0), (2, 0), (2, 1), (1, 1), (1, 2), (0, 2)])?;
+        assert!(!ell.is_convex());
+        assert!(ell.is_simple());
+
+        let bowtie = Polygon([(0, 0), (2, 2), (2, 0), (0, 2)])?;
+        assert!(!bowtie.is_convex());
+        assert!(!bowtie.is_simple());
+
+        let star = Polygon([(0, 0), (4, 2), (-1, 3), (2, -1), (3, 4)])?;
+        assert!(!star.is_convex());
+        assert!(!star.is_simple());
+        Ok(())
+    }
 }
@@ -3,9 +3,11 @@
 
 use crate::{
     bounded::{Bounded, Bounds},
-    crop::PointLocation,
+    containment::{default_tolerance, Locate, Locator},
+    crop::{CropType, Croppable, PointLocation},
     obj2::ObjType2d,
     shapes::{point::Point, polygon::Polygon},
+    triangulate::triangulate,
     Object,
 };
 use anyhow::{anyhow, Result};
@@ -26,7 +28,7 @@ pub fn PolygonWithCavities(
     let outer: Polygon = a.into();
     for inner_pg in &inner {
         for pt in &inner_pg.pts {
-            if outer.contains_pt_deprecated(pt)? == PointLocation::Outside {
+            if outer.locate(pt, default_tolerance())? == PointLocation::Outside {
                 return Err(anyhow!("pt in inner is outside of outer"));
             }
         }
@@ -34,6 +36,91 @@ pub fn PolygonWithCavities(
     Ok(PolygonWithCavities { outer, inner })
 }
 
+impl PolygonWithCavities {
+    /// A reusable point locator over the outer ring and cavities. Points
+    /// within a cavity are |Outside|.
+    pub fn locator(&self, tolerance: f64) -> Result<Locator> {
+        Locator::new(
+            std::iter::once(&self.outer.pts[..]).chain(self.inner.iter().map(|i| &i.pts[..])),
+            tolerance,
+        )
+    }
+
+    /// Triangulates the area inside |outer| and outside of each cavity.
+    pub fn triangulate(&self) -> Result<Vec<Polygon>> {
+        triangulate(&self.outer, &self.inner)
+    }
+}
+
+impl Croppable for PolygonWithCavities {
+    type Output = PolygonWithCavities;
+    /// Crops the outer ring, then each piece of it loses whatever cavities
+    /// overlap it: a cavity wholly within a piece stays a cavity, and any
+    /// other is cut away from the piece. Excluding a frame which lies wholly
+    /// within the outer ring adds it to the cavities instead.
+    fn crop(&self, frame: &Polygon, crop_type: CropType) -> Result<Vec<Self::Output>> {
+        if crop_type == CropType::Exclusive && self.outer.surrounds(frame)? {
+            return self.with_cavity(frame).map(|pwc| vec![pwc]);
+        }
+        let mut pieces: Vec<PolygonWithCavities> = self
+            .outer
+            .crop(frame, crop_type)?
+            .into_iter()
+            .map(|outer| PolygonWithCavities {
+                outer,
+                inner: vec![],
+            })
+            .collect();
+        for hole in &self.inner {
+            let mut next = vec![];
+            for piece in pieces {
+                if piece.outer.surrounds(hole)? {
+                    let mut inner = piece.inner;
+                    inner.push(hole.clone());
+                    next.push(PolygonWithCavities {
+                        outer: piece.outer,
+                        inner,
+                    });
+                    continue;
+                }
+                for outer in piece.outer.crop_excluding(hole)? {
+                    // the cavities already within this piece, which are still
+                    // within whichever part of it they fall in.
+                    let mut inner = vec![];
+                    for cavity in &piece.inner {
+                        if outer.surrounds(cavity)? {
+                            inner.push(cavity.clone());
+                        }
+                    }
+                    next.push(PolygonWithCavities { outer, inner });
+                }
+            }
+            pieces = next;
+        }
+        Ok(pieces)
+    }
+}
+
+impl PolygonWithCavities {
+    // This, with |hole| (which lies within |outer|) cut out of it as well.
+    // Cavities the hole covers are dropped, and those it overlaps lose the
+    // part of them it covers, so that no two cavities overlap.
+    fn with_cavity(&self, hole: &Polygon) -> Result<PolygonWithCavities> {
+        let mut inner = vec![hole.clone()];
+        for cavity in &self.inner {
+            if cavity.surrounds(hole)? {
+                // nothing there to cut out.
+                return Ok(self.clone());
+            }
+            inner.extend(cavity.crop_excluding(hole)?);
+        }
+        Ok(PolygonWithCavities {
+            outer: self.outer.clone(),
+            inner,
+        })
+    }
+}
+
 impl PartialEq for PolygonWithCavities {
     fn eq(&self, _: &Self) -> bool {
         unimplemented!("TODO(jbuckland): implement partialeq. we should compare each polygon flexibly _and_ w/o respect for inner ordering");
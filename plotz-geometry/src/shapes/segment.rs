@@ -3,14 +3,16 @@
 
 use crate::{
     bounded::{Bounded, Bounds},
-    crop::{CropType, Croppable},
+    containment::{default_tolerance, Locate},
+    crop::{CropType, Croppable, PointLocation},
     intersection::{Intersection, IntersectionResult},
     obj2::ObjType2d,
     overlaps::{
-        opinion::segment_opinion::SegmentOp, polygon_overlaps_segment, segment_overlaps_segment,
+        opinion::segment_opinion::SegmentOp, polygon_overlaps_point, polygon_overlaps_segment,
+        segment_overlaps_segment,
     },
     shapes::{point::Point, polygon::Polygon, ray::Ray},
-    Object,
+    tolerance, Object,
 };
 use anyhow::Result;
 use float_eq::float_ne;
@@ -114,6 +116,17 @@ impl Segment {
         (o.x * s.x) + (o.y * s.y)
     }
 
+    /// The distance from |pt| to the nearest point on this segment.
+    pub fn dist_to_point(&self, pt: &Point) -> f64 {
+        let d = self.f - self.i;
+        let l2 = d.dot(&d);
+        if l2 == 0.0 {
+            return pt.dist(&self.i);
+        }
+        let t = ((*pt - self.i).dot(&d) / l2).clamp(0.0, 1.0);
+        pt.dist(&(self.i + d * t))
+    }
+
     pub fn midpoint(&self) -> Point {
         (self.i + self.f) / 2.0
     }
@@ -195,7 +208,46 @@ impl Croppable for Segment {
             },
 
             // the bits of |self| which are _not_ in |frame|.
-            CropType::Exclusive => todo!(),
+            CropType::Exclusive => {
+                let d = self.f - self.i;
+                let len2 = d.dot(&d);
+                if len2 == 0.0 {
+                    return Ok(match polygon_overlaps_point(frame, &self.i)? {
+                        Some(_) => vec![],
+                        None => vec![*self],
+                    });
+                }
+                // the spans of |self| (as fractions along it) which are in
+                // |frame|, in order. spans running along its edge aren't
+                // hidden by it...
+                let t = |pt: &Point| (*pt - self.i).dot(&d) / len2;
+                let mut inside: Vec<(f64, f64)> = vec![];
+                for sg in self.crop_to(frame)? {
+                    if frame.locate(&sg.midpoint(), default_tolerance())? == PointLocation::Inside {
+                        inside.push((t(&sg.i).min(t(&sg.f)), t(&sg.i).max(t(&sg.f))));
+                    }
+                }
+                inside.sort_by(|a, b| a.0.total_cmp(&b.0));
+
+                // ...and the gaps between them.
+                let mut gaps = vec![];
+                let mut from = 0.0;
+                for (lo, hi) in inside {
+                    if lo > from {
+                        gaps.push((from, lo));
+                    }
+                    from = f64::max(from, hi);
+                }
+                if from < 1.0 {
+                    gaps.push((from, 1.0));
+                }
+                let tol = tolerance::current();
+                Ok(gaps
+                    .into_iter()
+                    .map(|(lo, hi)| Segment(self.i + d * lo, self.i + d * hi))
+                    .filter(|sg| !tol.pts_eq(&sg.i, &sg.f))
+                    .collect())
+            }
         }
     }
 }
@@ -501,4 +553,17 @@ mod tests {
         let sb = sb.into();
         assert_eq!(sa.try_add(&sb), expectation);
     }
+
+    #[test_case(Segment((-1, 1), (3, 1)), vec![Segment((-1, 1), (0, 1)), Segment((2, 1), (3, 1))]; "through")]
+    #[test_case(Segment((1, 1), (3, 1)), vec![Segment((2, 1), (3, 1))]; "from within")]
+    #[test_case(Segment((0.5, 0.5), (1.5, 1.5)), vec![]; "within")]
+    #[test_case(Segment((3, 0), (3, 2)), vec![Segment((3, 0), (3, 2))]; "without")]
+    #[test_case(Segment((1, 1), (1, 1)), vec![]; "degenerate within")]
+    #[test_case(Segment((0, 0), (2, 0)), vec![Segment((0, 0), (2, 0))]; "along an edge")]
+    #[test_case(Segment((-1, 2), (1, 2)), vec![Segment((-1, 2), (1, 2))]; "partly along an edge")]
+    fn test_crop_excluding(sg: Segment, expected: Vec<Segment>) -> Result<()> {
+        let frame = Polygon([(0, 0), (2, 0), (2, 2), (0, 2)])?;
+        assert_eq!(sg.crop_excluding(&frame)?, expected);
+        Ok(())
+    }
 }
@@ -0,0 +1,385 @@
+//! Point distributions for stippling: Poisson-disk sampling, and weighted
+//! Voronoi stippling after Secord, "Weighted Voronoi Stippling" (2002).
+
+use crate::{
+    bounded::{streaming_bbox, Bounded, Bounds},
+    containment::{default_tolerance, ToLocator},
+    crop::PointLocation,
+    shapes::{curve::CurveArc, point::Point},
+    utils::PointGrid,
+};
+use anyhow::{anyhow, Result};
+use rand::Rng;
+use std::{
+    f64::consts::{SQRT_2, TAU},
+    ops::RangeInclusive,
+};
+use typed_builder::TypedBuilder;
+
+// A grid cell size holding about one of |n| points spread over |bounds|.
+fn cell_for(bounds: &Bounds, n: usize) -> f64 {
+    let area = (bounds.x_max - bounds.x_min) * (bounds.y_max - bounds.y_min);
+    let cell = (area / n.max(1) as f64).sqrt();
+    if cell > 0.0 {
+        cell
+    } else {
+        1.0
+    }
+}
+
+/// Orders points so that a pen visiting them in turn travels little between
+/// them: starting from the least point, always on to the nearest one left.
+pub fn order_for_pen(pts: &[Point]) -> Result<Vec<Point>> {
+    let Some(start) = (0..pts.len()).min_by_key(|i| pts[*i]) else {
+        return Ok(vec![]);
+    };
+    let bounds = streaming_bbox(pts)?;
+    let mut grid = PointGrid::new(&bounds, cell_for(&bounds, pts.len()));
+    for (idx, pt) in pts.iter().enumerate() {
+        grid.insert(pt, idx);
+    }
+
+    let mut ordered = Vec::with_capacity(pts.len());
+    let mut cur = start;
+    loop {
+        grid.remove(&pts[cur], cur);
+        ordered.push(pts[cur]);
+        match grid.nearest(&pts[cur], pts) {
+            Some(next) => cur = next,
+            None => break,
+        }
+    }
+    Ok(ordered)
+}
+
+// A uniformly random point within |bounds|.
+fn random_in(bounds: &Bounds, rng: &mut impl Rng) -> Point {
+    Point(
+        bounds.x_min + rng.gen::<f64>() * (bounds.x_max - bounds.x_min),
+        bounds.y_min + rng.gen::<f64>() * (bounds.y_max - bounds.y_min),
+    )
+}
+
+/// Scatters points over |region| such that no two are closer than |radius|,
+/// and no more can be fit in, by Bridson's algorithm. Works on any region
+/// which can build a locator (e.g. a |Polygon| or |PolygonWithCavities|).
+/// Points are ordered for the pen; see |order_for_pen|.
+pub fn poisson_disk(
+    region: &(impl ToLocator + Bounded),
+    radius: f64,
+    rng: &mut impl Rng,
+) -> Result<Vec<Point>> {
+    if radius <= 0.0 {
+        return Err(anyhow!("radius must be positive"));
+    }
+    // candidates around each point before it is retired.
+    const ATTEMPTS: usize = 30;
+
+    let bounds = region.bounds()?;
+    let locator = region.to_locator(default_tolerance())?;
+    let inside = |pt: &Point| {
+        bounds.dist_to_point(pt) == 0.0 && locator.locate(pt) != PointLocation::Outside
+    };
+    // a cell small enough to hold at most one point.
+    let mut grid = PointGrid::new(&bounds, radius / SQRT_2);
+    let mut pts: Vec<Point> = vec![];
+    let mut active: Vec<usize> = vec![];
+
+    // seed with a random point somewhere inside the region.
+    for _ in 0..1000 {
+        let pt = random_in(&bounds, rng);
+        if inside(&pt) {
+            grid.insert(&pt, 0);
+            pts.push(pt);
+            active.push(0);
+            break;
+        }
+    }
+
+    while !active.is_empty() {
+        let a = rng.gen_range(0..active.len());
+        let origin = pts[active[a]];
+        let mut found = false;
+        for _ in 0..ATTEMPTS {
+            let theta = rng.gen::<f64>() * TAU;
+            let r = radius * (1.0 + rng.gen::<f64>());
+            let pt = origin + Point(theta.cos(), theta.sin()) * r;
+            if inside(&pt) && grid.near(&pt, 2).all(|k| pts[k].dist(&pt) >= radius) {
+                grid.insert(&pt, pts.len());
+                active.push(pts.len());
+                pts.push(pt);
+                found = true;
+                break;
+            }
+        }
+        if !found {
+            active.swap_remove(a);
+        }
+    }
+    order_for_pen(&pts)
+}
+
+/// Config for weighted Voronoi stippling.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct StippleConfig {
+    /// How many stipples.
+    pub count: usize,
+
+    /// How many rounds of relaxation.
+    #[builder(default = 30)]
+    pub iterations: usize,
+
+    /// How many density samples span the region, along its longer side.
+    #[builder(default = 256)]
+    pub resolution: usize,
+}
+
+/// Weighted Voronoi stippling: scatters |count| points over |region|, more of
+/// them where |density| is higher, then repeatedly moves each one to the
+/// density-weighted centroid of its Voronoi cell, which spaces them evenly
+/// for their density. Density is sampled on a grid; negative densities count
+/// as zero. Every point stays within the region, holes included: a point
+/// whose centroid falls outside moves to its cell's nearest sample instead.
+/// Points are ordered for the pen; see |order_for_pen|.
+pub fn stipple(
+    region: &(impl ToLocator + Bounded),
+    density: impl Fn(&Point) -> f64,
+    config: &StippleConfig,
+    rng: &mut impl Rng,
+) -> Result<Vec<Point>> {
+    let bounds = region.bounds()?;
+    let locator = region.to_locator(default_tolerance())?;
+    let inside = |pt: &Point| locator.locate(pt) != PointLocation::Outside;
+    let side = (bounds.x_max - bounds.x_min).max(bounds.y_max - bounds.y_min);
+    let step = side / config.resolution.max(1) as f64;
+
+    // density samples, at the centers of a grid over the region.
+    let mut grid_pts: Vec<Point> = vec![];
+    let mut y = bounds.y_min + step / 2.0;
+    while y < bounds.y_max {
+        let mut x = bounds.x_min + step / 2.0;
+        while x < bounds.x_max {
+            grid_pts.push(Point(x, y));
+            x += step;
+        }
+        y += step;
+    }
+    let samples: Vec<(Point, f64)> = grid_pts
+        .iter()
+        .zip(locator.locate_all(&grid_pts))
+        .filter(|(_, loc)| *loc != PointLocation::Outside)
+        .map(|(pt, _)| (*pt, density(pt).max(0.0)))
+        .filter(|(_, w)| *w > 0.0)
+        .collect();
+    if config.count == 0 {
+        return Ok(vec![]);
+    }
+    if samples.is_empty() {
+        return Err(anyhow!("density is zero everywhere in the region"));
+    }
+
+    // initial sites, drawn from the samples in proportion to their density,
+    // and jittered within their sample cell so that none coincide (unless
+    // that would take them out of the region).
+    let cumulative: Vec<f64> = samples
+        .iter()
+        .scan(0.0, |acc, (_, w)| {
+            *acc += w;
+            Some(*acc)
+        })
+        .collect();
+    let total = cumulative[cumulative.len() - 1];
+    let mut sites: Vec<Point> = (0..config.count)
+        .map(|_| {
+            let t = rng.gen::<f64>() * total;
+            let idx = cumulative
+                .partition_point(|c| *c < t)
+                .min(samples.len() - 1);
+            let jittered =
+                samples[idx].0 + Point(rng.gen::<f64>() - 0.5, rng.gen::<f64>() - 0.5) * step;
+            if inside(&jittered) {
+                jittered
+            } else {
+                samples[idx].0
+            }
+        })
+        .collect();
+
+    let cell = cell_for(&bounds, config.count);
+    for _ in 0..config.iterations {
+        let mut grid = PointGrid::new(&bounds, cell);
+        for (idx, site) in sites.iter().enumerate() {
+            grid.insert(site, idx);
+        }
+        // each sample pulls on the site whose cell it lies in.
+        let owners: Vec<Option<usize>> = samples
+            .iter()
+            .map(|(pt, _)| grid.nearest(pt, &sites))
+            .collect();
+        let mut acc: Vec<(Point, f64)> = vec![(Point(0, 0), 0.0); sites.len()];
+        for ((pt, w), owner) in samples.iter().zip(&owners) {
+            if let Some(idx) = owner {
+                acc[*idx].0 += *pt * *w;
+                acc[*idx].1 += w;
+            }
+        }
+        let centroids: Vec<Option<Point>> = acc
+            .into_iter()
+            .map(|(sum, w)| (w > 0.0).then(|| sum / w))
+            .collect();
+
+        // a cell wrapped around a hole, or a bend, can have its centroid
+        // outside the region. move those sites to the nearest of their own
+        // samples instead.
+        let mut nearest: Vec<Option<Point>> = vec![None; sites.len()];
+        for ((pt, _), owner) in samples.iter().zip(&owners) {
+            let Some(idx) = owner else { continue };
+            let Some(c) = centroids[*idx].filter(|c| !inside(c)) else {
+                continue;
+            };
+            if nearest[*idx].is_none_or(|n| pt.dist(&c) < n.dist(&c)) {
+                nearest[*idx] = Some(*pt);
+            }
+        }
+        for ((site, c), n) in sites.iter_mut().zip(centroids).zip(nearest) {
+            if let Some(c) = n.or(c) {
+                *site = c;
+            }
+        }
+    }
+    order_for_pen(&sites)
+}
+
+/// Stipples as small circles, whose radii run from the start of |radii| where
+/// |density| is least to its end where it is greatest.
+pub fn stipple_dots(
+    pts: &[Point],
+    density: impl Fn(&Point) -> f64,
+    radii: RangeInclusive<f64>,
+) -> Vec<CurveArc> {
+    let ds: Vec<f64> = pts.iter().map(|pt| density(pt).max(0.0)).collect();
+    let (lo, hi) = ds
+        .iter()
+        .fold((f64::MAX, f64::MIN), |(lo, hi), d| (lo.min(*d), hi.max(*d)));
+    pts.iter()
+        .zip(ds)
+        .map(|(pt, d)| {
+            let t = if hi > lo { (d - lo) / (hi - lo) } else { 1.0 };
+            CurveArc(
+                *pt,
+                0.0..=TAU,
+                radii.start() + t * (radii.end() - radii.start()),
+            )
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        containment::Locate,
+        shapes::{polygon::Rect, polygon_with_cavity::PolygonWithCavities},
+    };
+    use float_eq::assert_float_eq;
+    use rand::{rngs::StdRng, SeedableRng};
+
+    fn path_length(pts: &[Point]) -> f64 {
+        pts.iter()
+            .zip(pts.iter().skip(1))
+            .map(|(a, b)| a.dist(b))
+            .sum()
+    }
+
+    #[test]
+    fn test_poisson_disk() -> Result<()> {
+        let hole = Rect((3, 3), (4, 4))?;
+        let region = PolygonWithCavities(Rect((0, 0), (10, 10))?, vec![hole.clone()])?;
+        let radius = 0.5;
+        let pts = poisson_disk(&region, radius, &mut StdRng::seed_from_u64(1))?;
+
+        for (i, a) in pts.iter().enumerate() {
+            assert!(region.contains(a, default_tolerance())?);
+            assert!(!hole.strictly_contains(a, default_tolerance())?);
+            for b in &pts[i + 1..] {
+                assert!(a.dist(b) >= radius);
+            }
+        }
+        // a maximal packing fills the region: no disk of radius |radius|
+        // fits between the points, so they can't be too sparse.
+        let area = 100.0 - 16.0;
+        assert!(pts.len() as f64 > area / (4.0 * radius * radius));
+        Ok(())
+    }
+
+    #[test]
+    fn test_stipple_follows_density() -> Result<()> {
+        let region = Rect((0, 0), (10, 10))?;
+        let config = StippleConfig::builder()
+            .count(400)
+            .iterations(10)
+            .resolution(100)
+            .build();
+        let pts = stipple(&region, |pt| pt.x, &config, &mut StdRng::seed_from_u64(2))?;
+        assert_eq!(pts.len(), 400);
+        for pt in &pts {
+            assert!(region.contains(pt, default_tolerance())?);
+        }
+        // density x puts three times as many points right of center as left.
+        let left = pts.iter().filter(|pt| pt.x < 5.0).count() as f64;
+        assert_float_eq!(left / 400.0, 0.25, abs <= 0.05);
+
+        let nowhere = stipple(&region, |_| 0.0, &config, &mut StdRng::seed_from_u64(2));
+        assert!(nowhere.is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stipple_avoids_holes() -> Result<()> {
+        // a thin frame around a big hole: so few points that each one's cell
+        // bends around a corner, and has its centroid in the hole.
+        let hole = Rect((1, 1), (8, 8))?;
+        let region = PolygonWithCavities(Rect((0, 0), (10, 10))?, vec![hole.clone()])?;
+        let config = StippleConfig::builder()
+            .count(4)
+            .iterations(10)
+            .resolution(50)
+            .build();
+        let pts = stipple(&region, |_| 1.0, &config, &mut StdRng::seed_from_u64(4))?;
+        assert_eq!(pts.len(), 4);
+        for pt in &pts {
+            assert!(region.contains(pt, default_tolerance())?);
+            assert!(!hole.strictly_contains(pt, default_tolerance())?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_order_for_pen() -> Result<()> {
+        // a grid of points, visited in a scrambled order.
+        let mut pts: Vec<Point> = (0..400).map(|i| Point(i % 20, i / 20)).collect();
+        let mut rng = StdRng::seed_from_u64(3);
+        for i in (1..pts.len()).rev() {
+            pts.swap(i, rng.gen_range(0..=i));
+        }
+        let ordered = order_for_pen(&pts)?;
+        assert_eq!(ordered[0], Point(0, 0));
+        assert!(path_length(&ordered) < path_length(&pts) / 5.0);
+
+        let (mut a, mut b) = (pts.clone(), ordered.clone());
+        a.sort();
+        b.sort();
+        assert_eq!(a, b);
+        assert!(order_for_pen(&[])?.is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_stipple_dots() {
+        let pts = vec![Point(0, 0), Point(5, 0), Point(10, 0)];
+        let dots = stipple_dots(&pts, |pt| pt.x, 0.1..=0.5);
+        assert_float_eq!(dots[0].radius, 0.1, abs <= 1e-9);
+        assert_float_eq!(dots[1].radius, 0.3, abs <= 1e-9);
+        assert_float_eq!(dots[2].radius, 0.5, abs <= 1e-9);
+    }
+}
@@ -0,0 +1,520 @@
+//! A Bentley-Ottmann sweep over a set of segments, reporting every
+//! intersection, and the planar arrangement those segments make.
+
+use crate::{
+    bounded::Bounds,
+    intersection::{Intersection, IntersectionResult},
+    quadtree::QuadTree,
+    shapes::{point::Point, polygon::Polygon, segment::Segment},
+    tolerance,
+};
+use anyhow::Result;
+use float_ord::FloatOrd;
+use petgraph::prelude::UnGraphMap;
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+// The current tolerance, scaled by the magnitude of the input.
+fn tolerance_for(sgs: &[Segment]) -> f64 {
+    let scale = sgs
+        .iter()
+        .flat_map(|sg| [sg.i.x.abs(), sg.i.y.abs(), sg.f.x.abs(), sg.f.y.abs()])
+        .fold(1.0, f64::max);
+    tolerance::current().dist(scale)
+}
+
+// the fraction of the way along |sg| at which |pt| (which lies on it) sits.
+fn pct_along(sg: &Segment, pt: &Point) -> f64 {
+    let d = sg.f - sg.i;
+    ((*pt - sg.i).dot(&d) / d.dot(&d)).clamp(0.0, 1.0)
+}
+
+/// Classifies how two segments meet, if they do: at a single point, or along
+/// some shared stretch. Points within |tolerance| are considered coincident.
+pub fn classify(sa: &Segment, sb: &Segment, tolerance: f64) -> Option<IntersectionResult> {
+    if sa == sb {
+        return Some(IntersectionResult::ErrSegmentsAreTheSame);
+    }
+    if *sa == sb.flip() {
+        return Some(IntersectionResult::ErrSegmentsAreTheSameButReversed);
+    }
+    let (ra, rb) = (sa.f - sa.i, sb.f - sb.i);
+    let (la, lb) = (sa.length(), sb.length());
+    let denom = ra.cross_z(&rb);
+
+    if denom.abs() <= 1e-12 * la * lb {
+        // parallel. are they collinear, and if so how much do they share?
+        let off_line = if la == 0.0 {
+            sa.i.dist(&sb.i)
+        } else {
+            (sb.i - sa.i).cross_z(&ra).abs() / la
+        };
+        if off_line > tolerance {
+            return None;
+        }
+        let shared: Vec<Point> = [sb.i, sb.f]
+            .into_iter()
+            .filter(|p| sa.dist_to_point(p) <= tolerance)
+            .chain(
+                [sa.i, sa.f]
+                    .into_iter()
+                    .filter(|p| sb.dist_to_point(p) <= tolerance),
+            )
+            .collect();
+        let extent = shared
+            .iter()
+            .flat_map(|p| shared.iter().map(move |q| p.dist(q)))
+            .fold(0.0, f64::max);
+        return match shared.first() {
+            None => None,
+            Some(_) if extent > tolerance => Some(IntersectionResult::ErrSegmentsAreColinear),
+            Some(pt) => Intersection::new(*pt, pct_along(sa, pt), pct_along(sb, pt))
+                .map(IntersectionResult::Ok),
+        };
+    }
+
+    let q = sb.i - sa.i;
+    let t = q.cross_z(&rb) / denom;
+    let u = q.cross_z(&ra) / denom;
+    let (t_slack, u_slack) = (tolerance / la, tolerance / lb);
+    if t < -t_slack || t > 1.0 + t_slack || u < -u_slack || u > 1.0 + u_slack {
+        return None;
+    }
+    let t = t.clamp(0.0, 1.0);
+    // snap to endpoints, so that touching segments report exact points.
+    let pt = [sa.i, sa.f, sb.i, sb.f]
+        .into_iter()
+        .find(|p| p.dist(&(sa.i + ra * t)) <= tolerance)
+        .unwrap_or(sa.i + ra * t);
+    Intersection::new(pt, pct_along(sa, &pt), pct_along(sb, &pt)).map(IntersectionResult::Ok)
+}
+
+// A segment, stored left-to-right for the sweep.
+struct Swept {
+    l: Point,
+    r: Point,
+}
+
+impl Swept {
+    // the height of this segment where it crosses the sweep line at |p|.
+    fn y_at(&self, p: &Point) -> f64 {
+        if self.l.x == self.r.x {
+            return p.y.clamp(self.l.y, self.r.y);
+        }
+        let x = p.x.clamp(self.l.x, self.r.x);
+        self.l.y + (x - self.l.x) * (self.r.y - self.l.y) / (self.r.x - self.l.x)
+    }
+
+    // the angle at which this segment leaves the sweep line.
+    fn angle(&self) -> f64 {
+        let d = self.r - self.l;
+        d.y.atan2(d.x)
+    }
+
+    fn segment(&self) -> Segment {
+        Segment(self.l, self.r)
+    }
+}
+
+struct Sweep<'a> {
+    sgs: &'a [Segment],
+    swept: Vec<Swept>,
+    tolerance: f64,
+    events: BTreeMap<Point, Vec<usize>>,
+    status: Vec<usize>,
+    reported: BTreeMap<(usize, usize), IntersectionResult>,
+}
+
+impl<'a> Sweep<'a> {
+    fn new(sgs: &'a [Segment]) -> Sweep<'a> {
+        let tolerance = tolerance_for(sgs);
+        let mut events: BTreeMap<Point, Vec<usize>> = BTreeMap::new();
+        let swept: Vec<Swept> = sgs
+            .iter()
+            .map(|sg| {
+                let (l, r) = if sg.i <= sg.f {
+                    (sg.i, sg.f)
+                } else {
+                    (sg.f, sg.i)
+                };
+                Swept { l, r }
+            })
+            .collect();
+        for (idx, s) in swept.iter().enumerate() {
+            if s.l.dist(&s.r) <= tolerance {
+                // degenerate segments have no direction to sweep along.
+                continue;
+            }
+            events.entry(s.l).or_default().push(idx);
+            events.entry(s.r).or_default();
+        }
+        Sweep {
+            sgs,
+            swept,
+            tolerance,
+            events,
+            status: vec![],
+            reported: BTreeMap::new(),
+        }
+    }
+
+    fn contains(&self, s: usize, p: &Point) -> bool {
+        self.swept[s].segment().dist_to_point(p) <= self.tolerance
+    }
+
+    // schedules an event where |s| and |t| cross, if that is ahead of |p|.
+    fn schedule(&mut self, s: usize, t: usize, p: &Point) {
+        if let Some(IntersectionResult::Ok(isxn)) = classify(
+            &self.swept[s].segment(),
+            &self.swept[t].segment(),
+            self.tolerance,
+        ) {
+            if isxn.pt > *p && isxn.pt.dist(p) > self.tolerance {
+                self.events.entry(isxn.pt).or_default();
+            }
+        }
+    }
+
+    fn run(mut self) -> BTreeMap<(usize, usize), IntersectionResult> {
+        while let Some((p, starting)) = self.events.pop_first() {
+            // the segments which pass through or end at |p| sit together in
+            // the status. find them.
+            let lo = self
+                .status
+                .partition_point(|s| self.swept[*s].y_at(&p) < p.y - self.tolerance);
+            let mut hi = lo;
+            while hi < self.status.len()
+                && self.swept[self.status[hi]].y_at(&p) <= p.y + self.tolerance
+            {
+                hi += 1;
+            }
+            let (through, others): (Vec<usize>, Vec<usize>) = self.status[lo..hi]
+                .iter()
+                .partition(|s| self.contains(**s, &p));
+
+            // report every pair meeting here.
+            let mut here: Vec<usize> = through.iter().chain(starting.iter()).copied().collect();
+            here.sort_unstable();
+            here.dedup();
+            for (i, a) in here.iter().enumerate() {
+                for b in &here[i + 1..] {
+                    if self.reported.contains_key(&(*a, *b)) {
+                        continue;
+                    }
+                    if let Some(result) = classify(&self.sgs[*a], &self.sgs[*b], self.tolerance) {
+                        self.reported.insert((*a, *b), result);
+                    }
+                }
+            }
+
+            // everything through |p| which continues past it gets reinserted,
+            // along with everything starting at |p|, in the order in which
+            // they leave |p|.
+            let mut continuing: Vec<usize> = through
+                .iter()
+                .filter(|s| self.swept[**s].r.dist(&p) > self.tolerance)
+                .chain(starting.iter())
+                .copied()
+                .collect();
+            continuing.sort_by_key(|s| (FloatOrd(self.swept[*s].angle()), *s));
+            continuing.dedup();
+
+            let mut replacement = others.clone();
+            let insert_at = lo + replacement.len();
+            replacement.extend(continuing.iter().copied());
+            self.status.splice(lo..hi, replacement);
+
+            if continuing.is_empty() {
+                if insert_at > 0 && insert_at < self.status.len() {
+                    let (s, t) = (self.status[insert_at - 1], self.status[insert_at]);
+                    self.schedule(s, t, &p);
+                }
+            } else {
+                let first = insert_at;
+                let last = insert_at + continuing.len() - 1;
+                if first > 0 {
+                    let (s, t) = (self.status[first - 1], self.status[first]);
+                    self.schedule(s, t, &p);
+                }
+                if last + 1 < self.status.len() {
+                    let (s, t) = (self.status[last], self.status[last + 1]);
+                    self.schedule(s, t, &p);
+                }
+            }
+        }
+        self.reported
+    }
+}
+
+/// Every pair of segments (a, b), a < b, which meet, and how. Single points of
+/// contact (crossings, and ends touching anything) are |IntersectionResult::Ok|
+/// with percents relative to the segments as given; segments sharing a stretch
+/// are |ErrSegmentsAreColinear| (or |ErrSegmentsAreTheSame*|).
+///
+/// For n segments and k intersections there are O(n + k) events. Each finds
+/// its place in the sweep's status by binary search, but the status is a
+/// sorted |Vec|, so updating it shifts up to n entries: O((n + k) n) in all,
+/// though the shifts are cheap memmoves in practice.
+pub fn all_intersections(sgs: &[Segment]) -> Vec<(usize, usize, IntersectionResult)> {
+    Sweep::new(sgs)
+        .run()
+        .into_iter()
+        .map(|((a, b), r)| (a, b, r))
+        .collect()
+}
+
+/// The planar graph made by splitting a set of segments at every point where
+/// they meet. Nodes are points; each edge carries the indices of the input
+/// segments which run along it.
+pub struct Arrangement {
+    /// The split edges, each tagged with the input segments along it.
+    pub graph: UnGraphMap<Point, Vec<usize>>,
+}
+
+impl Arrangement {
+    /// Builds the arrangement of a set of segments.
+    pub fn new(sgs: &[Segment]) -> Arrangement {
+        let tolerance = tolerance_for(sgs);
+
+        // the points at which each segment is cut.
+        let mut cuts: Vec<Vec<Point>> = sgs.iter().map(|sg| vec![sg.i, sg.f]).collect();
+        for (a, b, result) in all_intersections(sgs) {
+            match result {
+                IntersectionResult::Ok(isxn) => {
+                    cuts[a].push(isxn.pt);
+                    cuts[b].push(isxn.pt);
+                }
+                _ => {
+                    // collinear; each is cut by the other's ends.
+                    for (x, y) in [(a, b), (b, a)] {
+                        for pt in [sgs[y].i, sgs[y].f] {
+                            if sgs[x].dist_to_point(&pt) <= tolerance {
+                                cuts[x].push(pt);
+                            }
+                        }
+                    }
+                }
+            }
+        }
+
+        // snap nearly-coincident points together.
+        let mut known: QuadTree<Point> = QuadTree::new();
+        let mut snap = |pt: Point| -> Point {
+            let window = Bounds {
+                x_min: pt.x - tolerance,
+                x_max: pt.x + tolerance,
+                y_min: pt.y - tolerance,
+                y_max: pt.y + tolerance,
+            };
+            if let Some(idx) = known.query(&window).first() {
+                return known.get(*idx).unwrap().1;
+            }
+            known.insert(window, pt);
+            pt
+        };
+
+        let mut graph: UnGraphMap<Point, Vec<usize>> = UnGraphMap::new();
+        for (idx, (sg, mut pts)) in sgs.iter().zip(cuts).enumerate() {
+            pts.sort_by_key(|pt| FloatOrd(pct_along(sg, pt)));
+            let mut nodes: Vec<Point> = pts.into_iter().map(&mut snap).collect();
+            nodes.dedup();
+            for w in nodes.windows(2) {
+                if let Some(e) = graph.edge_weight_mut(w[0], w[1]) {
+                    if !e.contains(&idx) {
+                        e.push(idx);
+                    }
+                } else {
+                    graph.add_edge(w[0], w[1], vec![idx]);
+                }
+            }
+        }
+        Arrangement { graph }
+    }
+
+    /// The neighbors of a node, sorted counterclockwise by angle.
+    pub fn neighbors_ccw(&self, pt: Point) -> Vec<Point> {
+        let mut ns: Vec<Point> = self.graph.neighbors(pt).collect();
+        ns.sort_by_key(|n| FloatOrd((n.y - pt.y).atan2(n.x - pt.x)));
+        ns
+    }
+
+    /// The bounded faces of the arrangement, as polygons. Edges which don't
+    /// bound any face (i.e. dangling ones) are ignored.
+    pub fn faces(&self) -> Result<Vec<Polygon>> {
+        // prune dangling edges first.
+        let mut g = self.graph.clone();
+        while let Some(n) = g.nodes().find(|n| g.neighbors(*n).count() < 2) {
+            g.remove_node(n);
+        }
+        let pruned = Arrangement { graph: g };
+        let ccw: HashMap<Point, Vec<Point>> = pruned
+            .graph
+            .nodes()
+            .map(|n| (n, pruned.neighbors_ccw(n)))
+            .collect();
+
+        // walk each directed edge once, always turning as far right as
+        // possible, which traces faces counterclockwise.
+        let mut visited: BTreeSet<(Point, Point)> = BTreeSet::new();
+        let mut faces = vec![];
+        for (u, v, _) in pruned.graph.all_edges() {
+            for (start_u, start_v) in [(u, v), (v, u)] {
+                if visited.contains(&(start_u, start_v)) {
+                    continue;
+                }
+                let mut pts = vec![];
+                let (mut a, mut b) = (start_u, start_v);
+                while visited.insert((a, b)) {
+                    pts.push(a);
+                    let around = &ccw[&b];
+                    let k = around.iter().position(|n| *n == a).unwrap();
+                    let next = around[(k + around.len() - 1) % around.len()];
+                    (a, b) = (b, next);
+                }
+                if pts.len() >= 3 {
+                    let area: f64 = pts
+                        .iter()
+                        .zip(pts.iter().cycle().skip(1))
+                        .map(|(p, q)| p.cross_z(q))
+                        .sum();
+                    if area > 0.0 {
+                        // start each face at its least point, for stability.
+                        let k = (0..pts.len()).min_by_key(|k| pts[*k]).unwrap();
+                        pts.rotate_left(k);
+                        faces.push(Polygon(pts)?);
+                    }
+                }
+            }
+        }
+        Ok(faces)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::BTreeSet;
+
+    fn bruteforce(sgs: &[Segment]) -> BTreeSet<(usize, usize)> {
+        let tol = tolerance_for(sgs);
+        let mut r = BTreeSet::new();
+        for i in 0..sgs.len() {
+            for j in (i + 1)..sgs.len() {
+                if classify(&sgs[i], &sgs[j], tol).is_some() {
+                    r.insert((i, j));
+                }
+            }
+        }
+        r
+    }
+
+    fn pairs(sgs: &[Segment]) -> BTreeSet<(usize, usize)> {
+        all_intersections(sgs)
+            .into_iter()
+            .map(|(a, b, _)| (a, b))
+            .collect()
+    }
+
+    #[test]
+    fn test_simple_crossing() {
+        let sgs = vec![Segment((0, 0), (2, 2)), Segment((0, 2), (2, 0))];
+        let actual = all_intersections(&sgs);
+        assert_eq!(actual.len(), 1);
+        let (a, b, IntersectionResult::Ok(isxn)) = actual[0] else {
+            panic!("expected a point");
+        };
+        assert_eq!((a, b), (0, 1));
+        assert_eq!(isxn.pt, Point(1, 1));
+        assert_eq!(isxn.percent_along_a(), FloatOrd(0.5));
+    }
+
+    #[test]
+    fn test_degenerate_cases() {
+        let sgs = vec![
+            // touching end-to-end.
+            Segment((0, 0), (1, 0)),
+            Segment((1, 0), (2, 1)),
+            // collinear overlap.
+            Segment((0, 5), (3, 5)),
+            Segment((2, 5), (4, 5)),
+            // the same, and the same but reversed.
+            Segment((0, 8), (1, 9)),
+            Segment((0, 8), (1, 9)),
+            Segment((1, 9), (0, 8)),
+            // a vertical, crossed.
+            Segment((10, 0), (10, 4)),
+            Segment((9, 2), (11, 2)),
+            // t-junction.
+            Segment((20, 0), (24, 0)),
+            Segment((22, 0), (22, 3)),
+        ];
+        let actual = all_intersections(&sgs);
+        let find = |a, b| {
+            actual
+                .iter()
+                .find(|(x, y, _)| (*x, *y) == (a, b))
+                .map(|t| t.2)
+        };
+        assert!(matches!(find(0, 1), Some(IntersectionResult::Ok(_))));
+        assert_eq!(find(2, 3), Some(IntersectionResult::ErrSegmentsAreColinear));
+        assert_eq!(find(4, 5), Some(IntersectionResult::ErrSegmentsAreTheSame));
+        assert_eq!(
+            find(4, 6),
+            Some(IntersectionResult::ErrSegmentsAreTheSameButReversed)
+        );
+        assert!(matches!(find(7, 8), Some(IntersectionResult::Ok(i)) if i.pt == Point(10, 2)));
+        assert!(matches!(find(9, 10), Some(IntersectionResult::Ok(i)) if i.pt == Point(22, 0)));
+        assert_eq!(pairs(&sgs), bruteforce(&sgs));
+    }
+
+    #[test]
+    fn test_matches_bruteforce_on_a_dense_grid() {
+        // a deterministic jumble of segments on an integer lattice, which
+        // is full of shared endpoints, verticals and collinear overlaps.
+        let mut seed: u64 = 12345;
+        let mut next = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            ((seed >> 33) % 12) as f64
+        };
+        let sgs: Vec<Segment> = (0..150)
+            .map(|_| Segment((next(), next()), (next(), next())))
+            .filter(|sg| sg.i != sg.f)
+            .collect();
+        assert_eq!(pairs(&sgs), bruteforce(&sgs));
+    }
+
+    #[test]
+    fn test_arrangement() -> Result<()> {
+        // a square with both diagonals makes four triangular faces.
+        let sgs = vec![
+            Segment((0, 0), (2, 0)),
+            Segment((2, 0), (2, 2)),
+            Segment((2, 2), (0, 2)),
+            Segment((0, 2), (0, 0)),
+            Segment((0, 0), (2, 2)),
+            Segment((0, 2), (2, 0)),
+            // and a dangling edge.
+            Segment((2, 2), (3, 3)),
+        ];
+        let arr = Arrangement::new(&sgs);
+        assert_eq!(arr.graph.node_count(), 6);
+        assert_eq!(arr.graph.edge_count(), 9);
+        assert_eq!(
+            arr.neighbors_ccw(Point(1, 1)),
+            vec![Point(0, 0), Point(2, 0), Point(2, 2), Point(0, 2)]
+        );
+        let mut faces = arr.faces()?;
+        faces.sort();
+        assert_eq!(
+            faces,
+            vec![
+                Polygon([(0, 0), (1, 1), (0, 2)])?,
+                Polygon([(0, 0), (2, 0), (1, 1)])?,
+                Polygon([(0, 2), (1, 1), (2, 2)])?,
+                Polygon([(1, 1), (2, 0), (2, 2)])?,
+            ]
+        );
+        Ok(())
+    }
+}
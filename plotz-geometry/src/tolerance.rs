@@ -0,0 +1,272 @@
+//! Geometric tolerance, and robust orientation and incircle predicates.
+//!
+//! Points compare and hash exactly, so that they can key maps and graphs.
+//! Everywhere geometry needs to decide whether two values are "close enough"
+//! -- percents snapping to endpoints, points landing on segments, crop graph
+//! nodes merging -- it asks the current |Tolerance| instead. By default that
+//! is a few ulps; geometry at very large or very small scales should run
+//! under a tolerance of its own (see |with_tolerance|).
+//!
+//! The tolerance in effect is per thread. Work handed to other threads (e.g.
+//! rayon's) starts from the default, so pass |current()| along and re-enter it
+//! with |with_tolerance| there.
+//!
+//! The predicates |orient2d| and |incircle| are exact in sign: they evaluate in
+//! floating point when the result is clearly nonzero, and fall back to exact
+//! rational arithmetic when it isn't.
+
+use crate::{bounded::Bounds, shapes::point::Point};
+use num::{bigint::BigInt, rational::Ratio, Signed, ToPrimitive, Zero};
+use std::cell::Cell;
+
+/// How far apart two values may be and still be considered equal.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct Tolerance {
+    /// Absolute slack, in units of the values compared.
+    pub abs: f64,
+    /// Relative slack, as a fraction of the larger magnitude compared.
+    pub rel: f64,
+}
+
+impl Default for Tolerance {
+    /// A few ulps, matching |float_cmp::approx_eq!|'s defaults.
+    fn default() -> Self {
+        Tolerance {
+            abs: f64::EPSILON,
+            rel: 4.0 * f64::EPSILON,
+        }
+    }
+}
+
+impl Tolerance {
+    /// A tolerance suited to geometry spanning roughly |scale| units: values
+    /// within a billionth of that are equal.
+    pub fn for_scale(scale: f64) -> Tolerance {
+        Tolerance {
+            abs: scale.abs() * 1e-9,
+            ..Default::default()
+        }
+    }
+
+    /// A tolerance suited to geometry within |bounds|.
+    pub fn for_bounds(bounds: &Bounds) -> Tolerance {
+        Tolerance::for_scale((bounds.x_max - bounds.x_min).max(bounds.y_max - bounds.y_min))
+    }
+
+    /// True if |a| and |b| are equal within this tolerance.
+    pub fn eq(&self, a: f64, b: f64) -> bool {
+        if a == b {
+            return true;
+        }
+        if !a.is_finite() || !b.is_finite() {
+            return false;
+        }
+        let d = (a - b).abs();
+        d <= self.abs || d <= self.rel * a.abs().max(b.abs())
+    }
+
+    /// True if |a| is zero within this tolerance.
+    pub fn is_zero(&self, a: f64) -> bool {
+        self.eq(a, 0.0)
+    }
+
+    /// How close two points of geometry spanning roughly |scale| units of the
+    /// origin may be and still coincide.
+    pub fn dist(&self, scale: f64) -> f64 {
+        self.abs.max(self.rel * scale.abs())
+    }
+
+    /// True if |a| and |b| coincide within this tolerance.
+    pub fn pts_eq(&self, a: &Point, b: &Point) -> bool {
+        self.eq(a.x, b.x) && self.eq(a.y, b.y)
+    }
+
+    /// True if |c| lies on the line through |a| and |b|, i.e. exactly so, or
+    /// within |abs| of it.
+    pub fn collinear(&self, a: &Point, b: &Point, c: &Point) -> bool {
+        let o = orient2d(a, b, c);
+        o == 0.0 || o.abs() <= self.abs * a.dist(b)
+    }
+}
+
+thread_local! {
+    static CURRENT: Cell<Tolerance> = Cell::new(Tolerance::default());
+}
+
+/// The tolerance in effect on this thread.
+pub fn current() -> Tolerance {
+    CURRENT.with(|c| c.get())
+}
+
+/// Runs |f| with |tolerance| in effect on this thread, then restores the
+/// previous one (even if |f| panics).
+pub fn with_tolerance<R>(tolerance: Tolerance, f: impl FnOnce() -> R) -> R {
+    struct Restore(Tolerance);
+    impl Drop for Restore {
+        fn drop(&mut self) {
+            CURRENT.with(|c| c.set(self.0));
+        }
+    }
+    let _restore = Restore(CURRENT.with(|c| c.replace(tolerance)));
+    f()
+}
+
+// The unit roundoff, and Shewchuk's error bounds for the floating-point
+// evaluations below. See https://www.cs.cmu.edu/~quake/robust.html
+const EPS: f64 = f64::EPSILON / 2.0;
+const CCW_ERRBOUND: f64 = (3.0 + 16.0 * EPS) * EPS;
+const ICC_ERRBOUND: f64 = (10.0 + 96.0 * EPS) * EPS;
+
+type Exact = Ratio<BigInt>;
+
+fn exact(f: f64) -> Exact {
+    Exact::from_float(f).unwrap_or_else(Exact::zero)
+}
+
+// An exact value, as an f64 of the same sign.
+fn to_f64_signed(e: &Exact) -> f64 {
+    if e.is_zero() {
+        return 0.0;
+    }
+    match e.to_f64() {
+        Some(f) if f != 0.0 => f,
+        // too small to represent; all that matters is the sign.
+        _ if e.is_positive() => f64::MIN_POSITIVE,
+        _ => -f64::MIN_POSITIVE,
+    }
+}
+
+/// Twice the signed area of the triangle (a, b, c): positive if the points
+/// turn counterclockwise, negative if clockwise, and zero if (exactly)
+/// collinear. The sign is always correct.
+pub fn orient2d(a: &Point, b: &Point, c: &Point) -> f64 {
+    let detleft = (a.x - c.x) * (b.y - c.y);
+    let detright = (a.y - c.y) * (b.x - c.x);
+    let det = detleft - detright;
+    if det.abs() > CCW_ERRBOUND * (detleft.abs() + detright.abs()) {
+        return det;
+    }
+
+    let (ax, ay, bx, by, cx, cy) = (
+        exact(a.x),
+        exact(a.y),
+        exact(b.x),
+        exact(b.y),
+        exact(c.x),
+        exact(c.y),
+    );
+    to_f64_signed(&((&ax - &cx) * (&by - &cy) - (&ay - &cy) * (&bx - &cx)))
+}
+
+/// Positive if |d| lies inside the circle through the counterclockwise
+/// triangle (a, b, c), negative if outside, and zero if (exactly) on it. The
+/// sign is always correct.
+pub fn incircle(a: &Point, b: &Point, c: &Point, d: &Point) -> f64 {
+    let (adx, ady) = (a.x - d.x, a.y - d.y);
+    let (bdx, bdy) = (b.x - d.x, b.y - d.y);
+    let (cdx, cdy) = (c.x - d.x, c.y - d.y);
+
+    let (bdxcdy, cdxbdy) = (bdx * cdy, cdx * bdy);
+    let (cdxady, adxcdy) = (cdx * ady, adx * cdy);
+    let (adxbdy, bdxady) = (adx * bdy, bdx * ady);
+    let alift = adx * adx + ady * ady;
+    let blift = bdx * bdx + bdy * bdy;
+    let clift = cdx * cdx + cdy * cdy;
+
+    let det = alift * (bdxcdy - cdxbdy) + blift * (cdxady - adxcdy) + clift * (adxbdy - bdxady);
+    let permanent = (bdxcdy.abs() + cdxbdy.abs()) * alift
+        + (cdxady.abs() + adxcdy.abs()) * blift
+        + (adxbdy.abs() + bdxady.abs()) * clift;
+    if det.abs() > ICC_ERRBOUND * permanent {
+        return det;
+    }
+
+    let (dx, dy) = (exact(d.x), exact(d.y));
+    let [(adx, ady), (bdx, bdy), (cdx, cdy)] =
+        [a, b, c].map(|p| (exact(p.x) - &dx, exact(p.y) - &dy));
+    let alift = &adx * &adx + &ady * &ady;
+    let blift = &bdx * &bdx + &bdy * &bdy;
+    let clift = &cdx * &cdx + &cdy * &cdy;
+    to_f64_signed(
+        &(alift * (&bdx * &cdy - &cdx * &bdy)
+            + blift * (&cdx * &ady - &adx * &cdy)
+            + clift * (&adx * &bdy - &bdx * &ady)),
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{bounded::Bounded, crop::Croppable, shapes::polygon::Rect};
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_orient2d_signs() {
+        assert!(orient2d(&Point(0, 0), &Point(1, 0), &Point(0, 1)) > 0.0);
+        assert!(orient2d(&Point(0, 0), &Point(0, 1), &Point(1, 0)) < 0.0);
+        assert_eq!(orient2d(&Point(0, 0), &Point(1, 1), &Point(2, 2)), 0.0);
+    }
+
+    #[test]
+    fn test_orient2d_is_exact_near_degeneracy() {
+        // points a hair off the line y = x, far from the origin, where the
+        // naive determinant rounds to the wrong sign (or to zero).
+        let (a, b) = (Point(12.0, 12.0), Point(24.0, 24.0));
+        for k in 1..64 {
+            let c = Point(0.5 + k as f64 * f64::EPSILON, 0.5);
+            assert!(orient2d(&a, &b, &c) < 0.0, "k={}", k);
+            let c = Point(0.5, 0.5 + k as f64 * f64::EPSILON);
+            assert!(orient2d(&a, &b, &c) > 0.0, "k={}", k);
+        }
+        assert_eq!(orient2d(&a, &b, &Point(0.5, 0.5)), 0.0);
+    }
+
+    #[test]
+    fn test_incircle() {
+        let (a, b, c) = (Point(0, 0), Point(1, 0), Point(0, 1));
+        assert!(incircle(&a, &b, &c, &Point(0.5, 0.5)) > 0.0);
+        assert!(incircle(&a, &b, &c, &Point(2, 2)) < 0.0);
+        // cocircular.
+        assert_eq!(incircle(&a, &b, &c, &Point(1, 1)), 0.0);
+        let far = 1e6;
+        let (a, b, c) = (
+            Point(far, far),
+            Point(far + 1.0, far),
+            Point(far, far + 1.0),
+        );
+        assert_eq!(incircle(&a, &b, &c, &Point(far + 1.0, far + 1.0)), 0.0);
+    }
+
+    #[test]
+    fn test_with_tolerance_is_scoped() {
+        assert_eq!(current(), Tolerance::default());
+        assert!(!current().eq(1.0, 1.001));
+        let inner = with_tolerance(Tolerance::for_scale(1e7), || {
+            assert!(current().eq(1.0, 1.001));
+            current()
+        });
+        assert_eq!(inner.abs, 1e-2);
+        assert_eq!(current(), Tolerance::default());
+    }
+
+    #[test]
+    fn test_collinear() {
+        let tol = Tolerance::for_scale(1.0);
+        assert!(tol.collinear(&Point(0, 0), &Point(1, 0), &Point(5, 1e-10)));
+        assert!(!tol.collinear(&Point(0, 0), &Point(1, 0), &Point(5, 1e-8)));
+    }
+
+    #[test]
+    fn test_crop_is_stable_across_scales() -> anyhow::Result<()> {
+        // the same crop, at unit scale and far out at map scale.
+        for (offset, scale) in [(0.0, 1.0), (4e6, 1e3), (-1e-3, 1e-6)] {
+            let a = Rect((0, 0), (1, 1))? * scale + offset;
+            let b = Rect((0.25, 0.5), (1, 1))? * scale + offset;
+            let tol = Tolerance::for_bounds(&a.bounds()?);
+            let cropped = with_tolerance(tol, || a.crop_to(&b))?;
+            assert_eq!(cropped.len(), 1);
+            assert_float_eq!(cropped[0].area() / (scale * scale), 0.375, abs <= 1e-6);
+        }
+        Ok(())
+    }
+}
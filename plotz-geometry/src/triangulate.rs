@@ -0,0 +1,210 @@
+//! Ear-clipping triangulation of polygons (with or without cavities).
+
+use crate::{
+    shapes::{point::Point, polygon::Polygon},
+    tolerance::orient2d,
+};
+use anyhow::{anyhow, Result};
+
+// true if |p| lies inside or on the border of the counterclockwise triangle
+// (a, b, c).
+fn in_triangle(p: &Point, a: &Point, b: &Point, c: &Point) -> bool {
+    orient2d(a, b, p) >= 0.0 && orient2d(b, c, p) >= 0.0 && orient2d(c, a, p) >= 0.0
+}
+
+// Splices |hole| (clockwise) into |ring| (counterclockwise) along a bridge
+// from the hole's rightmost vertex to a visible vertex of the ring. See
+// https://www.geometrictools.com/Documentation/TriangulationByEarClipping.pdf
+fn bridge_hole(ring: &mut Vec<Point>, hole: &[Point]) -> Result<()> {
+    let (m_idx, m) = hole
+        .iter()
+        .enumerate()
+        .max_by(|(_, a), (_, b)| a.x.total_cmp(&b.x))
+        .map(|(i, p)| (i, *p))
+        .ok_or_else(|| anyhow!("empty hole"))?;
+
+    // cast a ray in +x from |m| and find the closest edge it hits.
+    let n = ring.len();
+    let mut best: Option<(f64, usize)> = None;
+    for k in 0..n {
+        let (a, b) = (ring[k], ring[(k + 1) % n]);
+        if (a.y < m.y && b.y < m.y) || (a.y > m.y && b.y > m.y) || a.y == b.y {
+            continue;
+        }
+        let x = a.x + (m.y - a.y) / (b.y - a.y) * (b.x - a.x);
+        if x < m.x {
+            continue;
+        }
+        if best.is_none_or(|(bx, _)| x < bx) {
+            best = Some((x, k));
+        }
+    }
+    let (ix, k) = best.ok_or_else(|| anyhow!("hole is not inside of outer ring"))?;
+    let i = Point(ix, m.y);
+    let (a, b) = (ring[k], ring[(k + 1) % n]);
+    let mut p_idx = if a.x > b.x { k } else { (k + 1) % n };
+
+    // if any reflex vertex of the ring lies within (m, i, p), bridge to the
+    // one making the smallest angle with the ray instead.
+    let p = ring[p_idx];
+    let mut best_angle = f64::MAX;
+    for (j, q) in ring.iter().enumerate() {
+        if j == p_idx || q.x < m.x {
+            continue;
+        }
+        let (prev, next) = (ring[(j + n - 1) % n], ring[(j + 1) % n]);
+        let reflex = orient2d(&prev, q, &next) <= 0.0;
+        let inside = if orient2d(&m, &i, &p) >= 0.0 {
+            in_triangle(q, &m, &i, &p)
+        } else {
+            in_triangle(q, &m, &p, &i)
+        };
+        if reflex && inside {
+            let angle = (q.y - m.y).abs().atan2(q.x - m.x);
+            if angle < best_angle {
+                best_angle = angle;
+                p_idx = j;
+            }
+        }
+    }
+
+    let mut spliced: Vec<Point> = ring[..=p_idx].to_vec();
+    spliced.extend(hole[m_idx..].iter().chain(hole[..=m_idx].iter()));
+    spliced.extend(ring[p_idx..].iter());
+    *ring = spliced;
+    Ok(())
+}
+
+/// Triangulates the region inside |outer| and outside each of |holes| by ear
+/// clipping. Returns the triangles, each positively oriented.
+pub fn triangulate(outer: &Polygon, holes: &[Polygon]) -> Result<Vec<Polygon>> {
+    let mut ring: Vec<Point> = outer.pts.clone();
+    if outer.signed_area() < 0.0 {
+        ring.reverse();
+    }
+
+    let mut holes: Vec<Vec<Point>> = holes
+        .iter()
+        .map(|h| {
+            let mut pts = h.pts.clone();
+            if h.signed_area() > 0.0 {
+                pts.reverse();
+            }
+            pts
+        })
+        .collect();
+    holes.sort_by(|a, b| {
+        let max_x = |h: &Vec<Point>| h.iter().map(|p| p.x).fold(f64::MIN, f64::max);
+        max_x(b).total_cmp(&max_x(a))
+    });
+    for hole in &holes {
+        bridge_hole(&mut ring, hole)?;
+    }
+
+    let mut triangles: Vec<Polygon> = vec![];
+    let mut idxs: Vec<usize> = (0..ring.len()).collect();
+    while idxs.len() > 3 {
+        let n = idxs.len();
+        let ear = (0..n).find(|&k| {
+            let (a, b, c) = (
+                &ring[idxs[(k + n - 1) % n]],
+                &ring[idxs[k]],
+                &ring[idxs[(k + 1) % n]],
+            );
+            if orient2d(a, b, c) <= 0.0 {
+                return false;
+            }
+            // no other vertex may sit within the ear. vertices which coincide
+            // with the ear's corners (from bridges) don't count.
+            idxs.iter().all(|&j| {
+                let q = &ring[j];
+                q == a || q == b || q == c || !in_triangle(q, a, b, c)
+            })
+        });
+        match ear {
+            Some(k) => {
+                let (a, b, c) = (
+                    ring[idxs[(k + n - 1) % n]],
+                    ring[idxs[k]],
+                    ring[idxs[(k + 1) % n]],
+                );
+                triangles.push(Polygon([a, b, c])?);
+                idxs.remove(k);
+            }
+            None => {
+                // only degenerate (zero-area) corners remain.
+                let k = (0..n)
+                    .find(|&k| {
+                        orient2d(
+                            &ring[idxs[(k + n - 1) % n]],
+                            &ring[idxs[k]],
+                            &ring[idxs[(k + 1) % n]],
+                        ) == 0.0
+                    })
+                    .ok_or_else(|| anyhow!("polygon is not simple; can't triangulate."))?;
+                idxs.remove(k);
+            }
+        }
+    }
+    if let [a, b, c] = idxs[..] {
+        if orient2d(&ring[a], &ring[b], &ring[c]) > 0.0 {
+            triangles.push(Polygon([ring[a], ring[b], ring[c]])?);
+        }
+    }
+    Ok(triangles)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        containment::{default_tolerance, Locate},
+        shapes::{polygon::Rect, polygon_with_cavity::PolygonWithCavities},
+    };
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_triangulate_square() -> Result<()> {
+        let triangles = Rect((0, 0), (2, 2))?.triangulate()?;
+        assert_eq!(triangles.len(), 2);
+        assert_float_eq!(
+            triangles.iter().map(|t| t.area()).sum::<f64>(),
+            4.0,
+            abs <= 1e-9
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_triangulate_concave() -> Result<()> {
+        let ell = Polygon([(0, 0), (2, 0), (2, 1), (1, 1), (1, 2), (0, 2)])?;
+        let triangles = ell.triangulate()?;
+        assert_eq!(triangles.len(), 4);
+        assert_float_eq!(
+            triangles.iter().map(|t| t.area()).sum::<f64>(),
+            3.0,
+            abs <= 1e-9
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_triangulate_with_cavities() -> Result<()> {
+        let pgc = PolygonWithCavities(
+            Rect((0, 0), (10, 10))?,
+            vec![Rect((2, 2), (2, 2))?, Rect((6, 5), (2, 3))?],
+        )?;
+        let triangles = pgc.triangulate()?;
+        assert_float_eq!(
+            triangles.iter().map(|t| t.area()).sum::<f64>(),
+            100.0 - 4.0 - 6.0,
+            abs <= 1e-9
+        );
+        // no triangle covers a hole.
+        for t in &triangles {
+            assert!(!t.strictly_contains(&Point(3, 3), default_tolerance())?);
+            assert!(!t.strictly_contains(&Point(7, 6.5), default_tolerance())?);
+        }
+        Ok(())
+    }
+}
@@ -1,7 +1,7 @@
 use std::cmp::Ordering;
 
+use crate::{bounded::Bounds, shapes::point::Point, tolerance};
 use anyhow::{anyhow, Result};
-use float_cmp::approx_eq;
 use float_ord::FloatOrd;
 
 #[derive(Debug, Copy, Clone)]
@@ -42,9 +42,10 @@ pub enum Percent {
 }
 impl Percent {
     pub fn new(f: f64) -> Result<Percent> {
+        let tol = tolerance::current();
         match f {
-            f if approx_eq!(f64, f, 0.0) => Ok(Percent::Zero),
-            f if approx_eq!(f64, f, 1.0) => Ok(Percent::One),
+            f if tol.eq(f, 0.0) => Ok(Percent::Zero),
+            f if tol.eq(f, 1.0) => Ok(Percent::One),
             f if (0.0..=1.0).contains(&f) => Ok(Percent::Val(f)),
             _ => Err(anyhow!("f not in 0.0..=1.0")),
         }
@@ -95,3 +96,92 @@ impl core::hash::Hash for Percent {
         core::mem::discriminant(self).hash(state);
     }
 }
+
+// splitmix64.
+pub fn splitmix64(mut z: u64) -> u64 {
+    z = z.wrapping_add(0x9E3779B97F4A7C15);
+    z = (z ^ (z >> 30)).wrapping_mul(0xBF58476D1CE4E5B9);
+    z = (z ^ (z >> 27)).wrapping_mul(0x94D049BB133111EB);
+    z ^ (z >> 31)
+}
+
+// Points bucketed into a uniform grid, for nearest-neighbor queries.
+pub struct PointGrid {
+    origin: Point,
+    cell: f64,
+    w: usize,
+    h: usize,
+    buckets: Vec<Vec<usize>>,
+}
+
+impl PointGrid {
+    pub fn new(bounds: &Bounds, cell: f64) -> PointGrid {
+        let w = (((bounds.x_max - bounds.x_min) / cell).ceil() as usize).max(1);
+        let h = (((bounds.y_max - bounds.y_min) / cell).ceil() as usize).max(1);
+        PointGrid {
+            origin: Point(bounds.x_min, bounds.y_min),
+            cell,
+            w,
+            h,
+            buckets: vec![vec![]; w * h],
+        }
+    }
+
+    pub fn cell_of(&self, pt: &Point) -> (usize, usize) {
+        let d = (*pt - self.origin) / self.cell;
+        (
+            (d.x.floor().max(0.0) as usize).min(self.w - 1),
+            (d.y.floor().max(0.0) as usize).min(self.h - 1),
+        )
+    }
+
+    pub fn insert(&mut self, pt: &Point, idx: usize) {
+        let (i, j) = self.cell_of(pt);
+        self.buckets[j * self.w + i].push(idx);
+    }
+
+    pub fn remove(&mut self, pt: &Point, idx: usize) {
+        let (i, j) = self.cell_of(pt);
+        self.buckets[j * self.w + i].retain(|k| *k != idx);
+    }
+
+    // The indices in cells at most |k| cells away from |pt|'s, in each axis.
+    pub fn near(&self, pt: &Point, k: usize) -> impl Iterator<Item = usize> + '_ {
+        let (i, j) = self.cell_of(pt);
+        let (i_lo, i_hi) = (i.saturating_sub(k), (i + k).min(self.w - 1));
+        let (j_lo, j_hi) = (j.saturating_sub(k), (j + k).min(self.h - 1));
+        (j_lo..=j_hi)
+            .flat_map(move |jj| (i_lo..=i_hi).map(move |ii| (ii, jj)))
+            .flat_map(move |(ii, jj)| self.buckets[jj * self.w + ii].iter().copied())
+    }
+
+    // The index of the point in |pts| nearest to |pt|, searching outward one
+    // ring of cells at a time.
+    pub fn nearest(&self, pt: &Point, pts: &[Point]) -> Option<usize> {
+        let (i, j) = self.cell_of(pt);
+        let mut best: Option<(f64, usize)> = None;
+        for k in 0..=self.w.max(self.h) {
+            let (i_lo, i_hi) = (i as i64 - k as i64, i as i64 + k as i64);
+            let (j_lo, j_hi) = (j as i64 - k as i64, j as i64 + k as i64);
+            for jj in j_lo.max(0)..=j_hi.min(self.h as i64 - 1) {
+                for ii in i_lo.max(0)..=i_hi.min(self.w as i64 - 1) {
+                    // only the ring itself; the inside was searched already.
+                    if ii != i_lo && ii != i_hi && jj != j_lo && jj != j_hi {
+                        continue;
+                    }
+                    for idx in &self.buckets[jj as usize * self.w + ii as usize] {
+                        let d = pts[*idx].dist(pt);
+                        if best.is_none_or(|(bd, _)| d < bd) {
+                            best = Some((d, *idx));
+                        }
+                    }
+                }
+            }
+            // anything in a further ring is at least k cells away.
+            if best.is_some_and(|(bd, _)| bd <= k as f64 * self.cell) {
+                break;
+            }
+        }
+        best.map(|(_, idx)| idx)
+    }
+}
@@ -0,0 +1,320 @@
+//! Voronoi and power diagrams, clipped to a frame, and Lloyd relaxation.
+
+use crate::{
+    bounded::Bounded,
+    crop::Croppable,
+    delaunay::delaunay,
+    hull::convex_hull,
+    shapes::{point::Point, polygon::Polygon},
+};
+use anyhow::Result;
+use float_ord::FloatOrd;
+use std::collections::{BTreeSet, HashMap};
+
+// Clips a convex ring to the half-plane {x : x . n <= c}, by Sutherland-Hodgman.
+fn clip_to_halfplane(ring: &[Point], n: &Point, c: f64) -> Vec<Point> {
+    let mut out = vec![];
+    for (p, q) in ring.iter().zip(ring.iter().cycle().skip(1)) {
+        let (dp, dq) = (p.dot(n) - c, q.dot(n) - c);
+        if dp <= 0.0 {
+            out.push(*p);
+        }
+        if (dp < 0.0 && dq > 0.0) || (dp > 0.0 && dq < 0.0) {
+            out.push(*p + (*q - *p) * (dp / (dp - dq)));
+        }
+    }
+    out
+}
+
+// The cell of site |i| among weighted |sites|, cut from the convex |ring| by
+// the half-planes of its |neighbors|, which must include every site whose
+// cell might border its own.
+fn cell_ring(
+    sites: &[(Point, f64)],
+    i: usize,
+    neighbors: impl IntoIterator<Item = usize>,
+    mut ring: Vec<Point>,
+) -> Vec<Point> {
+    let (pi, wi) = sites[i];
+
+    // each other site j bounds the cell by the half-plane where
+    //   |x - pi|^2 - wi <= |x - pj|^2 - wj
+    // i.e. x . (pj - pi) <= (|pj|^2 - |pi|^2 + wi - wj) / 2.
+    // visit them nearest plane first, so we can stop once the planes are
+    // further away than the cell reaches.
+    let mut planes: Vec<(f64, usize)> = vec![];
+    for j in neighbors {
+        if j == i {
+            continue;
+        }
+        let (pj, wj) = sites[j];
+        let n = pj - pi;
+        let d = n.dot(&n).sqrt();
+        if d == 0.0 {
+            // coincident sites: the heavier (or else the first) takes it all.
+            if wj > wi || (wj == wi && j < i) {
+                return vec![];
+            }
+            continue;
+        }
+        planes.push(((d * d + wi - wj) / (2.0 * d), j));
+    }
+    planes.sort_by_key(|(offset, _)| FloatOrd(*offset));
+
+    for (offset, j) in planes {
+        let reach = ring.iter().map(|v| v.dist(&pi)).fold(0.0, f64::max);
+        if ring.len() < 3 || offset > reach {
+            break;
+        }
+        let (pj, wj) = sites[j];
+        let n = pj - pi;
+        ring = clip_to_halfplane(&ring, &n, (pj.dot(&pj) - pi.dot(&pi) + wi - wj) / 2.0);
+    }
+    ring
+}
+
+// Each point's neighbors in the Delaunay triangulation of |pts|. Voronoi cells
+// are its dual, so these are the only sites whose cells can border a site's
+// own. A point repeating an earlier one neighbors only that one.
+fn delaunay_neighbors(pts: &[Point]) -> Vec<BTreeSet<usize>> {
+    let mut neighbors = vec![BTreeSet::new(); pts.len()];
+    let mut link = |a: usize, b: usize| {
+        neighbors[a].insert(b);
+        neighbors[b].insert(a);
+    };
+
+    let mut first: HashMap<Point, usize> = HashMap::new();
+    for (i, p) in pts.iter().enumerate() {
+        let j = *first.entry(*p).or_insert(i);
+        if j != i {
+            link(i, j);
+        }
+    }
+
+    let triangles = delaunay(pts);
+    if triangles.is_empty() {
+        // all collinear, so each neighbors the next along the line.
+        let mut order: Vec<usize> = first.values().copied().collect();
+        order.sort_by_key(|i| (FloatOrd(pts[*i].x), FloatOrd(pts[*i].y)));
+        for w in order.windows(2) {
+            link(w[0], w[1]);
+        }
+        return neighbors;
+    }
+    for [a, b, c] in triangles {
+        link(a, b);
+        link(b, c);
+        link(c, a);
+    }
+    // the hull's edges are Delaunay edges, too, but the triangulation can lose
+    // one to its enclosing triangle if the hull is nearly flat there.
+    if let Ok(hull) = convex_hull(pts) {
+        for (p, q) in hull.pts.iter().zip(hull.pts.iter().cycle().skip(1)) {
+            link(first[p], first[q]);
+        }
+    }
+    neighbors
+}
+
+/// The power diagram of weighted sites, clipped to |frame|. Each site is a
+/// point and a weight (think: a squared radius); site i's cell is every point
+/// x for which |x - pi|^2 - wi is least. With equal weights this is the
+/// Voronoi diagram.
+///
+/// Returns one entry per site, in order. Cells are convex, but a nonconvex
+/// frame can cut one into several polygons; a cell may also be empty, if it
+/// is dominated by heavier neighbors or falls outside of the frame.
+///
+/// If every weight is the same, cells are cut along the edges of the Delaunay
+/// triangulation of the sites. Otherwise any site might border any other, and
+/// each cell is cut against all of them.
+pub fn power_diagram(sites: &[(Point, f64)], frame: &Polygon) -> Result<Vec<Vec<Polygon>>> {
+    let convex = frame.is_convex();
+    let start: Vec<Point> = if convex {
+        frame.pts.clone()
+    } else {
+        frame.bounds()?.to_polygon().pts
+    };
+    let neighbors: Option<Vec<BTreeSet<usize>>> = sites
+        .iter()
+        .all(|(_, w)| *w == sites[0].1)
+        .then(|| delaunay_neighbors(&sites.iter().map(|(p, _)| *p).collect::<Vec<_>>()));
+    (0..sites.len())
+        .map(|i| {
+            let ring = match &neighbors {
+                Some(neighbors) => cell_ring(sites, i, neighbors[i].iter().copied(), start.clone()),
+                None => cell_ring(sites, i, 0..sites.len(), start.clone()),
+            };
+            if ring.len() < 3 {
+                return Ok(vec![]);
+            }
+            let cell = Polygon(ring)?;
+            if cell.area() == 0.0 {
+                return Ok(vec![]);
+            }
+            if convex {
+                Ok(vec![cell])
+            } else {
+                cell.crop_to(frame)
+            }
+        })
+        .collect()
+}
+
+/// The Voronoi diagram of |sites|, clipped to |frame|. See |power_diagram|.
+pub fn voronoi(sites: &[Point], frame: &Polygon) -> Result<Vec<Vec<Polygon>>> {
+    power_diagram(&sites.iter().map(|p| (*p, 0.0)).collect::<Vec<_>>(), frame)
+}
+
+// The area-weighted centroid of a (possibly split) cell.
+fn cell_centroid(cell: &[Polygon]) -> Option<Point> {
+    let total: f64 = cell.iter().map(|pg| pg.area()).sum();
+    if total == 0.0 {
+        return None;
+    }
+    let mut acc = Point(0, 0);
+    for pg in cell {
+        acc += pg.centroid().ok()? * (pg.area() / total);
+    }
+    Some(acc)
+}
+
+/// Lloyd relaxation: repeatedly moves each site to the centroid of its
+/// Voronoi cell within |frame|, which evens out their spacing. Sites whose
+/// cells are empty stay put.
+pub fn lloyd_relaxation(sites: &[Point], frame: &Polygon, iterations: usize) -> Result<Vec<Point>> {
+    let mut sites = sites.to_vec();
+    for _ in 0..iterations {
+        let cells = voronoi(&sites, frame)?;
+        for (site, cell) in sites.iter_mut().zip(cells) {
+            if let Some(c) = cell_centroid(&cell) {
+                *site = c;
+            }
+        }
+    }
+    Ok(sites)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        containment::{default_tolerance, Locate},
+        shapes::polygon::Rect,
+    };
+    use float_eq::assert_float_eq;
+
+    fn scattered(n: usize, scale: f64) -> Vec<Point> {
+        let mut seed: u64 = 7;
+        let mut next = || {
+            seed = seed
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (seed >> 11) as f64 / (1u64 << 53) as f64
+        };
+        (0..n)
+            .map(|_| Point(next() * scale, next() * scale))
+            .collect()
+    }
+
+    fn total_area(cells: &[Vec<Polygon>]) -> f64 {
+        cells.iter().flatten().map(|pg| pg.area()).sum()
+    }
+
+    #[test]
+    fn test_two_sites() -> Result<()> {
+        let frame = Rect((0, 0), (4, 2))?;
+        let cells = voronoi(&[Point(1, 1), Point(3, 1)], &frame)?;
+        assert_eq!(cells[0], vec![Rect((0, 0), (2, 2))?]);
+        assert_eq!(cells[1], vec![Rect((2, 0), (2, 2))?]);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cells_tile_the_frame() -> Result<()> {
+        let frame = Rect((0, 0), (10, 10))?;
+        let sites = scattered(100, 10.0);
+        let cells = voronoi(&sites, &frame)?;
+        assert_float_eq!(total_area(&cells), 100.0, abs <= 1e-6);
+        for (site, cell) in sites.iter().zip(&cells) {
+            assert_eq!(cell.len(), 1);
+            assert!(cell[0].contains(site, default_tolerance())?);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_delaunay_cells_match_all_pairs() -> Result<()> {
+        let frame = Rect((0, 0), (10, 10))?;
+        let mut sites = scattered(300, 10.0);
+        sites.push(sites[0]);
+        let weighted: Vec<(Point, f64)> = sites.iter().map(|p| (*p, 0.0)).collect();
+        let cells = voronoi(&sites, &frame)?;
+        for (i, cell) in cells.iter().enumerate() {
+            let ring = cell_ring(&weighted, i, 0..sites.len(), frame.pts.clone());
+            match &cell[..] {
+                [] => assert!(ring.len() < 3),
+                [pg] => assert_float_eq!(pg.area(), Polygon(ring)?.area(), abs <= 1e-9),
+                _ => panic!("a convex frame should not split a cell"),
+            }
+        }
+        // the repeated site gets nothing.
+        assert!(cells[300].is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_collinear_sites() -> Result<()> {
+        let frame = Rect((0, 0), (6, 2))?;
+        let cells = voronoi(&[Point(5, 1), Point(1, 1), Point(3, 1)], &frame)?;
+        assert_float_eq!(cells[0][0].area(), 4.0, abs <= 1e-9);
+        assert_float_eq!(cells[1][0].area(), 4.0, abs <= 1e-9);
+        assert_float_eq!(cells[2][0].area(), 4.0, abs <= 1e-9);
+        Ok(())
+    }
+
+    #[test]
+    fn test_nonconvex_frame() -> Result<()> {
+        let frame = Polygon([(0, 0), (10, 0), (10, 4), (4, 4), (4, 10), (0, 10)])?;
+        let sites = vec![Point(2, 2), Point(8, 2), Point(2, 8), Point(8, 8)];
+        let cells = voronoi(&sites, &frame)?;
+        assert_float_eq!(total_area(&cells), frame.area(), abs <= 1e-6);
+        // the site outside of the frame gets nothing.
+        assert!(cells[3].is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_power_diagram() -> Result<()> {
+        let frame = Rect((0, 0), (4, 2))?;
+        // weighting the first site pushes the boundary from x=2 to x=2.5.
+        let cells = power_diagram(&[(Point(1, 1), 2.0), (Point(3, 1), 0.0)], &frame)?;
+        assert_float_eq!(cells[0][0].area(), 5.0, abs <= 1e-9);
+        assert_float_eq!(cells[1][0].area(), 3.0, abs <= 1e-9);
+
+        // a heavy enough neighbor swallows a cell entirely.
+        let cells = power_diagram(&[(Point(1, 1), 100.0), (Point(3, 1), 0.0)], &frame)?;
+        assert!(cells[1].is_empty());
+        Ok(())
+    }
+
+    #[test]
+    fn test_lloyd_relaxation_evens_out_cells() -> Result<()> {
+        let frame = Rect((0, 0), (10, 10))?;
+        let spread = |sites: &[Point]| -> Result<f64> {
+            let areas: Vec<f64> = voronoi(sites, &frame)?
+                .iter()
+                .map(|c| c.iter().map(|pg| pg.area()).sum())
+                .collect();
+            let mean = areas.iter().sum::<f64>() / areas.len() as f64;
+            Ok(areas.iter().map(|a| (a - mean).powi(2)).sum::<f64>())
+        };
+        let sites = scattered(30, 10.0);
+        let relaxed = lloyd_relaxation(&sites, &frame, 10)?;
+        assert!(spread(&relaxed)? < spread(&sites)? / 2.0);
+        for site in relaxed {
+            assert!(frame.contains(&site, default_tolerance())?);
+        }
+        Ok(())
+    }
+}
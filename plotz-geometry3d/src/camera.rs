@@ -5,22 +5,26 @@ use plotz_geometry::{group::Group, style::Style};
 use crate::{
     group3::Group3,
     obj3::Obj3,
+    scene::bsp::Viewer,
     shapes::{point3::Point3, polygon3::Polygon3, segment3::Segment3},
 };
+use anyhow::{anyhow, Result};
 use plotz_geometry::{
     obj2::Obj2,
     shapes::{point::Point, polygon::Polygon, segment::Segment},
 };
+use std::f64::consts::{FRAC_PI_2, FRAC_PI_3, FRAC_PI_4, FRAC_PI_6};
+use typed_builder::TypedBuilder;
 
 // Any oblique projection.  https://en.wikipedia.org/wiki/3D_projection#Oblique_projection
 #[derive(Debug, Clone)]
 pub struct Oblique {
-    u_src: Point3,
-    v_src: Point3,
-    w_src: Point3,
-    u_dst: Point,
-    v_dst: Point,
-    w_dst: Point,
+    pub u_src: Point3,
+    pub v_src: Point3,
+    pub w_src: Point3,
+    pub u_dst: Point,
+    pub v_dst: Point,
+    pub w_dst: Point,
 }
 
 impl Default for Oblique {
@@ -45,6 +49,33 @@ impl Oblique {
         Point3(0, 0, 0) - self.u_src - self.v_src - self.w_src
     }
 
+    // The direction along which points project to the same place; i.e. the
+    // direction the projection really looks in, signed to agree with the
+    // view vector.
+    pub fn projection_direction(&self) -> Result<Point3> {
+        // |dst| coefficients which cancel out...
+        let a = Point3(self.u_dst.x, self.v_dst.x, self.w_dst.x).cross(&Point3(
+            self.u_dst.y,
+            self.v_dst.y,
+            self.w_dst.y,
+        ));
+        // ...and the point whose |src| components they are.
+        let det = self.u_src.dot(&self.v_src.cross(&self.w_src));
+        if det == 0.0 {
+            return Err(anyhow!("the source vectors don't span 3d space"));
+        }
+        let d = (self.v_src.cross(&self.w_src) * a.x
+            + self.w_src.cross(&self.u_src) * a.y
+            + self.u_src.cross(&self.v_src) * a.z)
+            / det;
+        let d = d.normalize()?;
+        Ok(if d.dot(&self.view_vector()) < 0.0 {
+            d * -1.0
+        } else {
+            d
+        })
+    }
+
     pub fn project_pt3(&self, pt3d: &Point3) -> Point {
         (self.u_dst * pt3d.dot(&self.u_src))
             + (self.v_dst * pt3d.dot(&self.v_src))
@@ -71,10 +102,296 @@ impl Oblique {
     }
 }
 
+// A perspective projection, as seen by a pinhole camera at |eye| looking at
+// |target|. https://en.wikipedia.org/wiki/3D_projection#Perspective_projection
+//
+// The field of view spans -1 to 1 vertically in the projected image; up is
+// toward negative y, as it is for the default |Oblique|. Anything nearer to
+// the eye than |near| (or behind it) is clipped away, rather than projected
+// through the eye and out the other side.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct Perspective {
+    pub eye: Point3,
+
+    pub target: Point3,
+
+    // which way is up. needn't be perpendicular to the line of sight, but
+    // mustn't be parallel to it.
+    #[builder(default = Point3(0, 0, 1))]
+    pub up: Point3,
+
+    // the vertical field of view, in radians.
+    #[builder(default = FRAC_PI_3)]
+    pub fov: f64,
+
+    // the distance from the eye to the near clipping plane.
+    #[builder(default = 0.1)]
+    pub near: f64,
+}
+
+// A camera's (right, up, forward) basis, from the direction it looks in and
+// which way is up.
+fn basis(direction: Point3, up: Point3) -> Result<(Point3, Point3, Point3)> {
+    let forward = direction.normalize()?;
+    let right = forward
+        .cross(&up)
+        .normalize()
+        .map_err(|_| anyhow!("up ({:?}) is parallel to the line of sight", up))?;
+    let up = right.cross(&forward);
+    Ok((right, up, forward))
+}
+
+// An orthographic projection, looking in |direction|. Unlike an |Oblique|
+// projection, the image plane is always perpendicular to the line of sight.
+// https://en.wikipedia.org/wiki/Orthographic_projection
+//
+// Up is toward negative y in the projected image, as it is for the default
+// |Oblique|.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct Orthographic {
+    pub direction: Point3,
+
+    // which way is up. needn't be perpendicular to the line of sight, but
+    // mustn't be parallel to it.
+    #[builder(default = Point3(0, 0, 1))]
+    pub up: Point3,
+}
+
+impl Default for Orthographic {
+    fn default() -> Self {
+        Orthographic::isometric()
+    }
+}
+
+impl Orthographic {
+    // Looking at the origin from |azimuth| radians counterclockwise from the
+    // x axis, and |elevation| radians above the xy plane, with z up. Looking
+    // straight down (or up), the top of the image is the far side.
+    pub fn from_angles(azimuth: f64, elevation: f64) -> Orthographic {
+        let horizontal = Point3(azimuth.cos(), azimuth.sin(), 0);
+        let eye = horizontal * elevation.cos() + Point3(0, 0, elevation.sin());
+        let up = if elevation.cos().abs() < 1e-9 {
+            horizontal * -elevation.sin().signum()
+        } else {
+            Point3(0, 0, 1)
+        };
+        Orthographic {
+            direction: eye * -1.0,
+            up,
+        }
+    }
+
+    // All three axes foreshortened equally, and 120 degrees apart; looking
+    // down at the origin from (1,1,1), like the default |Oblique|.
+    // https://en.wikipedia.org/wiki/Isometric_projection
+    pub fn isometric() -> Orthographic {
+        Orthographic::from_angles(FRAC_PI_4, (1.0 / 2.0_f64.sqrt()).atan())
+    }
+
+    // The x and y axes foreshortened equally, and z less so; lines along x
+    // and y rise one for every two across.
+    // https://en.wikipedia.org/wiki/Axonometric_projection#Dimetric_projection
+    pub fn dimetric() -> Orthographic {
+        Orthographic::from_angles(FRAC_PI_4, FRAC_PI_6)
+    }
+
+    // No two axes foreshortened equally.
+    // https://en.wikipedia.org/wiki/Axonometric_projection#Trimetric_projection
+    pub fn trimetric() -> Orthographic {
+        Orthographic::from_angles(FRAC_PI_6, 20.0_f64.to_radians())
+    }
+
+    // Looking straight down, with x to the right and y up.
+    pub fn top() -> Orthographic {
+        Orthographic::from_angles(-FRAC_PI_2, FRAC_PI_2)
+    }
+
+    // Looking along y, with x to the right and z up.
+    pub fn front() -> Orthographic {
+        Orthographic::from_angles(-FRAC_PI_2, 0.0)
+    }
+
+    // Looking along -x, with y to the right and z up.
+    pub fn side() -> Orthographic {
+        Orthographic::from_angles(0.0, 0.0)
+    }
+
+    pub fn view_vector(&self) -> Point3 {
+        self.direction
+    }
+
+    pub fn project_pt3(&self, pt3d: &Point3) -> Result<Point> {
+        let (right, up, _) = basis(self.direction, self.up)?;
+        Ok(Point(pt3d.dot(&right), -pt3d.dot(&up)))
+    }
+    pub fn project_sg3(&self, sg3: &Segment3) -> Result<Segment> {
+        Ok(Segment(
+            self.project_pt3(&sg3.i)?,
+            self.project_pt3(&sg3.f)?,
+        ))
+    }
+    pub fn project_pg3(&self, pg3: &Polygon3) -> Result<Polygon> {
+        Polygon(
+            pg3.pts
+                .iter()
+                .map(|pt3d| self.project_pt3(pt3d))
+                .collect::<Result<Vec<_>>>()?,
+        )
+    }
+    pub fn project_obj3(&self, obj3: &Obj3) -> Result<Obj2> {
+        match obj3 {
+            Obj3::Polygon3(pg3d) => Ok(Obj2::Polygon(self.project_pg3(pg3d)?)),
+            Obj3::Segment3(sg3d) => Ok(Obj2::Segment(self.project_sg3(sg3d)?)),
+            Obj3::Group3(_) => Err(anyhow!(
+                "no support for groups yet; see https://github.com/ambuc/plotz_rs/issues/6"
+            )),
+        }
+    }
+}
+
+impl Perspective {
+    // The camera's (right, up, forward) basis.
+    fn basis(&self) -> Result<(Point3, Point3, Point3)> {
+        basis(self.target - self.eye, self.up)
+    }
+
+    pub fn view_vector(&self) -> Point3 {
+        self.target - self.eye
+    }
+
+    // How far in front of the eye a point is, along the line of sight.
+    pub fn depth(&self, pt3d: &Point3) -> Result<f64> {
+        let (_, _, forward) = self.basis()?;
+        Ok((*pt3d - self.eye).dot(&forward))
+    }
+
+    pub fn project_pt3(&self, pt3d: &Point3) -> Result<Point> {
+        if self.depth(pt3d)? < self.near {
+            return Err(anyhow!("{:?} is nearer than the near plane", pt3d));
+        }
+        self.project_clipped_pt3(pt3d)
+    }
+
+    // Projects a point already known to lie on or past the near plane.
+    fn project_clipped_pt3(&self, pt3d: &Point3) -> Result<Point> {
+        let (right, up, forward) = self.basis()?;
+        let d = *pt3d - self.eye;
+        let focal = 1.0 / (self.fov / 2.0).tan();
+        Ok(Point(d.dot(&right), -d.dot(&up)) * (focal / d.dot(&forward)))
+    }
+
+    // Clips a run of points to the far side of the near plane, by
+    // https://en.wikipedia.org/wiki/Sutherland%E2%80%93Hodgman_algorithm.
+    // If |closed|, the run wraps around from its last point to its first.
+    fn clip(&self, pts: &[Point3], closed: bool) -> Result<Vec<Point3>> {
+        let depths: Vec<f64> = pts.iter().map(|pt| self.depth(pt)).collect::<Result<_>>()?;
+        let n = pts.len();
+        let mut ret = vec![];
+        for k in 0..n {
+            let (a, da) = (pts[k], depths[k]);
+            if da >= self.near {
+                ret.push(a);
+            }
+            if !closed && k + 1 == n {
+                break;
+            }
+            let (b, db) = (pts[(k + 1) % n], depths[(k + 1) % n]);
+            if (da < self.near) != (db < self.near) {
+                let t = (self.near - da) / (db - da);
+                ret.push(a + (b - a) * t);
+            }
+        }
+        Ok(ret)
+    }
+
+    pub fn project_sg3(&self, sg3: &Segment3) -> Result<Option<Segment>> {
+        match self.clip(&[sg3.i, sg3.f], false)?[..] {
+            [i, f] => Ok(Some(Segment(
+                self.project_clipped_pt3(&i)?,
+                self.project_clipped_pt3(&f)?,
+            ))),
+            _ => Ok(None),
+        }
+    }
+    pub fn project_pg3(&self, pg3: &Polygon3) -> Result<Option<Polygon>> {
+        let mut pts = pg3.pts.clone();
+        if pts.len() > 1 && pts[0] == pts[pts.len() - 1] {
+            pts.pop();
+        }
+        let clipped = self.clip(&pts, true)?;
+        if clipped.len() < 3 {
+            return Ok(None);
+        }
+        Ok(Some(Polygon(
+            clipped
+                .iter()
+                .map(|pt3d| self.project_clipped_pt3(pt3d))
+                .collect::<Result<Vec<_>>>()?,
+        )?))
+    }
+    pub fn project_obj3(&self, obj3: &Obj3) -> Result<Option<Obj2>> {
+        match obj3 {
+            Obj3::Polygon3(pg3d) => Ok(self.project_pg3(pg3d)?.map(Obj2::Polygon)),
+            Obj3::Segment3(sg3d) => Ok(self.project_sg3(sg3d)?.map(Obj2::Segment)),
+            Obj3::Group3(_) => Err(anyhow!(
+                "no support for groups yet; see https://github.com/ambuc/plotz_rs/issues/6"
+            )),
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub enum Projection {
     /// https://en.wikipedia.org/wiki/Pohlke%27s_theorem
     Oblique(Oblique),
+    /// https://en.wikipedia.org/wiki/Orthographic_projection
+    Orthographic(Orthographic),
+    /// https://en.wikipedia.org/wiki/3D_projection#Perspective_projection
+    Perspective(Perspective),
+}
+
+impl Projection {
+    // The direction the projection looks in, from the viewer into the scene.
+    // Points further along it are further away.
+    pub fn view_vector(&self) -> Point3 {
+        match self {
+            Projection::Oblique(obl) => obl.view_vector(),
+            Projection::Orthographic(o) => o.view_vector(),
+            Projection::Perspective(p) => p.view_vector(),
+        }
+    }
+
+    // How far away an object is. Nearer objects have lesser depths.
+    pub fn depth(&self, obj3: &Obj3) -> f64 {
+        obj3.dist_along(&self.view_vector())
+    }
+
+    // Where the viewer is, for putting objects in visibility order.
+    pub fn viewer(&self) -> Result<Viewer> {
+        match self {
+            Projection::Oblique(obl) => Ok(Viewer::Along(obl.projection_direction()?)),
+            Projection::Orthographic(o) => Ok(Viewer::Along(o.direction)),
+            Projection::Perspective(p) => Ok(Viewer::At(p.eye)),
+        }
+    }
+
+    // Projects a single point.
+    pub fn project_pt3(&self, pt: &Point3) -> Result<Point> {
+        match self {
+            Projection::Oblique(obl) => Ok(obl.project_pt3(pt)),
+            Projection::Orthographic(o) => o.project_pt3(pt),
+            Projection::Perspective(p) => p.project_pt3(pt),
+        }
+    }
+
+    // Projects an object, or returns None if none of it can be seen.
+    pub fn project_styled_obj3(&self, sobj3: &(Obj3, Style)) -> Result<Option<(Obj2, Style)>> {
+        match self {
+            Projection::Oblique(obl) => Ok(Some(obl.project_styled_obj3(sobj3))),
+            Projection::Orthographic(o) => Ok(Some((o.project_obj3(&sobj3.0)?, sobj3.1))),
+            Projection::Perspective(p) => Ok(p.project_obj3(&sobj3.0)?.map(|o| (o, sobj3.1))),
+        }
+    }
 }
 
 impl Default for Projection {
@@ -89,3 +406,186 @@ pub enum Occlusion {
     #[default]
     True,
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        scene::{occluder::Occluder, Scene},
+        shapes::cube3d::Cube,
+    };
+    use float_eq::assert_float_eq;
+
+    fn assert_near(a: Point, b: Point) {
+        assert_float_eq!(a.x, b.x, abs <= 1e-9);
+        assert_float_eq!(a.y, b.y, abs <= 1e-9);
+    }
+
+    fn camera() -> Perspective {
+        // looking down the x axis, from x=-10.
+        Perspective::builder()
+            .eye(Point3(-10, 0, 0))
+            .target(Point3(0, 0, 0))
+            .fov(std::f64::consts::FRAC_PI_2)
+            .build()
+    }
+
+    #[test]
+    fn test_orthographic_presets() -> Result<()> {
+        let (x, y, z) = (Point3(1, 0, 0), Point3(0, 1, 0), Point3(0, 0, 1));
+
+        let top = Orthographic::top();
+        assert_near(top.project_pt3(&x)?, Point(1, 0));
+        assert_near(top.project_pt3(&y)?, Point(0, -1));
+        assert_near(top.project_pt3(&z)?, Point(0, 0));
+
+        let front = Orthographic::front();
+        assert_near(front.project_pt3(&x)?, Point(1, 0));
+        assert_near(front.project_pt3(&y)?, Point(0, 0));
+        assert_near(front.project_pt3(&z)?, Point(0, -1));
+
+        let side = Orthographic::side();
+        assert_near(side.project_pt3(&x)?, Point(0, 0));
+        assert_near(side.project_pt3(&y)?, Point(1, 0));
+        assert_near(side.project_pt3(&z)?, Point(0, -1));
+
+        let axes = |o: &Orthographic| -> Result<Vec<f64>> {
+            [x, y, z]
+                .iter()
+                .map(|a| Ok(o.project_pt3(a)?.dist(&Point(0, 0))))
+                .collect()
+        };
+        // isometric: all axes foreshortened alike, and 120 degrees apart.
+        let iso = Orthographic::isometric();
+        for l in axes(&iso)? {
+            assert_float_eq!(l, (2.0_f64 / 3.0).sqrt(), abs <= 1e-9);
+        }
+        let (px, py) = (iso.project_pt3(&x)?, iso.project_pt3(&y)?);
+        assert_float_eq!(px.dot(&py), -0.5 * 2.0 / 3.0, abs <= 1e-9);
+        // z is up; x goes down and to the left, y down and to the right.
+        assert!(px.x < 0.0 && px.y > 0.0 && py.x > 0.0 && py.y > 0.0);
+
+        // dimetric: two axes alike; trimetric: none alike.
+        let di = axes(&Orthographic::dimetric())?;
+        assert_float_eq!(di[0], di[1], abs <= 1e-9);
+        assert!((di[0] - di[2]).abs() > 1e-3);
+        let tri = axes(&Orthographic::trimetric())?;
+        assert!((tri[0] - tri[1]).abs() > 1e-3);
+        assert!((tri[1] - tri[2]).abs() > 1e-3);
+        assert!((tri[0] - tri[2]).abs() > 1e-3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_oblique_projection_direction() -> Result<()> {
+        let obl = Oblique::default();
+        let d = obl.projection_direction()?;
+        // points along it all land in the same place...
+        assert_near(obl.project_pt3(&(d * 5.0)), Point(0, 0));
+        // ...and it looks the same way as the view vector.
+        assert!(d.dot(&obl.view_vector()) > 0.0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_view_vectors_agree() {
+        // every projection looks from the viewer into the scene, so the point
+        // nearer the viewer is less far along the view vector.
+        let near = Point3(1, 1, 1);
+        let far = Point3(-1, -1, -1);
+        for projection in [
+            Projection::Oblique(Oblique::default()),
+            Projection::Orthographic(Orthographic::isometric()),
+            Projection::Perspective(
+                Perspective::builder()
+                    .eye(Point3(5, 5, 5))
+                    .target(Point3(0, 0, 0))
+                    .build(),
+            ),
+        ] {
+            let v = projection.view_vector();
+            assert!(near.dot(&v) < far.dot(&v));
+        }
+    }
+
+    #[test]
+    fn test_perspective_project_pt3() -> Result<()> {
+        let p = camera();
+        assert_near(p.project_pt3(&Point3(0, 0, 0))?, Point(0, 0));
+        // up is up (toward -y), and right is right.
+        assert_near(p.project_pt3(&Point3(0, 0, 10))?, Point(0, -1));
+        assert_near(p.project_pt3(&Point3(0, -10, 0))?, Point(1, 0));
+        // further away is smaller.
+        assert_near(p.project_pt3(&Point3(10, 0, 10))?, Point(0, -0.5));
+        // behind the eye can't be projected.
+        assert!(p.project_pt3(&Point3(-20, 0, 0)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_perspective_rejects_bad_up() {
+        let p = Perspective::builder()
+            .eye(Point3(0, 0, 10))
+            .target(Point3(0, 0, 0))
+            .build();
+        assert!(p.project_pt3(&Point3(0, 0, 0)).is_err());
+    }
+
+    #[test]
+    fn test_perspective_near_plane_clipping() -> Result<()> {
+        let p = camera();
+        // wholly behind the eye.
+        assert!(p
+            .project_sg3(&Segment3(Point3(-20, 0, 0), Point3(-20, 1, 0)))?
+            .is_none());
+        // reaching from behind the eye to in front of it.
+        let sg = p
+            .project_sg3(&Segment3(Point3(-20, 0, 1), Point3(0, 0, 1)))?
+            .unwrap();
+        assert_near(sg.f, Point(0, -0.1));
+        assert_float_eq!(sg.i.y, -1.0 / p.near, abs <= 1e-9);
+
+        // a wall running through the eye keeps only the part in front of it.
+        let pg = p
+            .project_pg3(&Polygon3([
+                Point3(-20, 1, -1),
+                Point3(10, 1, -1),
+                Point3(10, 1, 1),
+                Point3(-20, 1, 1),
+                Point3(-20, 1, -1),
+            ]))?
+            .unwrap();
+        assert_eq!(pg.pts.len(), 4);
+        assert!(pg.pts.iter().all(|pt| pt.x < 0.0));
+        Ok(())
+    }
+
+    #[test]
+    fn test_perspective_scene() -> Result<()> {
+        let objects = || -> Vec<(Obj3, Style)> {
+            [Point3(0, 0, 0), Point3(5, 3, 0), Point3(-15, 0, 0)]
+                .into_iter()
+                .flat_map(|pt| Cube(pt, 1.0).into_iter_objects())
+                .map(|(o, _)| (o, Style::default()))
+                .collect()
+        };
+        let projection = Projection::Perspective(camera());
+
+        // the cube behind the eye drops out.
+        let projected = Scene::builder()
+            .objects(objects())
+            .projection(projection.clone())
+            .build()
+            .project()?;
+        assert_eq!(projected.len(), 12);
+
+        let occluded = Scene::builder()
+            .objects(objects())
+            .projection(projection)
+            .occluder(Occluder::default())
+            .build()
+            .project()?;
+        assert!(!occluded.is_empty());
+        Ok(())
+    }
+}
@@ -0,0 +1,647 @@
+//! Heightfields, i.e. surfaces |z = f(x, y)| sampled on a grid, and ridge
+//! plots of them with hidden lines removed.
+//!
+//! There are two ways to draw one. |Heightfield::faces| makes two triangles of
+//! every cell of the grid, for a wireframe hidden behind itself by a |Scene|
+//! like any other solid. |Heightfield::ridge_lines| draws just the rows, each
+//! one hiding whatever is below it on the page, by the floating horizon
+//! algorithm; this is the look of a ridgeline ("Unknown Pleasures") plot.
+//!
+//! [`Scene`]: crate::scene::Scene
+
+use crate::{
+    camera::Projection,
+    group3::Group3,
+    obj3::Obj3,
+    shapes::{point3::Point3, polygon3::Polygon3, segment3::Segment3},
+};
+use anyhow::{anyhow, Context, Result};
+use float_ord::FloatOrd;
+use plotz_geometry::{
+    bounded::Bounds,
+    shapes::{multiline::Multiline, point::Point},
+};
+use std::{collections::HashMap, io::BufRead};
+
+/// A grid of heights. Sample |i| of row |j| is at
+/// |(origin.x + i * spacing.0, origin.y + j * spacing.1)|.
+#[derive(Debug, Clone)]
+pub struct Heightfield {
+    /// Where the first sample of the first row is.
+    pub origin: Point,
+    /// How far apart samples are, along x and along y.
+    pub spacing: (f64, f64),
+    /// The heights, row by row. Every row is the same length.
+    pub rows: Vec<Vec<f64>>,
+}
+
+impl Heightfield {
+    /// A heightfield of |rows| of samples, which must all be the same length.
+    /// There must be at least two rows of two samples each.
+    pub fn new(origin: Point, spacing: (f64, f64), rows: Vec<Vec<f64>>) -> Result<Heightfield> {
+        if rows.len() < 2 || rows[0].len() < 2 {
+            return Err(anyhow!("need at least two rows of two samples"));
+        }
+        if rows.iter().any(|row| row.len() != rows[0].len()) {
+            return Err(anyhow!("rows must all be the same length"));
+        }
+        Ok(Heightfield {
+            origin,
+            spacing,
+            rows,
+        })
+    }
+
+    /// Samples |f| at |nx| by |ny| points evenly spaced over |bounds|, corners
+    /// included.
+    pub fn from_fn(
+        bounds: &Bounds,
+        (nx, ny): (usize, usize),
+        f: impl Fn(f64, f64) -> f64,
+    ) -> Result<Heightfield> {
+        if nx < 2 || ny < 2 {
+            return Err(anyhow!("need at least two samples each way"));
+        }
+        let spacing = (
+            (bounds.x_max - bounds.x_min) / (nx - 1) as f64,
+            (bounds.y_max - bounds.y_min) / (ny - 1) as f64,
+        );
+        let rows = (0..ny)
+            .map(|j| {
+                let y = bounds.y_min + j as f64 * spacing.1;
+                (0..nx)
+                    .map(|i| f(bounds.x_min + i as f64 * spacing.0, y))
+                    .collect()
+            })
+            .collect();
+        Heightfield::new(Point(bounds.x_min, bounds.y_min), spacing, rows)
+    }
+
+    /// Parses a digital elevation model in the ESRI ASCII grid format.
+    /// https://en.wikipedia.org/wiki/Esri_grid
+    /// Cells with no data take the least height there is.
+    pub fn parse_esri_ascii(reader: impl BufRead) -> Result<Heightfield> {
+        let mut header: HashMap<String, f64> = HashMap::new();
+        let mut values: Vec<f64> = vec![];
+        for line in reader.lines() {
+            let line = line?;
+            let mut tokens = line.split_whitespace().peekable();
+            match tokens.peek() {
+                None => continue,
+                Some(t) if values.is_empty() && t.starts_with(|c: char| c.is_alphabetic()) => {
+                    let key = t.to_lowercase();
+                    tokens.next();
+                    let value = tokens
+                        .next()
+                        .ok_or(anyhow!("no value for {:?}", key))?
+                        .parse()
+                        .context(format!("bad value for {:?}", key))?;
+                    header.insert(key, value);
+                }
+                Some(_) => {
+                    for t in tokens {
+                        values.push(t.parse().context(format!("bad height {:?}", t))?);
+                    }
+                }
+            }
+        }
+
+        let get = |key: &str| header.get(key).copied().ok_or(anyhow!("no {:?}", key));
+        let (ncols, nrows) = (get("ncols")? as usize, get("nrows")? as usize);
+        let cellsize = get("cellsize")?;
+        if values.len() != ncols * nrows {
+            return Err(anyhow!(
+                "expected {} x {} heights, found {}",
+                ncols,
+                nrows,
+                values.len()
+            ));
+        }
+        // samples are at the centers of cells.
+        let origin = match (get("xllcenter"), get("yllcenter")) {
+            (Ok(x), Ok(y)) => Point(x, y),
+            _ => Point(get("xllcorner")?, get("yllcorner")?) + Point(cellsize, cellsize) / 2.0,
+        };
+
+        if let Ok(nodata) = get("nodata_value") {
+            let least = values
+                .iter()
+                .filter(|v| **v != nodata)
+                .map(|v| FloatOrd(*v))
+                .min()
+                .ok_or(anyhow!("no data at all"))?
+                .0;
+            for v in values.iter_mut().filter(|v| **v == nodata) {
+                *v = least;
+            }
+        }
+        // the first row in the file is the northernmost.
+        let rows = values.chunks(ncols).rev().map(|c| c.to_vec()).collect();
+        Heightfield::new(origin, (cellsize, cellsize), rows)
+    }
+
+    /// The sample |i| of row |j|, in 3d.
+    pub fn pt(&self, i: usize, j: usize) -> Point3 {
+        Point3(
+            self.origin.x + i as f64 * self.spacing.0,
+            self.origin.y + j as f64 * self.spacing.1,
+            self.rows[j][i],
+        )
+    }
+
+    /// Two triangular faces for every cell of the grid, split along the
+    /// diagonal from its least corner, so that every face is flat even where
+    /// the cell isn't.
+    pub fn faces(&self) -> Group3<()> {
+        let (nx, ny) = (self.rows[0].len(), self.rows.len());
+        Group3::new((0..ny - 1).flat_map(|j| {
+            (0..nx - 1).flat_map(move |i| {
+                let (a, b, c, d) = (
+                    self.pt(i, j),
+                    self.pt(i + 1, j),
+                    self.pt(i + 1, j + 1),
+                    self.pt(i, j + 1),
+                );
+                [
+                    (Obj3::Polygon3(Polygon3([a, b, c, a])), ()),
+                    (Obj3::Polygon3(Polygon3([a, c, d, a])), ()),
+                ]
+            })
+        }))
+    }
+
+    /// Each row, projected by |projection|, with whatever of it is hidden by
+    /// the rows in front of it removed. Each row hides everything below it
+    /// on the page, as if it were the top of a curtain hanging down to the
+    /// ground. Rows must run across the page, not into it.
+    pub fn ridge_lines(&self, projection: &Projection) -> Result<Vec<Multiline>> {
+        let mut rows: Vec<(f64, Vec<Point3>)> = (0..self.rows.len())
+            .map(|j| {
+                let pts: Vec<Point3> = (0..self.rows[j].len()).map(|i| self.pt(i, j)).collect();
+                let depth = projection.depth(&Obj3::Segment3(Segment3(pts[0], pts[pts.len() - 1])));
+                (depth, pts)
+            })
+            .collect();
+        rows.sort_by_key(|(depth, _)| FloatOrd(*depth));
+
+        let mut projected = vec![];
+        for (_, pts) in rows {
+            let mut row = vec![];
+            for pt in pts {
+                row.push(projection.project_pt3(&pt)?);
+            }
+            projected.push(row);
+        }
+        floating_horizon(projected)
+    }
+}
+
+// The upper edge of everything drawn so far, as a piecewise-linear function
+// of x: its points, in order of x. Two points may share an x where the edge
+// jumps. Up is toward negative y, so the edge is the least y at each x.
+#[derive(Debug, Default)]
+struct Horizon(Vec<Point>);
+
+impl Horizon {
+    // A row as a function of x, running left to right.
+    fn of(row: &[Point]) -> Result<Horizon> {
+        let mut pts = row.to_vec();
+        if pts.len() > 1 && pts[pts.len() - 1].x < pts[0].x {
+            pts.reverse();
+        }
+        if pts.windows(2).any(|w| w[1].x < w[0].x) {
+            return Err(anyhow!("rows must run across the page, not into it"));
+        }
+        Ok(Horizon(pts))
+    }
+
+    // The ends (at |x0| and |x1|) of the line this follows between them, if it
+    // is defined there. There must be no corner between them.
+    fn line_on(&self, x0: f64, x1: f64) -> Option<(f64, f64)> {
+        let pts = &self.0;
+        let mid = (x0 + x1) / 2.0;
+        let i = pts.partition_point(|p| p.x < mid);
+        if i == 0 || i == pts.len() {
+            return None;
+        }
+        let (a, b) = (pts[i - 1], pts[i]);
+        let at = |x: f64| a.y + (b.y - a.y) * (x - a.x) / (b.x - a.x);
+        Some((at(x0), at(x1)))
+    }
+
+    // Where this has corners, strictly between |x0| and |x1|.
+    fn corners_within(&self, x0: f64, x1: f64) -> impl Iterator<Item = f64> + '_ {
+        self.0
+            .iter()
+            .map(|p| p.x)
+            .skip_while(move |x| *x <= x0)
+            .take_while(move |x| *x < x1)
+    }
+
+    // The least y of this and |other| at each x.
+    fn min(&self, other: &Horizon) -> Horizon {
+        let mut xs: Vec<f64> = self.0.iter().chain(other.0.iter()).map(|p| p.x).collect();
+        xs.sort_by_key(|x| FloatOrd(*x));
+        xs.dedup();
+
+        let mut pts: Vec<Point> = vec![];
+        let mut push = |p: Point| {
+            if pts.last() != Some(&p) {
+                pts.push(p);
+            }
+        };
+        for (x0, x1) in xs.iter().zip(xs.iter().skip(1)) {
+            let (x0, x1) = (*x0, *x1);
+            match (self.line_on(x0, x1), other.line_on(x0, x1)) {
+                (None, None) => {}
+                (Some((y0, y1)), None) | (None, Some((y0, y1))) => {
+                    push(Point(x0, y0));
+                    push(Point(x1, y1));
+                }
+                (Some((a0, a1)), Some((b0, b1))) => {
+                    push(Point(x0, a0.min(b0)));
+                    let (d0, d1) = (a0 - b0, a1 - b1);
+                    if d0 * d1 < 0.0 {
+                        let t = d0 / (d0 - d1);
+                        push(Point(x0 + (x1 - x0) * t, a0 + (a1 - a0) * t));
+                    }
+                    push(Point(x1, a1.min(b1)));
+                }
+            }
+        }
+        Horizon(pts)
+    }
+
+    // The parts of |a| to |b| which are above this, as (x0, x1) intervals.
+    fn visible(&self, a: Point, b: Point) -> Vec<(Point, Point)> {
+        // how far above the horizon something must be to be seen.
+        const EPSILON: f64 = 1e-9;
+
+        let (a, b) = if b.x < a.x { (b, a) } else { (a, b) };
+        if a.x == b.x {
+            // a vertical segment shows above the horizon there.
+            let top = a.y.min(b.y);
+            let h = [(a.x - EPSILON, a.x), (a.x, a.x + EPSILON)]
+                .iter()
+                .filter_map(|(x0, x1)| self.line_on(*x0, *x1).map(|(y, _)| y))
+                .fold(f64::INFINITY, f64::min);
+            let bottom = a.y.max(b.y).min(h);
+            return if top < bottom - EPSILON {
+                vec![(Point(a.x, top), Point(a.x, bottom))]
+            } else {
+                vec![]
+            };
+        }
+
+        let at = |x: f64| Point(x, a.y + (b.y - a.y) * (x - a.x) / (b.x - a.x));
+        let xs: Vec<f64> = std::iter::once(a.x)
+            .chain(self.corners_within(a.x, b.x))
+            .chain(std::iter::once(b.x))
+            .collect();
+
+        let mut shown: Vec<(f64, f64)> = vec![];
+        let mut show = |x0: f64, x1: f64| match shown.last_mut() {
+            Some((_, end)) if *end == x0 => *end = x1,
+            _ => shown.push((x0, x1)),
+        };
+        for (x0, x1) in xs.iter().zip(xs.iter().skip(1)) {
+            let (x0, x1) = (*x0, *x1);
+            if x1 <= x0 {
+                continue;
+            }
+            let Some((h0, h1)) = self.line_on(x0, x1) else {
+                show(x0, x1);
+                continue;
+            };
+            // below zero is above the horizon.
+            let (d0, d1) = (at(x0).y - h0, at(x1).y - h1);
+            match (d0 < -EPSILON, d1 < -EPSILON) {
+                (true, true) => show(x0, x1),
+                (false, false) => {}
+                (true, false) => show(x0, x0 + (x1 - x0) * d0 / (d0 - d1)),
+                (false, true) => show(x0 + (x1 - x0) * d0 / (d0 - d1), x1),
+            }
+        }
+        shown
+            .into_iter()
+            .filter(|(x0, x1)| x1 > x0)
+            .map(|(x0, x1)| (at(x0), at(x1)))
+            .collect()
+    }
+}
+
+/// Removes the hidden parts of |rows| (polylines on the page, nearest first)
+/// by the floating horizon algorithm: each row shows only where it is above
+/// every row before it, and so hides everything below it on the page. Up is
+/// toward negative y. Each row must run across the page, left to right or
+/// right to left.
+/// https://en.wikipedia.org/wiki/Hidden-line_removal
+pub fn floating_horizon(rows: impl IntoIterator<Item = Vec<Point>>) -> Result<Vec<Multiline>> {
+    let mut horizon = Horizon::default();
+    let mut lines = vec![];
+    for row in rows {
+        let mut pieces: Vec<Vec<Point>> = vec![];
+        for w in row.windows(2) {
+            for (i, f) in horizon.visible(w[0], w[1]) {
+                // keep the piece running the same way as the row.
+                let (i, f) = if (f.x - i.x) * (w[1].x - w[0].x) < 0.0 {
+                    (f, i)
+                } else {
+                    (i, f)
+                };
+                match pieces.last_mut() {
+                    Some(piece) if piece.last() == Some(&i) => piece.push(f),
+                    _ => pieces.push(vec![i, f]),
+                }
+            }
+        }
+        for piece in pieces {
+            lines.push(Multiline::try_from(piece)?);
+        }
+        horizon = horizon.min(&Horizon::of(&row)?);
+    }
+    Ok(lines)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        camera::{Orthographic, Perspective},
+        scene::{occluder::Occluder, Scene},
+    };
+    use float_eq::assert_float_eq;
+    use itertools::iproduct;
+    use plotz_geometry::{
+        bounded::streaming_bbox, crop::PointLocation, obj2::Obj2, shapes::polygon::Polygon,
+        style::Style,
+    };
+    use std::f64::consts::FRAC_PI_2;
+    use test_case::test_case;
+
+    fn length(ml: &Multiline) -> f64 {
+        ml.pts.windows(2).map(|w| w[0].dist(&w[1])).sum()
+    }
+
+    #[test]
+    fn test_floating_horizon() -> Result<()> {
+        // a flat row in front, then a row behind with a peak poking up
+        // above it, then one wholly hidden.
+        let rows = vec![
+            vec![Point(0, 0), Point(4, 0)],
+            vec![Point(0, 1), Point(2, -1), Point(4, 1)],
+            vec![Point(4, 0.5), Point(0, 0.5)],
+        ];
+        let lines = floating_horizon(rows)?;
+        assert_eq!(lines.len(), 2);
+        assert_eq!(lines[0].pts, vec![Point(0, 0), Point(4, 0)]);
+        assert_eq!(lines[1].pts, vec![Point(1, 0), Point(2, -1), Point(3, 0)]);
+
+        // rows needn't cover the same span.
+        let lines = floating_horizon(vec![
+            vec![Point(1, 0), Point(2, 0)],
+            vec![Point(0, 1), Point(3, 1)],
+        ])?;
+        let total: f64 = lines.iter().map(length).sum();
+        assert_float_eq!(total, 3.0, abs <= 1e-9);
+
+        assert!(floating_horizon(vec![vec![Point(0, 0), Point(2, 0), Point(1, 0)]]).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_ridge_lines() -> Result<()> {
+        // a tall peak on the front row, and flat rows behind it, seen from in
+        // front and a little above. the rows behind show above the front row,
+        // except where its peak hides them.
+        let bounds = Bounds {
+            x_min: -2.0,
+            x_max: 2.0,
+            y_min: 0.0,
+            y_max: 4.0,
+        };
+        let field = Heightfield::from_fn(&bounds, (41, 5), |x, y| {
+            if y == 0.0 {
+                3.0 * (-x * x).exp()
+            } else {
+                0.0
+            }
+        })?;
+        let view = Projection::Orthographic(Orthographic::from_angles(-FRAC_PI_2, 0.2));
+        let lines = field.ridge_lines(&view)?;
+
+        assert_eq!(lines.len(), 9);
+        assert_float_eq!(
+            length(&lines[0]),
+            field_row_length(&field, &view)?,
+            abs <= 1e-9
+        );
+        for line in &lines[1..] {
+            for pt in &line.pts {
+                assert!(pt.x.abs() > 0.5);
+            }
+        }
+        Ok(())
+    }
+
+    fn field_row_length(field: &Heightfield, view: &Projection) -> Result<f64> {
+        let pts: Vec<Point> = (0..field.rows[0].len())
+            .map(|i| view.project_pt3(&field.pt(i, 0)))
+            .collect::<Result<_>>()?;
+        Ok(pts.windows(2).map(|w| w[0].dist(&w[1])).sum())
+    }
+
+    #[test_case(Projection::Orthographic(Orthographic::isometric()); "isometric")]
+    #[test_case(Projection::Perspective(
+        Perspective::builder()
+            .eye(Point3(-4, -6, 8))
+            .target(Point3(3, 3, 0))
+            .build()
+    ); "perspective")]
+    fn test_faces_hide_each_other(projection: Projection) -> Result<()> {
+        let bounds = Bounds {
+            x_min: 0.0,
+            x_max: 6.0,
+            y_min: 0.0,
+            y_max: 6.0,
+        };
+        let field = Heightfield::from_fn(&bounds, (13, 13), |x, y| (x * y).sin())?;
+        let faces: Vec<Polygon3> = field
+            .faces()
+            .into_iter_objects()
+            .map(|(o, _)| match o {
+                Obj3::Polygon3(pg) => pg,
+                x => panic!("{:?}", x),
+            })
+            .collect();
+        assert_eq!(faces.len(), 2 * 12 * 12);
+
+        // every face has a thickness of its own, so that what's left of it
+        // can be told apart from what's left of the others.
+        let projected = Scene::builder()
+            .objects(
+                faces
+                    .iter()
+                    .enumerate()
+                    .map(|(k, pg)| {
+                        (
+                            Obj3::Polygon3(pg.clone()),
+                            Style {
+                                thickness: k as f64,
+                                ..Default::default()
+                            },
+                        )
+                    })
+                    .collect(),
+            )
+            .projection(projection.clone())
+            .occluder(Occluder::default())
+            .build()
+            .project()?;
+        let visible = projected
+            .iter()
+            .map(|(o, s)| {
+                let locator = match o {
+                    Obj2::Polygon(pg) => pg.locator(1e-9)?,
+                    Obj2::PolygonWithCavities(pgc) => pgc.locator(1e-9)?,
+                    x => panic!("{:?}", x),
+                };
+                Ok((locator, s.thickness as usize))
+            })
+            .collect::<Result<Vec<_>>>()?;
+
+        // how near a point is to the viewer. this varies linearly across the
+        // picture of a face (for a perspective, its reciprocal depth does),
+        // so it can be found anywhere on one from its corners.
+        let nearness = |pt: &Point3| -> Result<f64> {
+            Ok(match &projection {
+                Projection::Perspective(p) => 1.0 / p.depth(pt)?,
+                _ => -pt.dot(&projection.view_vector().normalize()?),
+            })
+        };
+        let pictures = faces
+            .iter()
+            .map(|pg| {
+                let corners = &pg.pts[..3];
+                let pts = corners
+                    .iter()
+                    .map(|pt| projection.project_pt3(pt))
+                    .collect::<Result<Vec<_>>>()?;
+                let nearnesses = corners.iter().map(nearness).collect::<Result<Vec<_>>>()?;
+                let locator = Polygon(pts.clone())?.locator(1e-9)?;
+                Ok((pts, nearnesses, locator))
+            })
+            .collect::<Result<Vec<_>>>()?;
+        let nearness_at = |k: usize, pt: &Point| -> f64 {
+            let (pts, nearnesses, _) = &pictures[k];
+            let cross = |a: &Point, b: &Point, c: &Point| (*b - *a).cross_z(&(*c - *a));
+            let whole = cross(&pts[0], &pts[1], &pts[2]);
+            (0..3)
+                .map(|i| cross(pt, &pts[(i + 1) % 3], &pts[(i + 2) % 3]) / whole * nearnesses[i])
+                .sum()
+        };
+
+        // wherever faces overlap in the picture, only the nearest shows.
+        let Bounds {
+            x_min,
+            x_max,
+            y_min,
+            y_max,
+        } = streaming_bbox(projected.iter().map(|(o, _)| o))?;
+        let (width, height) = (x_max - x_min, y_max - y_min);
+        let n = 150;
+        let (mut covered, mut overlapping) = (0, 0);
+        for (i, j) in iproduct!(0..n, 0..n) {
+            let pt = Point(
+                x_min + (i as f64 + 0.37) / n as f64 * width,
+                y_min + (j as f64 + 0.61) / n as f64 * height,
+            );
+            let locations: Vec<PointLocation> =
+                pictures.iter().map(|(_, _, l)| l.locate(&pt)).collect();
+            if locations
+                .iter()
+                .any(|l| matches!(l, PointLocation::OnPoint(_) | PointLocation::OnSegment(_)))
+            {
+                continue;
+            }
+            let covering: Vec<usize> = (0..faces.len())
+                .filter(|k| locations[*k] == PointLocation::Inside)
+                .collect();
+            let touching: Vec<(PointLocation, usize)> = visible
+                .iter()
+                .map(|(l, k)| (l.locate(&pt), *k))
+                .filter(|(l, _)| *l != PointLocation::Outside)
+                .collect();
+            let showing: Vec<usize> = touching
+                .iter()
+                .filter(|(l, _)| *l == PointLocation::Inside)
+                .map(|(_, k)| *k)
+                .collect();
+            match covering
+                .iter()
+                .max_by_key(|k| FloatOrd(nearness_at(**k, &pt)))
+            {
+                None => assert_eq!(touching, vec![], "{:?}", pt),
+                Some(nearest) => {
+                    assert!(!touching.is_empty(), "{:?} shows nothing", pt);
+                    assert!(showing.len() <= 1, "{:?} shows {:?}", pt, showing);
+                    assert!(
+                        showing.iter().all(|k| k == nearest),
+                        "{:?} shows {:?}, not {:?}",
+                        pt,
+                        showing,
+                        nearest
+                    );
+                    covered += 1;
+                }
+            }
+            if covering.len() > 1 {
+                overlapping += 1;
+            }
+        }
+        // the surface is bumpy enough to hide behind itself.
+        assert!(overlapping > n * n / 20, "{}", overlapping);
+
+        // so the faces' pictures add up to more than what shows, by however
+        // much of them is hidden, and what shows adds up to the picture of the
+        // whole surface.
+        let area = |o: &Obj2| match o {
+            Obj2::Polygon(pg) => pg.area(),
+            Obj2::PolygonWithCavities(pgc) => {
+                pgc.outer.area() - pgc.inner.iter().map(|c| c.area()).sum::<f64>()
+            }
+            x => panic!("{:?}", x),
+        };
+        let shown: f64 = projected.iter().map(|(o, _)| area(o)).sum();
+        let pictured: f64 = pictures
+            .iter()
+            .map(|(pts, _, _)| Ok(Polygon(pts.clone())?.area()))
+            .sum::<Result<f64>>()?;
+        let sampled = covered as f64 / (n * n) as f64 * width * height;
+        assert!(pictured - shown > 0.05 * pictured, "{} {}", pictured, shown);
+        assert_float_eq!(shown, sampled, r2nd <= 0.02);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_esri_ascii() -> Result<()> {
+        let grid = "ncols 3
+            nrows 2
+            xllcorner 10
+            yllcorner 20
+            cellsize 2
+            NODATA_value -9999
+            1 2 3
+            4 -9999 6";
+        let field = Heightfield::parse_esri_ascii(grid.as_bytes())?;
+        // the last row in the file is the southernmost, i.e. the first.
+        assert_eq!(field.rows, vec![vec![4.0, 1.0, 6.0], vec![1.0, 2.0, 3.0]]);
+        assert_eq!(field.pt(0, 0), Point3(11, 21, 4));
+        assert_eq!(field.pt(2, 1), Point3(15, 23, 3));
+
+        assert!(
+            Heightfield::parse_esri_ascii("ncols 3\nnrows 2\ncellsize 1\n1 2".as_bytes()).is_err()
+        );
+        Ok(())
+    }
+}
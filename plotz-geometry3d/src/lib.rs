@@ -14,6 +14,8 @@ use std::f64::consts::FRAC_PI_2;
 pub mod bounded3;
 pub mod camera;
 pub mod group3;
+pub mod heightfield;
+pub mod mesh;
 pub mod obj3;
 pub mod scene;
 pub mod shapes;
@@ -0,0 +1,584 @@
+//! Loaders for triangle meshes: Wavefront OBJ, and ASCII or binary STL.
+//! https://en.wikipedia.org/wiki/Wavefront_.obj_file
+//! https://en.wikipedia.org/wiki/STL_(file_format)
+
+use crate::{
+    group3::Group3,
+    obj3::Obj3,
+    shapes::{
+        point3::Point3,
+        polygon3::{newell_normal, Polygon3},
+        segment3::Segment3,
+    },
+};
+use anyhow::{anyhow, Context, Result};
+use plotz_geometry::style::Style;
+use std::{
+    collections::HashMap,
+    io::{BufRead, Read},
+};
+use typed_builder::TypedBuilder;
+
+/// Config for loading a mesh.
+#[derive(Debug, Copy, Clone, TypedBuilder)]
+pub struct MeshConfig {
+    /// Whether to merge neighboring faces which lie in the same plane into
+    /// larger polygons, so that there are fewer faces to occlude and hatch.
+    #[builder(default = false)]
+    pub merge_coplanar: bool,
+
+    /// How close to parallel (as one minus the cosine of the angle between
+    /// them) two faces must be to count as coplanar.
+    #[builder(default = 1e-6)]
+    pub tolerance: f64,
+}
+
+impl Default for MeshConfig {
+    fn default() -> Self {
+        MeshConfig::builder().build()
+    }
+}
+
+// An OBJ index, which counts from one, or back from the end if negative.
+fn obj_index(token: &str, len: usize) -> Result<usize> {
+    // only the vertex index of |v/vt/vn| matters.
+    let v = token.split('/').next().unwrap_or(token);
+    let i: i64 = v.parse().context(format!("bad index {:?}", token))?;
+    let idx = match i {
+        i if i > 0 => i - 1,
+        i if i < 0 => len as i64 + i,
+        _ => return Err(anyhow!("indices count from one")),
+    };
+    if idx < 0 || idx as usize >= len {
+        return Err(anyhow!("index {} out of range", i));
+    }
+    Ok(idx as usize)
+}
+
+fn parse_floats<'a>(tokens: impl Iterator<Item = &'a str>) -> Result<Vec<f64>> {
+    tokens
+        .map(|t| t.parse::<f64>().context(format!("bad number {:?}", t)))
+        .collect()
+}
+
+/// Parses a Wavefront OBJ file into faces (|f|) and segments (|l|), each
+/// tagged with the name of the group (|g|) or object (|o|) it is in, or the
+/// empty string if none. Texture coordinates, normals and materials are
+/// ignored. See |style_groups| for styling each group.
+pub fn parse_obj(reader: impl BufRead, config: &MeshConfig) -> Result<Group3<String>> {
+    let mut vertices: Vec<Point3> = vec![];
+    let mut faces: Vec<(Polygon3, String)> = vec![];
+    let mut segments: Vec<(Obj3, String)> = vec![];
+    let mut group = String::new();
+
+    for (n, line) in reader.lines().enumerate() {
+        let line = line?;
+        let mut tokens = line.split_whitespace();
+        let context = || format!("line {}: {:?}", n + 1, line);
+        match tokens.next() {
+            Some("v") => match parse_floats(tokens.take(3)).with_context(context)?[..] {
+                [x, y, z] => vertices.push(Point3(x, y, z)),
+                _ => return Err(anyhow!("a vertex needs three coordinates")).with_context(context),
+            },
+            Some("f") => {
+                let mut pts = tokens
+                    .map(|t| Ok(vertices[obj_index(t, vertices.len())?]))
+                    .collect::<Result<Vec<_>>>()
+                    .with_context(context)?;
+                if pts.len() < 3 {
+                    return Err(anyhow!("a face needs three vertices")).with_context(context);
+                }
+                pts.push(pts[0]);
+                faces.push((Polygon3(pts), group.clone()));
+            }
+            Some("l") => {
+                let pts = tokens
+                    .map(|t| Ok(vertices[obj_index(t, vertices.len())?]))
+                    .collect::<Result<Vec<_>>>()
+                    .with_context(context)?;
+                segments.extend(
+                    pts.windows(2)
+                        .map(|w| (Obj3::Segment3(Segment3(w[0], w[1])), group.clone())),
+                );
+            }
+            Some("g" | "o") => {
+                group = tokens.collect::<Vec<_>>().join(" ");
+            }
+            _ => {}
+        }
+    }
+
+    let faces = if config.merge_coplanar {
+        // faces are only merged within their group.
+        let mut by_group: Vec<(String, Vec<Polygon3>)> = vec![];
+        for (face, group) in faces {
+            match by_group.iter_mut().find(|(g, _)| *g == group) {
+                Some((_, v)) => v.push(face),
+                None => by_group.push((group, vec![face])),
+            }
+        }
+        let mut merged = vec![];
+        for (group, faces) in by_group {
+            merged.extend(
+                merge_coplanar(faces, config.tolerance)?
+                    .into_iter()
+                    .map(|f| (f, group.clone())),
+            );
+        }
+        merged
+    } else {
+        faces
+    };
+
+    Ok(Group3::new(
+        faces
+            .into_iter()
+            .map(|(f, g)| (Obj3::Polygon3(f), g))
+            .chain(segments),
+    ))
+}
+
+/// Styles each object of a mesh parsed by |parse_obj| by its group.
+pub fn style_groups(mesh: Group3<String>, style: impl Fn(&str) -> Style) -> Vec<(Obj3, Style)> {
+    mesh.into_iter_objects()
+        .map(|(obj, group)| {
+            let s = style(&group);
+            (obj, s)
+        })
+        .collect()
+}
+
+// A triangle from three points.
+fn triangle(a: Point3, b: Point3, c: Point3) -> Polygon3 {
+    Polygon3([a, b, c, a])
+}
+
+// Parses the facets of an ASCII STL file.
+fn parse_ascii_stl(text: &str) -> Result<Vec<Polygon3>> {
+    let mut triangles = vec![];
+    let mut loop_pts: Vec<Point3> = vec![];
+    for (n, line) in text.lines().enumerate() {
+        let mut tokens = line.split_whitespace();
+        let context = || format!("line {}: {:?}", n + 1, line);
+        match tokens.next() {
+            Some("vertex") => match parse_floats(tokens).with_context(context)?[..] {
+                [x, y, z] => loop_pts.push(Point3(x, y, z)),
+                _ => return Err(anyhow!("a vertex needs three coordinates")).with_context(context),
+            },
+            Some("endloop") => {
+                match loop_pts[..] {
+                    [a, b, c] => triangles.push(triangle(a, b, c)),
+                    _ => return Err(anyhow!("a facet needs three vertices")).with_context(context),
+                }
+                loop_pts.clear();
+            }
+            _ => {}
+        }
+    }
+    Ok(triangles)
+}
+
+// Parses the facets of a binary STL file: an 80-byte header, a count, then
+// for each facet a normal, three vertices, and two bytes of attributes.
+fn parse_binary_stl(bytes: &[u8]) -> Result<Vec<Polygon3>> {
+    let count = u32::from_le_bytes(bytes[80..84].try_into()?) as usize;
+    if bytes.len() < 84 + 50 * count {
+        return Err(anyhow!(
+            "expected {} facets, but the file ends early",
+            count
+        ));
+    }
+    let f =
+        |at: usize| -> Result<f64> { Ok(f32::from_le_bytes(bytes[at..at + 4].try_into()?) as f64) };
+    let pt = |at: usize| -> Result<Point3> { Ok(Point3(f(at)?, f(at + 4)?, f(at + 8)?)) };
+    (0..count)
+        .map(|i| {
+            // skip the normal; it's recomputed from the vertices.
+            let at = 84 + 50 * i + 12;
+            Ok(triangle(pt(at)?, pt(at + 12)?, pt(at + 24)?))
+        })
+        .collect()
+}
+
+/// Parses an STL file, either ASCII or binary, into triangular faces.
+pub fn parse_stl(mut reader: impl Read, config: &MeshConfig) -> Result<Group3<()>> {
+    let mut bytes = vec![];
+    reader.read_to_end(&mut bytes)?;
+
+    // binary files may start with "solid" too, so trust the facet count
+    // first.
+    let is_binary = bytes.len() >= 84
+        && bytes.len() == 84 + 50 * u32::from_le_bytes(bytes[80..84].try_into()?) as usize;
+    let triangles = if is_binary {
+        parse_binary_stl(&bytes)?
+    } else if bytes.starts_with(b"solid") {
+        parse_ascii_stl(std::str::from_utf8(&bytes)?)?
+    } else if bytes.len() >= 84 {
+        parse_binary_stl(&bytes)?
+    } else {
+        return Err(anyhow!("not an STL file"));
+    };
+
+    let faces = if config.merge_coplanar {
+        merge_coplanar(triangles, config.tolerance)?
+    } else {
+        triangles
+    };
+    Ok(Group3::new(
+        faces.into_iter().map(|f| (Obj3::Polygon3(f), ())),
+    ))
+}
+
+// An edge, whichever way it runs.
+pub(crate) fn undirected(a: Point3, b: Point3) -> (Point3, Point3) {
+    if a < b {
+        (a, b)
+    } else {
+        (b, a)
+    }
+}
+
+/// Merges neighboring faces which share an edge and lie in the same plane
+/// into larger polygons, dropping the edges between them and any vertices
+/// left in the middle of a straight edge. A patch grows out from its first
+/// face, taking in each neighbor which faces the same way as that first face
+/// to within |tolerance| (see |MeshConfig::tolerance|), and which lies in its
+/// plane to within the same angle. Comparing against the first face, rather
+/// than neighbor against neighbor, keeps a finely tessellated curve from
+/// merging into one bent patch. A patch of faces whose outline isn't a single
+/// simple ring (e.g. one with a hole) is left as it was. Faces with no area
+/// are dropped.
+pub fn merge_coplanar(faces: Vec<Polygon3>, tolerance: f64) -> Result<Vec<Polygon3>> {
+    let faces: Vec<(Polygon3, Point3)> = faces
+        .into_iter()
+        .filter_map(|f| f.normal().ok().map(|n| (f, n)))
+        .collect();
+
+    // which faces share each edge.
+    let mut edges: HashMap<(Point3, Point3), Vec<usize>> = HashMap::new();
+    for (i, (f, _)) in faces.iter().enumerate() {
+        for w in f.pts.windows(2) {
+            edges.entry(undirected(w[0], w[1])).or_default().push(i);
+        }
+    }
+
+    // the sine of the angle whose cosine is |1 - tolerance|.
+    let sin = (1.0 - (1.0 - tolerance).powi(2)).max(0.0).sqrt();
+    // whether face |j| lies in the plane of, and faces the same way as, the
+    // face |seed|.
+    let fits = |seed: usize, j: usize| {
+        let (origin, normal) = (faces[seed].0.pts[0], faces[seed].1);
+        normal.dot(&faces[j].1) > 1.0 - tolerance
+            && faces[j]
+                .0
+                .pts
+                .iter()
+                .all(|pt| normal.dot(&(*pt - origin)).abs() <= sin * pt.dist(&origin))
+    };
+
+    // patches of coplanar faces, grown across shared edges.
+    let mut patch_of: Vec<Option<usize>> = vec![None; faces.len()];
+    let mut patches: Vec<Vec<usize>> = vec![];
+    for seed in 0..faces.len() {
+        if patch_of[seed].is_some() {
+            continue;
+        }
+        patch_of[seed] = Some(patches.len());
+        let mut patch = vec![seed];
+        let mut k = 0;
+        while k < patch.len() {
+            let f = &faces[patch[k]].0;
+            for w in f.pts.windows(2) {
+                for j in &edges[&undirected(w[0], w[1])] {
+                    if patch_of[*j].is_none() && fits(seed, *j) {
+                        patch_of[*j] = Some(patches.len());
+                        patch.push(*j);
+                    }
+                }
+            }
+            k += 1;
+        }
+        // in the order of their faces, to keep the output stable.
+        patch.sort();
+        patches.push(patch);
+    }
+
+    let mut merged = vec![];
+    for patch in patches {
+        if patch.len() == 1 {
+            merged.push(faces[patch[0]].0.clone());
+            continue;
+        }
+        match outline(patch.iter().map(|i| &faces[*i].0))? {
+            Some(mut ring) => {
+                // wind the outline the same way as the faces.
+                let normal = faces[patch[0]].1;
+                if newell_normal(&ring).dot(&normal) < 0.0 {
+                    ring.reverse();
+                }
+                merged.push(Polygon3(ring));
+            }
+            None => merged.extend(patch.iter().map(|i| faces[*i].0.clone())),
+        }
+    }
+    Ok(merged)
+}
+
+// The outline of a patch of faces, as a closed ring, if it is one simple
+// ring. Vertices in the middle of a straight edge are dropped.
+fn outline<'a>(faces: impl Iterator<Item = &'a Polygon3>) -> Result<Option<Vec<Point3>>> {
+    // edges on the outline belong to just one face of the patch.
+    let mut counts: HashMap<(Point3, Point3), usize> = HashMap::new();
+    for f in faces {
+        for w in f.pts.windows(2) {
+            *counts.entry(undirected(w[0], w[1])).or_default() += 1;
+        }
+    }
+    let mut neighbors: HashMap<Point3, Vec<Point3>> = HashMap::new();
+    for ((a, b), _) in counts.into_iter().filter(|(_, c)| *c == 1) {
+        neighbors.entry(a).or_default().push(b);
+        neighbors.entry(b).or_default().push(a);
+    }
+    if neighbors.is_empty() || neighbors.values().any(|n| n.len() != 2) {
+        return Ok(None);
+    }
+
+    // walk the ring from its least vertex.
+    let start = *neighbors.keys().min().ok_or(anyhow!("no outline"))?;
+    let mut ring = vec![start];
+    let (mut prev, mut cur) = (start, neighbors[&start][0]);
+    while cur != start {
+        ring.push(cur);
+        let next = neighbors[&cur]
+            .iter()
+            .copied()
+            .find(|n| *n != prev)
+            .ok_or(anyhow!("dead end"))?;
+        (prev, cur) = (cur, next);
+    }
+    if ring.len() != neighbors.len() {
+        // more than one ring.
+        return Ok(None);
+    }
+
+    // drop vertices where the outline runs straight on.
+    let n = ring.len();
+    let straight = |i: usize| {
+        let (a, b, c) = (ring[(i + n - 1) % n], ring[i], ring[(i + 1) % n]);
+        let (ab, bc) = (b - a, c - b);
+        ab.cross(&bc).norm() <= 1e-9 * ab.norm() * bc.norm() && ab.dot(&bc) > 0.0
+    };
+    let mut pts: Vec<Point3> = (0..n).filter(|i| !straight(*i)).map(|i| ring[i]).collect();
+    if pts.len() < 3 {
+        return Ok(None);
+    }
+    pts.push(pts[0]);
+    Ok(Some(pts))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_eq::assert_float_eq;
+
+    fn faces<T: 'static>(mesh: Group3<T>) -> Vec<Polygon3> {
+        mesh.into_iter_objects()
+            .filter_map(|(o, _)| match o {
+                Obj3::Polygon3(pg) => Some(pg),
+                _ => None,
+            })
+            .collect()
+    }
+
+    // the area of a planar ring.
+    fn area(pg: &Polygon3) -> f64 {
+        newell_normal(&pg.pts).norm() / 2.0
+    }
+
+    // a unit cube, each of whose sides is two triangles.
+    const CUBE_OBJ: &str = "
+        # a cube.
+        v 0 0 0
+        v 1 0 0
+        v 1 1 0
+        v 0 1 0
+        v 0 0 1
+        v 1 0 1
+        v 1 1 1
+        v 0 1 1
+        g bottom
+        f 1 3 2
+        f 1 4 3
+        g top
+        f 5 6 7
+        f 5 7 8
+        g sides
+        f 1/1/1 2/2/1 6/3/1
+        f 1 6 5
+        f 2 3 7
+        f 2 7 6
+        f 3 4 8
+        f 3 8 7
+        f -4 -8 -5
+        f -4 -5 -1
+        l 1 7
+    ";
+
+    #[test]
+    fn test_parse_obj() -> Result<()> {
+        let mesh = parse_obj(CUBE_OBJ.as_bytes(), &MeshConfig::default())?;
+        let objs: Vec<(Obj3, String)> = mesh.into_iter_objects().collect();
+        assert_eq!(objs.len(), 13);
+        let count = |g: &str| objs.iter().filter(|(_, group)| group == g).count();
+        assert_eq!(count("bottom"), 2);
+        assert_eq!(count("top"), 2);
+        assert_eq!(count("sides"), 9);
+        assert!(matches!(objs[12].0, Obj3::Segment3(_)));
+
+        let styled = style_groups(
+            parse_obj(CUBE_OBJ.as_bytes(), &MeshConfig::default())?,
+            |g| Style {
+                thickness: if g == "top" { 2.0 } else { 1.0 },
+                ..Default::default()
+            },
+        );
+        assert_eq!(styled.iter().filter(|(_, s)| s.thickness == 2.0).count(), 2);
+
+        assert!(parse_obj("v 0 0 0\nf 1 2 3".as_bytes(), &MeshConfig::default()).is_err());
+        assert!(parse_obj("v 0 0\n".as_bytes(), &MeshConfig::default()).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_obj_merge_coplanar() -> Result<()> {
+        let config = MeshConfig::builder().merge_coplanar(true).build();
+        let merged = faces(parse_obj(CUBE_OBJ.as_bytes(), &config)?);
+        // each side is one square.
+        assert_eq!(merged.len(), 6);
+        for face in &merged {
+            assert_eq!(face.pts.len(), 5);
+            assert_float_eq!(area(face), 1.0, abs <= 1e-9);
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_coplanar_keeps_holes() -> Result<()> {
+        // a ring of eight unit squares around a missing middle one, which
+        // can't be one simple polygon.
+        let square = |x: f64, y: f64| {
+            Polygon3([
+                Point3(x, y, 0.0),
+                Point3(x + 1.0, y, 0.0),
+                Point3(x + 1.0, y + 1.0, 0.0),
+                Point3(x, y + 1.0, 0.0),
+                Point3(x, y, 0.0),
+            ])
+        };
+        let ring: Vec<Polygon3> = (0..9)
+            .filter(|i| *i != 4)
+            .map(|i| square((i % 3) as f64, (i / 3) as f64))
+            .collect();
+        assert_eq!(merge_coplanar(ring, 1e-6)?.len(), 8);
+
+        // but a row of them is one long rectangle.
+        let row: Vec<Polygon3> = (0..3).map(|i| square(i as f64, 0.0)).collect();
+        let merged = merge_coplanar(row, 1e-6)?;
+        assert_eq!(merged.len(), 1);
+        assert_eq!(merged[0].pts.len(), 5);
+        assert_float_eq!(area(&merged[0]), 3.0, abs <= 1e-9);
+        Ok(())
+    }
+
+    #[test]
+    fn test_merge_coplanar_keeps_curves() -> Result<()> {
+        // a strip bending a little at each of its twenty faces; each face is
+        // within tolerance of its neighbors, but not of faces further along.
+        let step: f64 = 1e-3;
+        let at = |k: usize, y: f64| {
+            let theta = k as f64 * step;
+            Point3(1e3 * theta.sin(), y, 1e3 * (1.0 - theta.cos()))
+        };
+        let strip: Vec<Polygon3> = (0..20)
+            .map(|k| {
+                Polygon3([
+                    at(k, 0.0),
+                    at(k + 1, 0.0),
+                    at(k + 1, 1.0),
+                    at(k, 1.0),
+                    at(k, 0.0),
+                ])
+            })
+            .collect();
+        assert!(merge_coplanar(strip, 1e-6)?.len() >= 10);
+
+        // nor are faces folded back onto each other merged.
+        let folded = vec![
+            Polygon3([
+                Point3(0, 0, 0),
+                Point3(1, 0, 0),
+                Point3(0, 1, 0),
+                Point3(0, 0, 0),
+            ]),
+            Polygon3([
+                Point3(1, 1, 0),
+                Point3(1, 0, 0),
+                Point3(0, 0, 0),
+                Point3(1, 1, 0),
+            ]),
+        ];
+        assert_eq!(merge_coplanar(folded, 1e-6)?.len(), 2);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_ascii_stl() -> Result<()> {
+        let stl = "solid square
+            facet normal 0 0 1
+              outer loop
+                vertex 0 0 0
+                vertex 1 0 0
+                vertex 1 1 0
+              endloop
+            endfacet
+            facet normal 0 0 1
+              outer loop
+                vertex 0 0 0
+                vertex 1 1 0
+                vertex 0 1 0
+              endloop
+            endfacet
+            endsolid square";
+        assert_eq!(
+            faces(parse_stl(stl.as_bytes(), &MeshConfig::default())?).len(),
+            2
+        );
+
+        let config = MeshConfig::builder().merge_coplanar(true).build();
+        let merged = faces(parse_stl(stl.as_bytes(), &config)?);
+        assert_eq!(merged.len(), 1);
+        assert_float_eq!(area(&merged[0]), 1.0, abs <= 1e-9);
+        Ok(())
+    }
+
+    #[test]
+    fn test_parse_binary_stl() -> Result<()> {
+        let mut bytes = vec![0u8; 80];
+        bytes.extend(1u32.to_le_bytes());
+        for v in [
+            0.0f32, 0.0, 1.0, 0.0, 0.0, 0.0, 2.0, 0.0, 0.0, 0.0, 2.0, 0.0,
+        ] {
+            bytes.extend(v.to_le_bytes());
+        }
+        bytes.extend([0u8, 0u8]);
+
+        let triangles = faces(parse_stl(&bytes[..], &MeshConfig::default())?);
+        assert_eq!(triangles.len(), 1);
+        assert_float_eq!(area(&triangles[0]), 2.0, abs <= 1e-9);
+
+        // a file which claims more facets than it has.
+        bytes[80] = 2;
+        assert!(parse_stl(&bytes[..], &MeshConfig::default()).is_err());
+        Ok(())
+    }
+}
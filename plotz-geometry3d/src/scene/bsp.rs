@@ -0,0 +1,638 @@
+//! A binary space partitioning tree, for putting faces in visibility order.
+//! https://en.wikipedia.org/wiki/Binary_space_partitioning
+//!
+//! Sorting faces by their distance from the viewer is wrong whenever two faces
+//! interpenetrate, or overlap cyclically. A BSP tree instead splits faces
+//! along each other's planes until every piece lies wholly on one side of
+//! every plane it shares a branch with, at which point walking the tree from
+//! the viewer's side outward yields a true front-to-back order.
+
+use crate::{
+    obj3::Obj3,
+    shapes::{
+        point3::Point3,
+        polygon3::{newell_normal, Polygon3},
+        segment3::Segment3,
+    },
+};
+use anyhow::{anyhow, Result};
+use plotz_geometry::tolerance::{self, Tolerance};
+
+// How far from a plane (or from each other) the points of |item| may be and
+// still be on it, under |tol|.
+fn slack<T>(item: &Item<T>, plane_offset: f64, tol: &Tolerance) -> f64 {
+    tol.dist(item.scale.max(plane_offset))
+}
+
+/// Where the viewer is.
+#[derive(Debug, Copy, Clone)]
+pub enum Viewer {
+    /// At a point, as for a perspective projection.
+    At(Point3),
+    /// Infinitely far away, looking in a direction, as for a parallel
+    /// projection.
+    Along(Point3),
+}
+
+impl Viewer {
+    /// Whether |pg| turns its front (see |Polygon3::normal|) towards the
+    /// viewer. Errs if |pg| has no area.
+    pub fn sees_front(&self, pg: &Polygon3) -> Result<bool> {
+        let normal = pg.normal()?;
+        Ok(match self {
+            Viewer::At(eye) => normal.dot(&(*eye - pg.centroid())) >= 0.0,
+            Viewer::Along(direction) => normal.dot(direction) <= 0.0,
+        })
+    }
+}
+
+// A plane, of points |pt| where |normal.dot(pt) == offset|.
+#[derive(Debug, Copy, Clone)]
+struct Plane {
+    normal: Point3,
+    offset: f64,
+}
+
+impl Plane {
+    // The plane through a polygon, if it has one, by Newell's method.
+    fn of(pts: &[Point3]) -> Option<Plane> {
+        let normal = newell_normal(pts).normalize().ok()?;
+        let ctr = pts.iter().fold(Point3(0, 0, 0), |acc, pt| acc + *pt) / pts.len() as f64;
+        Some(Plane {
+            normal,
+            offset: normal.dot(&ctr),
+        })
+    }
+
+    // How far in front of the plane a point is; negative if behind it.
+    fn dist(&self, pt: &Point3) -> f64 {
+        self.normal.dot(pt) - self.offset
+    }
+
+    // Whether the viewer is in front of the plane.
+    fn faces(&self, viewer: &Viewer) -> bool {
+        match viewer {
+            Viewer::At(eye) => self.dist(eye) >= 0.0,
+            Viewer::Along(direction) => self.normal.dot(direction) <= 0.0,
+        }
+    }
+}
+
+// A face (a closed ring of points) or an edge (an open pair of them), to be
+// ordered.
+#[derive(Debug, Clone)]
+struct Item<T> {
+    pts: Vec<Point3>,
+    closed: bool,
+    payload: T,
+    // a sphere around the points, and how far the furthest of them is from
+    // the origin.
+    ctr: Point3,
+    radius: f64,
+    scale: f64,
+}
+
+impl<T: Clone> Item<T> {
+    fn new(pts: Vec<Point3>, closed: bool, payload: T) -> Item<T> {
+        let ctr = pts.iter().fold(Point3(0, 0, 0), |acc, pt| acc + *pt) / pts.len() as f64;
+        let radius = pts.iter().map(|pt| pt.dist(&ctr)).fold(0.0, f64::max);
+        let scale = pts.iter().map(Point3::norm).fold(0.0, f64::max);
+        Item {
+            pts,
+            closed,
+            payload,
+            ctr,
+            radius,
+            scale,
+        }
+    }
+
+    fn with_pts(&self, pts: Vec<Point3>) -> Item<T> {
+        Item::new(pts, self.closed, self.payload.clone())
+    }
+
+    fn into_obj3(self) -> (Obj3, T) {
+        let Item {
+            mut pts, payload, ..
+        } = self;
+        if self.closed {
+            pts.push(pts[0]);
+            (Obj3::Polygon3(Polygon3 { pts }), payload)
+        } else {
+            (Obj3::Segment3(Segment3(pts[0], pts[1])), payload)
+        }
+    }
+}
+
+// Which side of a plane an item lies on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum Class {
+    Coplanar,
+    Front,
+    Back,
+    Spanning,
+}
+
+fn classify<T>(item: &Item<T>, plane: &Plane, tol: &Tolerance) -> Class {
+    let eps = slack(item, plane.offset.abs(), tol);
+    // most items are well clear of the plane, as their bounding spheres show.
+    let d = plane.dist(&item.ctr);
+    if d + item.radius < -eps {
+        return Class::Back;
+    }
+    if d - item.radius > eps {
+        return Class::Front;
+    }
+    let (mut front, mut back) = (false, false);
+    for pt in &item.pts {
+        let d = plane.dist(pt);
+        front |= d > eps;
+        back |= d < -eps;
+    }
+    match (front, back) {
+        (false, false) => Class::Coplanar,
+        (true, false) => Class::Front,
+        (false, true) => Class::Back,
+        (true, true) => Class::Spanning,
+    }
+}
+
+// Which side of a plane an item lies on, splitting it if it spans the plane.
+enum Side<T> {
+    Coplanar(Item<T>),
+    Front(Item<T>),
+    Back(Item<T>),
+    Spanning(Item<T>, Item<T>),
+}
+
+fn split<T: Clone>(item: Item<T>, plane: &Plane, tol: &Tolerance) -> Side<T> {
+    match classify(&item, plane, tol) {
+        Class::Coplanar => return Side::Coplanar(item),
+        Class::Front => return Side::Front(item),
+        Class::Back => return Side::Back(item),
+        Class::Spanning => {}
+    }
+
+    // by https://en.wikipedia.org/wiki/Sutherland%E2%80%93Hodgman_algorithm,
+    // once for each side.
+    let ds: Vec<f64> = item.pts.iter().map(|pt| plane.dist(pt)).collect();
+    let eps = slack(&item, plane.offset.abs(), tol);
+    let n = item.pts.len();
+    let (mut front, mut back) = (vec![], vec![]);
+    for k in 0..n {
+        let (a, da) = (item.pts[k], ds[k]);
+        if da >= -eps {
+            front.push(a);
+        }
+        if da <= eps {
+            back.push(a);
+        }
+        if !item.closed && k + 1 == n {
+            break;
+        }
+        let (b, db) = (item.pts[(k + 1) % n], ds[(k + 1) % n]);
+        if (da > eps && db < -eps) || (da < -eps && db > eps) {
+            let x = a + (b - a) * (da / (da - db));
+            front.push(x);
+            back.push(x);
+        }
+    }
+    Side::Spanning(item.with_pts(front), item.with_pts(back))
+}
+
+// Drops repeated points (which splitting a face through a vertex makes), and
+// whatever is left too small to be a face or an edge.
+fn tidy<T>(mut item: Item<T>, tol: &Tolerance) -> Option<Item<T>> {
+    let eps = slack(&item, 0.0, tol);
+    item.pts.dedup_by(|a, b| a.dist(b) <= eps);
+    if item.closed && item.pts.len() > 1 && item.pts[0].dist(&item.pts[item.pts.len() - 1]) <= eps {
+        item.pts.pop();
+    }
+    if item.pts.len() < if item.closed { 3 } else { 2 } {
+        return None;
+    }
+    Some(item)
+}
+
+// How many faces to try splitting along at each node, and how many items to
+// judge each of them by.
+const CANDIDATES: usize = 5;
+const SAMPLE: usize = 64;
+
+// |k| indices spread evenly over 0..n.
+fn evenly(n: usize, k: usize) -> impl Iterator<Item = usize> {
+    let k = k.min(n);
+    (0..k).map(move |j| j * n / k)
+}
+
+// The face (as an index into |items|) to split |items| along, and its plane:
+// of a few candidates, the one which cuts the fewest of a sample of the items
+// and leaves the rest most evenly balanced. Ties go to the face furthest
+// along, which is cheapest to take out. None if no face has a plane.
+fn choose_splitter<T>(items: &[Item<T>], tol: &Tolerance) -> Option<(usize, Plane)> {
+    let score = |plane: &Plane| {
+        let (mut front, mut back, mut spanning) = (0_usize, 0_usize, 0_usize);
+        for i in evenly(items.len(), SAMPLE) {
+            match classify(&items[i], plane, tol) {
+                Class::Coplanar => {}
+                Class::Front => front += 1,
+                Class::Back => back += 1,
+                Class::Spanning => spanning += 1,
+            }
+        }
+        8 * spanning + front.abs_diff(back)
+    };
+    let plane_of = |i: usize| Some((i, Plane::of(&items[i].pts)?));
+    evenly(items.len(), CANDIDATES)
+        .filter_map(|j| {
+            items[..items.len() - j]
+                .iter()
+                .rposition(|item| item.closed)
+        })
+        .filter_map(plane_of)
+        .min_by_key(|(_, plane)| score(plane))
+        .or_else(|| {
+            (0..items.len())
+                .rev()
+                .filter(|i| items[*i].closed)
+                .find_map(plane_of)
+        })
+}
+
+// How many items in a row |Batch| puts one sphere around.
+const RUN: usize = 32;
+
+// Items awaiting a node, with a sphere around each run of them, so that a
+// whole run can be found to lie on one side of a plane at once. Meshes list
+// neighboring faces near each other, which keeps the spheres small.
+#[derive(Debug)]
+struct Batch<T> {
+    items: Vec<Item<T>>,
+    // each run's center, radius, and furthest point from the origin.
+    runs: Vec<(Point3, f64, f64)>,
+}
+
+impl<T: Clone> Batch<T> {
+    fn new(items: Vec<Item<T>>) -> Batch<T> {
+        let runs = items
+            .chunks(RUN)
+            .map(|run| {
+                let ctr = run.iter().fold(Point3(0, 0, 0), |acc, i| acc + i.ctr) / run.len() as f64;
+                let radius = run
+                    .iter()
+                    .map(|i| i.ctr.dist(&ctr) + i.radius)
+                    .fold(0.0, f64::max);
+                let scale = run.iter().map(|i| i.scale).fold(0.0, f64::max);
+                (ctr, radius, scale)
+            })
+            .collect();
+        Batch { items, runs }
+    }
+
+    // Takes out the item at |idx|, moving the last one into its place.
+    fn swap_remove(&mut self, idx: usize) -> Item<T> {
+        let item = self.items.swap_remove(idx);
+        if let Some(moved) = self.items.get(idx) {
+            let (ctr, radius, scale) = &mut self.runs[idx / RUN];
+            *radius = radius.max(ctr.dist(&moved.ctr) + moved.radius);
+            *scale = scale.max(moved.scale);
+        }
+        self.runs.truncate(self.items.len().div_ceil(RUN));
+        item
+    }
+
+    // |Front| or |Back| if every item lies wholly on that side of |plane|.
+    fn side(&self, plane: &Plane, tol: &Tolerance) -> Option<Class> {
+        let (mut front, mut back) = (false, false);
+        for (run, (ctr, radius, scale)) in self.items.chunks(RUN).zip(&self.runs) {
+            let eps = tol.dist(scale.max(plane.offset.abs()));
+            let d = plane.dist(ctr);
+            if d + radius < -eps {
+                back = true;
+            } else if d - radius > eps {
+                front = true;
+            } else {
+                for item in run {
+                    match classify(item, plane, tol) {
+                        Class::Front => front = true,
+                        Class::Back => back = true,
+                        _ => return None,
+                    }
+                }
+            }
+            if front && back {
+                return None;
+            }
+        }
+        Some(if front { Class::Front } else { Class::Back })
+    }
+}
+
+#[derive(Debug)]
+struct Node<T> {
+    // the splitting plane, unless every item here is planeless.
+    plane: Option<Plane>,
+    // items in the splitting plane: edges first, so that they are drawn atop
+    // the faces they lie on.
+    here: Vec<Item<T>>,
+    // the subtrees on either side, as indices into |Bsp::nodes|.
+    front: Option<usize>,
+    back: Option<usize>,
+}
+
+/// A BSP tree of faces and edges, each carrying some payload (e.g. a style).
+#[derive(Debug)]
+pub struct Bsp<T> {
+    // the root comes first. nodes point to each other by index, rather than
+    // owning each other, so that neither building, walking, nor dropping a
+    // deep tree recurses.
+    nodes: Vec<Node<T>>,
+}
+
+impl<T: Clone> Bsp<T> {
+    /// Builds a tree, splitting faces and edges as needed. Groups are
+    /// flattened; each of their members takes the group's payload.
+    pub fn new(objs: impl IntoIterator<Item = (Obj3, T)>) -> Result<Bsp<T>> {
+        fn items<T: Clone>(obj: Obj3, payload: T, out: &mut Vec<Item<T>>) -> Result<()> {
+            match obj {
+                Obj3::Polygon3(pg) => {
+                    let mut pts = pg.pts;
+                    if pts.len() > 1 && pts[0] == pts[pts.len() - 1] {
+                        pts.pop();
+                    }
+                    if pts.len() < 3 {
+                        return Err(anyhow!("a face needs three or more points"));
+                    }
+                    out.push(Item::new(pts, true, payload));
+                }
+                Obj3::Segment3(sg) => out.push(Item::new(vec![sg.i, sg.f], false, payload)),
+                Obj3::Group3(g) => {
+                    for (o, _) in g.into_iter_objects() {
+                        items(o, payload.clone(), out)?;
+                    }
+                }
+            }
+            Ok(())
+        }
+
+        let mut all = vec![];
+        for (obj, payload) in objs {
+            items(obj, payload, &mut all)?;
+        }
+
+        let tol = tolerance::current();
+        let mut nodes: Vec<Node<T>> = vec![];
+        // items yet to be placed, and the node (and side of it) they go under.
+        let mut todo = vec![(Batch::new(all), None::<(usize, bool)>)];
+        while let Some((mut batch, parent)) = todo.pop() {
+            if batch.items.is_empty() {
+                continue;
+            }
+            let idx = nodes.len();
+            match parent {
+                Some((p, true)) => nodes[p].front = Some(idx),
+                Some((p, false)) => nodes[p].back = Some(idx),
+                None => {}
+            }
+
+            let Some((splitter, plane)) = choose_splitter(&batch.items, &tol) else {
+                // edges and degenerate faces can't hide anything, so their
+                // order doesn't matter.
+                nodes.push(Node {
+                    plane: None,
+                    here: batch.items,
+                    front: None,
+                    back: None,
+                });
+                continue;
+            };
+
+            let splitter = batch.swap_remove(splitter);
+            let mut here = vec![splitter];
+            // along a convex stretch of mesh, everything lies on one side.
+            let (front, back) = match batch.side(&plane, &tol) {
+                Some(Class::Front) => (batch, Batch::new(vec![])),
+                Some(_) => (Batch::new(vec![]), batch),
+                None => {
+                    let (mut front, mut back) = (vec![], vec![]);
+                    for item in batch.items {
+                        match split(item, &plane, &tol) {
+                            Side::Coplanar(i) => here.push(i),
+                            Side::Front(i) => front.push(i),
+                            Side::Back(i) => back.push(i),
+                            Side::Spanning(f, b) => {
+                                front.extend(tidy(f, &tol));
+                                back.extend(tidy(b, &tol));
+                            }
+                        }
+                    }
+                    (Batch::new(front), Batch::new(back))
+                }
+            };
+            here.sort_by_key(|item| item.closed);
+
+            nodes.push(Node {
+                plane: Some(plane),
+                here,
+                front: None,
+                back: None,
+            });
+            todo.push((front, Some((idx, true))));
+            todo.push((back, Some((idx, false))));
+        }
+        Ok(Bsp { nodes })
+    }
+
+    /// Every face and edge, nearest to |viewer| first: no face is hidden,
+    /// even partly, by any face after it.
+    pub fn front_to_back(mut self, viewer: &Viewer) -> Vec<(Obj3, T)> {
+        // a subtree to walk, or the items of a node to emit.
+        enum Visit {
+            Tree(usize),
+            Here(usize),
+        }
+
+        let mut out = vec![];
+        let mut todo: Vec<Visit> = vec![];
+        if !self.nodes.is_empty() {
+            todo.push(Visit::Tree(0));
+        }
+        while let Some(visit) = todo.pop() {
+            match visit {
+                Visit::Tree(idx) => {
+                    let node = &self.nodes[idx];
+                    let (near, far) = match node.plane {
+                        Some(p) if !p.faces(viewer) => (node.back, node.front),
+                        _ => (node.front, node.back),
+                    };
+                    // pushed in reverse, since the last pushed is walked first.
+                    todo.extend(far.map(Visit::Tree));
+                    todo.push(Visit::Here(idx));
+                    todo.extend(near.map(Visit::Tree));
+                }
+                Visit::Here(idx) => {
+                    out.extend(
+                        std::mem::take(&mut self.nodes[idx].here)
+                            .into_iter()
+                            .map(Item::into_obj3),
+                    );
+                }
+            }
+        }
+        out
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::sphere3d::Icosphere;
+    use plotz_geometry::{
+        containment::{default_tolerance, Locate},
+        shapes::{point::Point, polygon::Polygon},
+    };
+    use test_case::test_case;
+
+    // A face over [x0,x1]x[y0,y1], at heights given by |z|.
+    fn face(x0: f64, x1: f64, y0: f64, y1: f64, z: impl Fn(f64, f64) -> f64) -> Obj3 {
+        let pts = [(x0, y0), (x1, y0), (x1, y1), (x0, y1), (x0, y0)]
+            .into_iter()
+            .map(|(x, y)| Point3(x, y, z(x, y)))
+            .collect();
+        Obj3::Polygon3(Polygon3 { pts })
+    }
+
+    // The height of a face at (x,y), if it lies over it.
+    fn height(obj: &Obj3, x: f64, y: f64) -> Result<Option<f64>> {
+        let Obj3::Polygon3(pg) = obj else {
+            return Ok(None);
+        };
+        let flat = Polygon(pg.pts.iter().map(|pt| Point(pt.x, pt.y)))?;
+        if !flat.strictly_contains(&Point(x, y), default_tolerance())? {
+            return Ok(None);
+        }
+        let p = Plane::of(&pg.pts[1..]).unwrap();
+        Ok(Some(
+            (p.offset - p.normal.x * x - p.normal.y * y) / p.normal.z,
+        ))
+    }
+
+    // Looking straight down (or up) at a grid of points, checks that the
+    // first face over each one is the nearest.
+    fn assert_ordered(faces: &[(Obj3, usize)], from_above: bool) -> Result<()> {
+        let viewer = Viewer::Along(Point3(0, 0, if from_above { -1 } else { 1 }));
+        let ordered = Bsp::new(faces.iter().cloned())?.front_to_back(&viewer);
+        assert!(ordered.len() >= faces.len());
+        let nearer = |a: f64, b: f64| if from_above { a > b } else { a < b };
+        for i in 0..30 {
+            for j in 0..30 {
+                let (x, y) = (0.05 + 0.1 * i as f64, 0.07 + 0.1 * j as f64);
+                let mut nearest: Option<f64> = None;
+                for (f, _) in faces {
+                    if let Some(z) = height(f, x, y)? {
+                        if nearest.is_none_or(|n| nearer(z, n)) {
+                            nearest = Some(z);
+                        }
+                    }
+                }
+                let mut first: Option<f64> = None;
+                for (f, _) in &ordered {
+                    if let Some(z) = height(f, x, y)? {
+                        first = Some(z);
+                        break;
+                    }
+                }
+                match (nearest, first) {
+                    (Some(n), Some(f)) => assert!((n - f).abs() < 1e-6, "at ({x},{y})"),
+                    (n, f) => assert_eq!(n.is_some(), f.is_some(), "at ({x},{y})"),
+                }
+            }
+        }
+        Ok(())
+    }
+
+    #[test_case(true; "from above")]
+    #[test_case(false; "from below")]
+    fn test_interpenetrating_faces(from_above: bool) -> Result<()> {
+        // a flat face, and one tilting up through it at x=1.5.
+        assert_ordered(
+            &[
+                (face(0.0, 3.0, 0.0, 3.0, |_, _| 0.0), 0),
+                (face(0.0, 3.0, 0.0, 3.0, |x, _| x - 1.5), 1),
+            ],
+            from_above,
+        )
+    }
+
+    #[test_case(true; "from above")]
+    #[test_case(false; "from below")]
+    fn test_cyclic_overlap(from_above: bool) -> Result<()> {
+        // a pinwheel of sticks, each lying atop the next, and the last atop
+        // the first.
+        assert_ordered(
+            &[
+                (face(0.0, 3.0, 0.0, 1.0, |x, _| x / 3.0), 0),
+                (face(2.0, 3.0, 0.0, 3.0, |_, y| y / 3.0), 1),
+                (face(0.0, 3.0, 2.0, 3.0, |x, _| 1.0 - x / 3.0), 2),
+                (face(0.0, 1.0, 0.0, 3.0, |_, y| 1.0 - y / 3.0), 3),
+            ],
+            from_above,
+        )
+    }
+
+    #[test]
+    fn test_viewer_at_a_point() -> Result<()> {
+        // two parallel faces; whichever is nearer the eye comes first.
+        let faces = || {
+            [
+                (face(0.0, 1.0, 0.0, 1.0, |_, _| 0.0), 'a'),
+                (face(0.0, 1.0, 0.0, 1.0, |_, _| 1.0), 'b'),
+            ]
+        };
+        let first = |eye: Point3| -> Result<char> {
+            Ok(Bsp::new(faces())?.front_to_back(&Viewer::At(eye))[0].1)
+        };
+        assert_eq!(first(Point3(0, 0, 5))?, 'b');
+        assert_eq!(first(Point3(0, 0, -5))?, 'a');
+        // off to one side, but still just above the upper face.
+        assert_eq!(first(Point3(10, 10, 1.5))?, 'b');
+        assert_eq!(first(Point3(10, 10, -0.5))?, 'a');
+        Ok(())
+    }
+
+    #[test]
+    fn test_edges_on_faces_come_first() -> Result<()> {
+        let flat = face(0.0, 1.0, 0.0, 1.0, |_, _| 0.0);
+        let edge = Obj3::Segment3(Segment3(Point3(0, 0, 0), Point3(1, 1, 0)));
+        let ordered =
+            Bsp::new([(flat, 'f'), (edge, 'e')])?.front_to_back(&Viewer::Along(Point3(0, 0, -1)));
+        assert_eq!(
+            ordered.iter().map(|(_, c)| *c).collect::<Vec<_>>(),
+            vec!['e', 'f']
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_large_convex_mesh() -> Result<()> {
+        // every face of a sphere lies behind every other's plane, so no choice
+        // of splitter balances the tree; it is as deep as there are faces.
+        let sphere = Icosphere((0, 0, 0), 1.0, 5);
+        let n = sphere.iter_objects().count();
+        assert!(n >= 10_000);
+        let viewer = Viewer::Along(Point3(0, 0, -1));
+        let ordered = Bsp::new(sphere.into_iter_objects())?.front_to_back(&viewer);
+        assert_eq!(ordered.len(), n);
+
+        // the near side of the sphere comes first.
+        let sees_front = ordered
+            .iter()
+            .map(|(o, _)| match o {
+                Obj3::Polygon3(pg) => viewer.sees_front(pg),
+                _ => Err(anyhow!("expected a face")),
+            })
+            .collect::<Result<Vec<bool>>>()?;
+        assert!(sees_front.windows(2).all(|w| w[0] || !w[1]));
+        Ok(())
+    }
+}
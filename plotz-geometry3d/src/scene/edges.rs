@@ -0,0 +1,179 @@
+//! Picking out which edges of a mesh to draw.
+//!
+//! Outlining every face of a mesh draws every edge between them, most of which
+//! only show how the mesh happens to be cut up. The edges worth drawing are
+//! those on its silhouette, those where it folds sharply, and those where it
+//! ends.
+
+use crate::{
+    mesh::undirected,
+    scene::bsp::Viewer,
+    shapes::{point3::Point3, polygon3::Polygon3, segment3::Segment3},
+};
+use anyhow::Result;
+use std::{collections::HashMap, f64::consts::FRAC_PI_6};
+use typed_builder::TypedBuilder;
+
+/// Which edges of a mesh to draw. Faces meet along an edge if they share both
+/// of its ends exactly.
+#[derive(Debug, Copy, Clone, PartialEq, TypedBuilder)]
+pub struct EdgeConfig {
+    /// Whether to draw silhouette edges, between a face whose front is towards
+    /// the viewer and one whose back is.
+    #[builder(default = true)]
+    pub silhouette: bool,
+
+    /// Draws creases, i.e. edges between faces whose normals are more than
+    /// this many radians apart. Neighbors in the same plane are 0 apart, and
+    /// the faces of a cube are π/2 apart.
+    #[builder(default = Some(FRAC_PI_6))]
+    pub crease_angle: Option<f64>,
+
+    /// Whether to draw boundary edges, which only one face has.
+    #[builder(default = true)]
+    pub boundary: bool,
+}
+
+impl EdgeConfig {
+    /// The edges of |faces| to draw, as seen by |viewer|, in the order they
+    /// first appear. An edge which more than two faces share is always drawn.
+    /// Faces with no area are ignored.
+    pub fn edges(&self, faces: &[Polygon3], viewer: &Viewer) -> Result<Vec<Segment3>> {
+        // each face's normal, and whether its front is towards the viewer.
+        let mut sides: Vec<(Point3, bool)> = vec![];
+        // which faces have each edge, and whether they run along it the same
+        // way as its key.
+        let mut sharers: HashMap<(Point3, Point3), Vec<(usize, bool)>> = HashMap::new();
+        let mut order: Vec<(Point3, Point3)> = vec![];
+
+        for face in faces {
+            let Ok(normal) = face.normal() else {
+                continue;
+            };
+            let i = sides.len();
+            sides.push((normal, viewer.sees_front(face)?));
+            for w in face.pts.windows(2) {
+                if w[0] == w[1] {
+                    continue;
+                }
+                let key = undirected(w[0], w[1]);
+                let entry = sharers.entry(key).or_default();
+                if entry.is_empty() {
+                    order.push(key);
+                }
+                entry.push((i, key.0 == w[0]));
+            }
+        }
+
+        let mut edges = vec![];
+        for key in order {
+            let drawn = match sharers[&key][..] {
+                [_] => self.boundary,
+                [(i, i_along), (j, j_along)] => {
+                    let ((ni, fi), (mut nj, mut fj)) = (sides[i], sides[j]);
+                    // neighbors wound the same way run along their shared edge
+                    // opposite ways. if these don't, then turn one over.
+                    if i_along == j_along {
+                        nj *= -1.0;
+                        fj = !fj;
+                    }
+                    (self.silhouette && fi != fj)
+                        || self
+                            .crease_angle
+                            .is_some_and(|angle| ni.dot(&nj).clamp(-1.0, 1.0).acos() > angle)
+                }
+                _ => true,
+            };
+            if drawn {
+                edges.push(Segment3(key.0, key.1));
+            }
+        }
+        Ok(edges)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{
+        obj3::Obj3,
+        shapes::{cube3d::Cube, plane3d::PlaneGrid},
+    };
+
+    fn faces(obj: impl IntoIterator<Item = Obj3>) -> Vec<Polygon3> {
+        obj.into_iter()
+            .filter_map(|o| match o {
+                Obj3::Polygon3(pg) => Some(pg),
+                _ => None,
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_cube_edges() -> Result<()> {
+        let cube = faces(
+            Cube(Point3(0, 0, 0), 1.0)
+                .into_iter_objects()
+                .map(|(o, _)| o),
+        );
+        let from_above = Viewer::Along(Point3(0, 0, -1));
+        let config = |silhouette, crease_angle| {
+            EdgeConfig::builder()
+                .silhouette(silhouette)
+                .crease_angle(crease_angle)
+                .build()
+        };
+
+        // every edge of a cube is a crease.
+        assert_eq!(
+            config(false, Some(1.0)).edges(&cube, &from_above)?.len(),
+            12
+        );
+        assert_eq!(config(false, Some(2.0)).edges(&cube, &from_above)?.len(), 0);
+
+        // seen from straight above, only the top face is towards the viewer.
+        // the sides are edge-on, and so count as turned towards it, too.
+        let silhouette = config(true, None).edges(&cube, &from_above)?;
+        assert_eq!(silhouette.len(), 4);
+        assert!(silhouette.iter().all(|sg| sg.i.z == 0.0 && sg.f.z == 0.0));
+
+        // seen from a corner, three faces are towards the viewer.
+        let from_corner = Viewer::Along(Point3(-1, -1, -1));
+        assert_eq!(config(true, None).edges(&cube, &from_corner)?.len(), 6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_boundary_edges() -> Result<()> {
+        // a flat 2x2 grid of faces has a boundary all around, but no
+        // creases.
+        let grid = PlaneGrid(Point3(0, 0, 0), (Point3(2, 0, 0), Point3(0, 2, 0)), (2, 2))?;
+        let grid = faces(grid.into_iter_objects().map(|(o, _)| o));
+        let from_above = Viewer::Along(Point3(0, 0, -1));
+        assert_eq!(
+            EdgeConfig::builder()
+                .build()
+                .edges(&grid, &from_above)?
+                .len(),
+            8
+        );
+
+        // turning one face over changes nothing.
+        let mut flipped = grid.clone();
+        flipped[0].pts.reverse();
+        assert_eq!(
+            EdgeConfig::builder()
+                .build()
+                .edges(&flipped, &from_above)?
+                .len(),
+            8
+        );
+
+        assert!(EdgeConfig::builder()
+            .boundary(false)
+            .build()
+            .edges(&grid, &from_above)?
+            .is_empty());
+        Ok(())
+    }
+}
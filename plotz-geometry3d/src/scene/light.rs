@@ -0,0 +1,232 @@
+//! Lights, and how brightly they light each face.
+//!
+//! A face's brightness is Lambertian: each light adds its brightness times the
+//! cosine of the angle between the face's normal and the direction to it.
+//! Darker faces are hatched more densely.
+
+use crate::{
+    scene::bsp::Viewer,
+    shapes::{point3::Point3, polygon3::Polygon3},
+};
+use anyhow::{anyhow, Result};
+use plotz_geometry::shading::{fill_pattern::FillPattern, shade_config::ShadeConfig};
+use std::f64::consts::FRAC_PI_4;
+use typed_builder::TypedBuilder;
+
+/// A light source.
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum Light {
+    /// Light from infinitely far away, shining along |direction|, like the sun.
+    Directional {
+        /// Which way the light travels.
+        direction: Point3,
+        /// How bright the light is; 1.0 fully lights a face square to it.
+        brightness: f64,
+    },
+    /// Light shining out from a point, like a bulb. It doesn't fall off with
+    /// distance.
+    Point {
+        /// Where the light is.
+        at: Point3,
+        /// How bright the light is; 1.0 fully lights a face square to it.
+        brightness: f64,
+    },
+}
+
+impl Light {
+    // The unit vector from |pt| towards the light.
+    fn towards(&self, pt: &Point3) -> Result<Point3> {
+        match self {
+            Light::Directional { direction, .. } => (*direction * -1.0).normalize(),
+            Light::Point { at, .. } => (*at - *pt).normalize(),
+        }
+    }
+
+    fn brightness(&self) -> f64 {
+        match self {
+            Light::Directional { brightness, .. } | Light::Point { brightness, .. } => *brightness,
+        }
+    }
+}
+
+/// How darkness is drawn.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Hatching {
+    /// One direction of hatching, whose lines are closer together the darker
+    /// the face.
+    Density,
+    /// Layers of hatching at evenly spaced angles; the darker the face, the
+    /// more layers cross it.
+    CrossHatch,
+}
+
+/// Config for lighting a scene.
+#[derive(Debug, Clone, TypedBuilder)]
+pub struct Lighting {
+    /// The lights.
+    #[builder(default)]
+    pub lights: Vec<Light>,
+
+    /// How bright every face is, lit or not.
+    #[builder(default = 0.0)]
+    pub ambient: f64,
+
+    /// How many hatched tones to quantize darkness into. The lightest faces
+    /// get one more tone, which isn't hatched at all, so there are
+    /// |levels| + 1 tones in all.
+    #[builder(default = 4)]
+    pub levels: usize,
+
+    /// The gap between lines: in every layer for |Hatching::CrossHatch|, and
+    /// in the darkest tone for |Hatching::Density|.
+    #[builder(default = 0.1)]
+    pub gap: f64,
+
+    /// The angle of the first layer, in radians.
+    #[builder(default = FRAC_PI_4)]
+    pub angle: f64,
+
+    /// How darkness is drawn.
+    #[builder(default = Hatching::Density)]
+    pub hatching: Hatching,
+
+    /// Whether to hatch along each face, rather than in the camera plane. See
+    /// |ShadeConfig::along_face|.
+    #[builder(default = false)]
+    pub along_face: bool,
+}
+
+impl Lighting {
+    /// How brightly |pg| is lit, as seen by |viewer|, from 0.0 (black) to 1.0
+    /// (white). Only the side of the face towards the viewer is lit.
+    pub fn intensity(&self, pg: &Polygon3, viewer: &Viewer) -> Result<f64> {
+        let mut normal = pg.normal()?;
+        if !viewer.sees_front(pg)? {
+            normal *= -1.0;
+        }
+        let ctr = pg.centroid();
+
+        let mut intensity = self.ambient;
+        for light in &self.lights {
+            intensity += light.brightness() * normal.dot(&light.towards(&ctr)?).max(0.0);
+        }
+        Ok(intensity.clamp(0.0, 1.0))
+    }
+
+    /// How to hatch a face lit with |intensity|, or |None| if it is light
+    /// enough to leave bare.
+    pub fn shade_config(&self, intensity: f64) -> Result<Option<ShadeConfig>> {
+        if self.gap <= 0.0 {
+            return Err(anyhow!("gap must be positive"));
+        }
+        let levels = self.levels.max(1);
+        let tone = ((1.0 - intensity.clamp(0.0, 1.0)) * levels as f64).round() as usize;
+        if tone == 0 {
+            return Ok(None);
+        }
+        let (gap, pattern) = match self.hatching {
+            Hatching::Density => (self.gap * levels as f64 / tone as f64, FillPattern::Lines),
+            Hatching::CrossHatch => (self.gap, FillPattern::CrossHatch { n: tone }),
+        };
+        Ok(Some(
+            ShadeConfig::builder()
+                .gap(gap)
+                .angle(self.angle)
+                .pattern(pattern)
+                .along_face(self.along_face)
+                .build(),
+        ))
+    }
+
+    /// How to hatch |pg|, as seen by |viewer|. See |intensity| and
+    /// |shade_config|.
+    pub fn shade(&self, pg: &Polygon3, viewer: &Viewer) -> Result<Option<ShadeConfig>> {
+        self.shade_config(self.intensity(pg, viewer)?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_eq::assert_float_eq;
+
+    // a unit square in the plane z=0.
+    fn floor() -> Polygon3 {
+        Polygon3([
+            Point3(0, 0, 0),
+            Point3(1, 0, 0),
+            Point3(1, 1, 0),
+            Point3(0, 1, 0),
+            Point3(0, 0, 0),
+        ])
+    }
+
+    #[test]
+    fn test_intensity() -> Result<()> {
+        let from_above = Viewer::Along(Point3(0, 0, -1));
+        let lit = |light: Light| {
+            Lighting::builder()
+                .lights(vec![light])
+                .build()
+                .intensity(&floor(), &from_above)
+        };
+
+        let sun = |direction| Light::Directional {
+            direction,
+            brightness: 1.0,
+        };
+        assert_float_eq!(lit(sun(Point3(0, 0, -1)))?, 1.0, abs <= 1e-9);
+        assert_float_eq!(
+            lit(sun(Point3(1, 0, -1)))?,
+            std::f64::consts::FRAC_1_SQRT_2,
+            abs <= 1e-9
+        );
+        // grazing, and from beneath.
+        assert_float_eq!(lit(sun(Point3(1, 0, 0)))?, 0.0, abs <= 1e-9);
+        assert_float_eq!(lit(sun(Point3(0, 0, 1)))?, 0.0, abs <= 1e-9);
+
+        // a bulb right above the middle of the face.
+        let bulb = Light::Point {
+            at: Point3(0.5, 0.5, 3),
+            brightness: 0.5,
+        };
+        assert_float_eq!(lit(bulb)?, 0.5, abs <= 1e-9);
+
+        // the side facing the viewer is the one lit, however the ring winds.
+        let from_below = Viewer::At(Point3(0.5, 0.5, -5));
+        let lighting = Lighting::builder()
+            .lights(vec![sun(Point3(0, 0, 1))])
+            .ambient(0.25)
+            .build();
+        assert_float_eq!(lighting.intensity(&floor(), &from_below)?, 1.0, abs <= 1e-9);
+        assert_float_eq!(
+            lighting.intensity(&floor(), &from_above)?,
+            0.25,
+            abs <= 1e-9
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_shade_config() -> Result<()> {
+        let density = Lighting::builder().levels(4).gap(1.0).build();
+        assert_eq!(density.shade_config(1.0)?, None);
+        assert_float_eq!(density.shade_config(0.5)?.unwrap().gap, 2.0, abs <= 1e-9);
+        assert_float_eq!(density.shade_config(0.0)?.unwrap().gap, 1.0, abs <= 1e-9);
+
+        let cross = Lighting::builder()
+            .levels(4)
+            .gap(1.0)
+            .hatching(Hatching::CrossHatch)
+            .build();
+        assert_eq!(
+            cross.shade_config(0.0)?.unwrap().pattern,
+            FillPattern::CrossHatch { n: 4 }
+        );
+        assert_eq!(
+            cross.shade_config(0.75)?.unwrap().pattern,
+            FillPattern::CrossHatch { n: 1 }
+        );
+        Ok(())
+    }
+}
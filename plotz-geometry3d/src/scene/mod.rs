@@ -1,17 +1,26 @@
 //! A scene, i.e. a holder for 3d objects ready to be projected down onto a 2d
 //! plane.
 
+pub mod bsp;
 pub mod debug;
+pub mod edges;
+pub mod light;
 pub mod occluder;
 
 use crate::{
     camera::Projection,
+    group3::Group3,
     obj3::Obj3,
-    scene::{debug::SceneDebug, occluder::Occluder},
+    scene::{
+        bsp::{Bsp, Viewer},
+        debug::SceneDebug,
+        edges::EdgeConfig,
+        light::Lighting,
+        occluder::Occluder,
+    },
+    shapes::polygon3::Polygon3,
 };
 use anyhow::*;
-use float_ord::FloatOrd;
-use itertools::Itertools;
 use plotz_color::ColorRGB;
 use plotz_geometry::{obj2::Obj2, style::Style, *};
 use std::fmt::Debug;
@@ -33,51 +42,134 @@ pub struct Scene {
 
     #[builder(default)]
     projection: Projection,
+
+    // if set, shades every face by how brightly it is lit, in place of its
+    // own shading. only drawn when occluding.
+    #[builder(default, setter(strip_option))]
+    lighting: Option<Lighting>,
+
+    // if set, drops every face whose back is towards the viewer.
+    #[builder(default)]
+    cull_backfaces: bool,
+
+    // if set, draws only some edges of the faces of each style (see
+    // |EdgeConfig|) in place of the faces themselves, which still hide what's
+    // behind them when occluding.
+    #[builder(default, setter(strip_option))]
+    edges: Option<EdgeConfig>,
+}
+
+// |obj|, less whichever of its faces have their backs towards |viewer|.
+fn cull(obj: Obj3, viewer: &Viewer) -> Option<Obj3> {
+    match obj {
+        Obj3::Polygon3(pg) if viewer.sees_front(&pg).is_ok_and(|front| !front) => None,
+        Obj3::Group3(g) => Some(Obj3::Group3(Group3::new(
+            g.into_iter_objects()
+                .filter_map(|(o, t)| Some((cull(o, viewer)?, t))),
+        ))),
+        obj => Some(obj),
+    }
+}
+
+// Sorts |obj| into its faces and everything else.
+fn split_faces(obj: Obj3, faces: &mut Vec<Polygon3>, rest: &mut Vec<Obj3>) {
+    match obj {
+        Obj3::Polygon3(pg) => faces.push(pg),
+        Obj3::Group3(g) => {
+            for (o, _) in g.into_iter_objects() {
+                split_faces(o, faces, rest);
+            }
+        }
+        obj => rest.push(obj),
+    }
 }
 
 impl Scene {
     #[instrument(skip(self))]
     pub fn project(self) -> Result<Vec<(Obj2, Style)>> {
-        match (self.projection, self.occluder) {
-            (Projection::Oblique(obl), None) => Ok(self
-                .objects
+        let projection = self.projection;
+        let mut objects = self.objects;
+        // faces which hide what's behind them, but aren't drawn.
+        let mut masks: Vec<Obj3> = vec![];
+
+        if self.cull_backfaces || self.edges.is_some() {
+            let viewer = projection.viewer()?;
+            if self.cull_backfaces {
+                objects = objects
+                    .into_iter()
+                    .filter_map(|(o, s)| Some((cull(o, &viewer)?, s)))
+                    .collect();
+            }
+            if let Some(config) = &self.edges {
+                // faces of one style share edges whether or not they were
+                // given as one object, so their edges are found all at once.
+                let mut edged = vec![];
+                let mut faces_by_style: Vec<(Style, Vec<Polygon3>)> = vec![];
+                for (obj, style) in objects {
+                    let (mut faces, mut rest) = (vec![], vec![]);
+                    split_faces(obj, &mut faces, &mut rest);
+                    edged.extend(rest.into_iter().map(|o| (o, style)));
+                    match faces_by_style.iter_mut().find(|(s, _)| *s == style) {
+                        Some((_, fs)) => fs.extend(faces),
+                        None => faces_by_style.push((style, faces)),
+                    }
+                }
+                for (style, faces) in faces_by_style {
+                    let edge_style = Style {
+                        shading: None,
+                        ..style
+                    };
+                    edged.extend(
+                        config
+                            .edges(&faces, &viewer)?
+                            .into_iter()
+                            .map(|sg| (Obj3::Segment3(sg), edge_style)),
+                    );
+                    masks.extend(faces.into_iter().map(Obj3::Polygon3));
+                }
+                objects = edged;
+            }
+        }
+
+        match self.occluder {
+            None => objects
                 .iter()
-                .map(|sobj3| obl.project_styled_obj3(sobj3))
-                .collect()),
+                .filter_map(|sobj3| projection.project_styled_obj3(sobj3).transpose())
+                .collect(),
 
-            (Projection::Oblique(obl), Some(mut occluder)) => {
+            Some(mut occluder) => {
                 let mut resultant: Vec<(Obj2, Style)> = vec![];
 
-                // add objects to the occluder in distance order.
+                // add objects to the occluder in visibility order.
                 // start at the front (so that the objects in the front can
-                // remain unmodified) and work backwards.
-
-                let mut sorted_objs: Vec<(Obj3, Style)> = self
-                    .objects
-                    .into_iter()
-                    .sorted_by(|(o1, _), (o2, _)| {
-                        Ord::cmp(
-                            &FloatOrd(o1.dist_along(&obl.view_vector())),
-                            &FloatOrd(o2.dist_along(&obl.view_vector())),
-                        )
-                    })
-                    .collect();
+                // remain unmodified) and work backwards. faces are split
+                // where they must be, so that none is hidden by any after it.
+                let viewer = projection.viewer()?;
+                // masks carry no style.
+                let mut sorted_objs: Vec<(Obj3, Option<Style>)> = Bsp::new(
+                    objects
+                        .into_iter()
+                        .map(|(o, s)| (o, Some(s)))
+                        .chain(masks.into_iter().map(|o| (o, None))),
+                )?
+                .front_to_back(&viewer);
 
-                // TODO(https://github.com/ambuc/plotz_rs/issues/1):
-                // OK, here is the place to fix -- we don't want to sort by any
-                // individual min or max. we want to take two obj3s, proj them
-                // to obj2s, and find if they intersect.  if they don't
-                // intersect, then the ordering doesn't matter (i think this
-                // means we can order them by dist to obj center). if they do
-                // intersect, then we need to look at the intersection point and
-                // figure out if o1 or o2 is in front _at that point_ and order
-                // by that.
+                if let Some(lighting) = &self.lighting {
+                    for (obj, s) in sorted_objs.iter_mut() {
+                        if let (Obj3::Polygon3(pg), Some(s)) = (obj, s) {
+                            s.shading = lighting.shade(pg, &viewer)?;
+                        }
+                    }
+                }
 
                 // optionally color according to depth.
                 if let Some(x) = occluder.config.color_according_to_depth {
                     let length = sorted_objs.len();
 
                     for (i, (_, s)) in sorted_objs.iter_mut().enumerate() {
+                        let Some(s) = s else {
+                            continue;
+                        };
                         let pct: f64 = (i as f64) / (length as f64);
                         //
                         let c = x.at(pct);
@@ -89,8 +181,19 @@ impl Scene {
                     }
                 }
 
-                for sobj3 in sorted_objs {
-                    let (obj, style) = obl.project_styled_obj3(&sobj3);
+                for (obj3, style) in sorted_objs {
+                    let Some(style) = style else {
+                        if let Some((mask, _)) =
+                            projection.project_styled_obj3(&(obj3, Style::default()))?
+                        {
+                            occluder.add_mask(mask)?;
+                        }
+                        continue;
+                    };
+                    let sobj3 = (obj3, style);
+                    let Some((obj, style)) = projection.project_styled_obj3(&sobj3)? else {
+                        continue;
+                    };
 
                     if let Some(SceneDebug {
                         draw_wireframes,
@@ -116,6 +219,24 @@ impl Scene {
                         }
                     }
 
+                    // hatching along a face lies on it, so it is hidden by
+                    // whatever hides the face, but not by the face itself.
+                    if let (Obj3::Polygon3(pg), Some(shade_config)) = (&sobj3.0, style.shading) {
+                        if shade_config.along_face {
+                            let hatch_style = Style {
+                                shading: None,
+                                ..style
+                            };
+                            for sg in pg.hatch(&shade_config)? {
+                                if let Some(hatch) = projection
+                                    .project_styled_obj3(&(Obj3::Segment3(sg), hatch_style))?
+                                {
+                                    occluder.add(hatch)?;
+                                }
+                            }
+                        }
+                    }
+
                     let dbg = format!("adding object:\n\t{:?}", &obj);
                     occluder.add((obj, style)).context(dbg)?;
                 }
@@ -125,3 +246,272 @@ impl Scene {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::scene::light::Light;
+    use crate::{
+        camera::Orthographic,
+        shapes::{cube3d::Cube, point3::Point3, polygon3::Polygon3, segment3::Segment3},
+    };
+    use float_eq::assert_float_eq;
+    use plotz_color::{BLUE, RED};
+    use plotz_geometry::shading::shade_config::ShadeConfig;
+
+    #[test]
+    fn test_interpenetrating_faces_occlude_each_other() -> Result<()> {
+        // seen from above: a flat face, and one tilting up through it halfway
+        // along. each should hide the other's half.
+        let flat = Polygon3([
+            Point3(0, 0, 0),
+            Point3(2, 0, 0),
+            Point3(2, 1, 0),
+            Point3(0, 1, 0),
+            Point3(0, 0, 0),
+        ]);
+        let tilted = Polygon3([
+            Point3(0, 0, -1),
+            Point3(2, 0, 1),
+            Point3(2, 1, 1),
+            Point3(0, 1, -1),
+            Point3(0, 0, -1),
+        ]);
+        let style = |color| Style {
+            color,
+            ..Default::default()
+        };
+        let projected = Scene::builder()
+            .objects(vec![
+                (Obj3::Polygon3(flat), style(RED)),
+                (Obj3::Polygon3(tilted), style(BLUE)),
+            ])
+            .projection(Projection::Orthographic(Orthographic::top()))
+            .occluder(Occluder::default())
+            .build()
+            .project()?;
+
+        let area = |color| -> f64 {
+            projected
+                .iter()
+                .filter(|(_, s)| s.color == color)
+                .map(|(o, _)| match o {
+                    Obj2::Polygon(pg) => pg.area(),
+                    _ => 0.0,
+                })
+                .sum()
+        };
+        assert_float_eq!(area(RED), 1.0, abs <= 1e-6);
+        assert_float_eq!(area(BLUE), 1.0, abs <= 1e-6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_edge_behind_face_is_hidden() -> Result<()> {
+        // seen from above: an edge passing beneath a face shows only where it
+        // sticks out on either side.
+        let face = Polygon3([
+            Point3(0, 0, 0),
+            Point3(2, 0, 0),
+            Point3(2, 1, 0),
+            Point3(0, 1, 0),
+            Point3(0, 0, 0),
+        ]);
+        let edge = Segment3(Point3(-1, 0.5, -1), Point3(3, 0.5, -1));
+        let projected = Scene::builder()
+            .objects(vec![
+                (Obj3::Polygon3(face), Style::default()),
+                (Obj3::Segment3(edge), Style::default()),
+            ])
+            .projection(Projection::Orthographic(Orthographic::top()))
+            .occluder(Occluder::default())
+            .build()
+            .project()?;
+
+        let lengths: Vec<f64> = projected
+            .iter()
+            .filter_map(|(o, _)| match o {
+                Obj2::Segment(sg) => Some(sg.length()),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(lengths.len(), 2);
+        assert_float_eq!(lengths.iter().sum::<f64>(), 2.0, abs <= 1e-6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_hatching_along_face_is_occluded() -> Result<()> {
+        // seen from above: a hatched floor, half of it under a roof.
+        let floor = Polygon3([
+            Point3(0, 0, 0),
+            Point3(2, 0, 0),
+            Point3(2, 2, 0),
+            Point3(0, 2, 0),
+            Point3(0, 0, 0),
+        ]);
+        let roof = Polygon3([
+            Point3(0, -1, 1),
+            Point3(1, -1, 1),
+            Point3(1, 3, 1),
+            Point3(0, 3, 1),
+            Point3(0, -1, 1),
+        ]);
+        let hatched = Style {
+            color: RED,
+            shading: Some(
+                ShadeConfig::builder()
+                    .gap(0.3)
+                    .angle(0.0)
+                    .along_face(true)
+                    .build(),
+            ),
+            ..Default::default()
+        };
+        let hatch_length = |objects: Vec<(Obj3, Style)>| -> Result<f64> {
+            Ok(Scene::builder()
+                .objects(objects)
+                .projection(Projection::Orthographic(Orthographic::top()))
+                .occluder(Occluder::default())
+                .build()
+                .project()?
+                .iter()
+                .filter(|(_, s)| s.color == RED)
+                .map(|(o, _)| match o {
+                    Obj2::Segment(sg) => sg.length(),
+                    x => panic!("{:?}", x),
+                })
+                .sum())
+        };
+
+        let bare = hatch_length(vec![(Obj3::Polygon3(floor.clone()), hatched)])?;
+        assert!(bare > 0.0);
+        let covered = hatch_length(vec![
+            (Obj3::Polygon3(floor), hatched),
+            (
+                Obj3::Polygon3(roof),
+                Style {
+                    color: BLUE,
+                    ..Default::default()
+                },
+            ),
+        ])?;
+        assert_float_eq!(covered, bare / 2.0, abs <= 1e-6);
+        Ok(())
+    }
+
+    #[test]
+    fn test_lighting_shades_faces() -> Result<()> {
+        // a floor, seen from above.
+        let floor = Polygon3([
+            Point3(0, 0, 0),
+            Point3(2, 0, 0),
+            Point3(2, 2, 0),
+            Point3(0, 2, 0),
+            Point3(0, 0, 0),
+        ]);
+        let lit = |direction| -> Result<usize> {
+            Ok(Scene::builder()
+                .objects(vec![(Obj3::Polygon3(floor.clone()), Style::default())])
+                .projection(Projection::Orthographic(Orthographic::top()))
+                .occluder(Occluder::default())
+                .lighting(
+                    Lighting::builder()
+                        .lights(vec![Light::Directional {
+                            direction,
+                            brightness: 1.0,
+                        }])
+                        .build(),
+                )
+                .build()
+                .project()?
+                .iter()
+                .filter(|(o, _)| matches!(o, Obj2::Segment(_)))
+                .count())
+        };
+        // lit from overhead, the floor is left bare; lit from the side, it is
+        // hatched.
+        assert_eq!(lit(Point3(0, 0, -1))?, 0);
+        assert!(lit(Point3(1, 0, 0))? > 0);
+        Ok(())
+    }
+
+    #[test]
+    fn test_cull_backfaces() -> Result<()> {
+        // seen from a corner, three of a cube's faces are towards the viewer.
+        let faces = |cull_backfaces| -> Result<usize> {
+            Ok(Scene::builder()
+                .objects(
+                    Cube(Point3(0, 0, 0), 1.0)
+                        .into_iter_objects()
+                        .map(|(o, _)| (o, Style::default()))
+                        .collect(),
+                )
+                .projection(Projection::Orthographic(Orthographic::isometric()))
+                .cull_backfaces(cull_backfaces)
+                .build()
+                .project()?
+                .len())
+        };
+        assert_eq!(faces(false)?, 6);
+        assert_eq!(faces(true)?, 3);
+        Ok(())
+    }
+
+    #[test]
+    fn test_edges_in_place_of_faces() -> Result<()> {
+        // seen from a corner, a cube shows six edges around its silhouette and
+        // three creases inside it. the three edges meeting at the far corner
+        // are hidden.
+        let projected = Scene::builder()
+            .objects(vec![(
+                Obj3::Group3(Cube(Point3(0, 0, 0), 1.0)),
+                Style::default(),
+            )])
+            .projection(Projection::Orthographic(Orthographic::isometric()))
+            .occluder(Occluder::default())
+            .edges(EdgeConfig::builder().build())
+            .build()
+            .project()?;
+        assert_eq!(projected.len(), 9);
+        assert!(projected.iter().all(|(o, _)| matches!(o, Obj2::Segment(_))));
+        Ok(())
+    }
+
+    #[test]
+    fn test_edges_shared_between_objects() -> Result<()> {
+        // a cube given face by face has the same edges as one given whole.
+        let project = |objects: Vec<(Obj3, Style)>| {
+            Scene::builder()
+                .objects(objects)
+                .projection(Projection::Orthographic(Orthographic::isometric()))
+                .occluder(Occluder::default())
+                .edges(EdgeConfig::builder().build())
+                .build()
+                .project()
+        };
+        let faces: Vec<(Obj3, Style)> = Cube(Point3(0, 0, 0), 1.0)
+            .into_iter_objects()
+            .map(|(o, _)| (o, Style::default()))
+            .collect();
+        assert_eq!(faces.len(), 6);
+        assert_eq!(project(faces.clone())?.len(), 9);
+
+        // but faces of different styles don't share any.
+        let restyled = faces
+            .into_iter()
+            .enumerate()
+            .map(|(k, (o, s))| {
+                (
+                    o,
+                    Style {
+                        thickness: k as f64,
+                        ..s
+                    },
+                )
+            })
+            .collect();
+        assert_eq!(project(restyled)?.len(), 18);
+        Ok(())
+    }
+}
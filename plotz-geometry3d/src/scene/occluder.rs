@@ -2,10 +2,22 @@
 
 use anyhow::*;
 use itertools::Itertools;
-use plotz_geometry::{crop::Croppable, obj2::Obj2, shading::shade_polygon, style::Style};
+use plotz_geometry::{
+    bounded::Bounded,
+    crop::Croppable,
+    obj2::Obj2,
+    quadtree::QuadTree,
+    shading::shade,
+    shapes::{point::Point, polygon::Polygon, polygon_with_cavity::PolygonWithCavities},
+    style::Style,
+    tolerance::Tolerance,
+};
 use tracing::*;
 use typed_builder::TypedBuilder;
 
+// how thin a face may be, as a fraction of its size, before it is a sliver.
+const SLIVER: f64 = 1e-9;
+
 #[derive(Debug, Clone, Copy, Default, TypedBuilder)]
 pub struct OccluderConfig {
     #[builder(default)]
@@ -17,65 +29,106 @@ pub struct Occluder {
     #[builder(default)]
     pub config: OccluderConfig,
 
+    // private, so that nothing can change it behind |index|'s back.
     #[builder(default)]
-    pub objects: Vec<(Obj2, Style)>,
+    objects: Vec<(Obj2, Style)>,
+
+    // indexes |objects| by their bounds. built on first use, since the
+    // builder may have set |objects|, and kept up to date from then on.
+    #[builder(default, setter(skip))]
+    index: QuadTree<()>,
+
+    // which of |objects| are masks (see |add_mask|). anything not marked is
+    // drawn.
+    #[builder(default, setter(skip))]
+    masks: Vec<bool>,
 }
 
 // Despite the name, this really only layers A atop B atop C and computes their
 // crops. Maybe a better name would be |Obscurer|. Anyway.
 impl Occluder {
     fn hide_a_behind_b(incoming: &Obj2, existing: &Obj2) -> Result<Vec<Obj2>> {
-        // TODO(https://github.com/ambuc/plotz_rs/issues/3): use quadtrees here to make this MUCH faster please!!!!
-
-        match (&incoming, &existing) {
-            // points can/should be occluded, not handled yet.
-            (Obj2::Point(_), _) => {
-                unimplemented!("no support for points yet")
-            }
-            // chars are points, see above.
-            (Obj2::Text(_), _) => {
-                unimplemented!("no support for chars yet")
-            }
-            // groups are not handled yet.
-            (Obj2::Group(_), _) | (_, Obj2::Group(_)) => {
-                unimplemented!("no support for groups yet")
-            }
-            // curvearcs are not handled yet.
-            (Obj2::CurveArc(_), _) | (_, Obj2::CurveArc(_)) => {
-                unimplemented!("no support for curvearcs yet")
+        // neighboring faces of a mesh only touch, and cropping one by the
+        // other is a slow way to leave it as it was.
+        if let (Some(a), Some(b)) = (outline(incoming), outline(existing)) {
+            if apart(a, b)? {
+                return Ok(vec![incoming.clone()]);
             }
+        }
 
-            (Obj2::Multiline(_), _) => {
-                unimplemented!("no support for multilines yet")
-            }
+        match existing {
+            Obj2::Polygon(b) => incoming.crop_excluding(b).context(format!(
+                "crop excluding: \na\n\t{:?}\n\nb\n\t{:?}",
+                incoming, b
+            )),
 
-            (Obj2::Polygon(a), Obj2::Polygon(b)) => Ok(a
-                .crop_excluding(b)
-                .context(format!("crop excluding: \na\n\t{:?}\n\nb\n\t{:?}", a, b))?
-                .into_iter()
-                .map(Obj2::from)
-                .collect()),
-            (Obj2::Segment(_sg), Obj2::Polygon(_pg)) => {
-                unimplemented!("no support for pg x sg yet");
+            // whatever is outside of the outer ring, or within a cavity, still
+            // shows.
+            Obj2::PolygonWithCavities(b) => {
+                let mut r = incoming.crop_excluding(&b.outer)?;
+                for cavity in &b.inner {
+                    r.extend(incoming.crop_to(cavity)?);
+                }
+                Ok(r)
             }
 
-            (Obj2::PolygonWithCavities(_), _) | (_, Obj2::PolygonWithCavities(_)) => {
-                unimplemented!("TODO(jbuckland): implement cropping.")
-            }
+            // hiding behind a group is hiding behind each of its members.
+            Obj2::Group(g) => g.iter_objects().try_fold(
+                vec![incoming.clone()],
+                |acc, (member, _)| -> Result<Vec<Obj2>> {
+                    acc.iter()
+                        .map(|a| Occluder::hide_a_behind_b(a, member))
+                        .flatten_ok()
+                        .collect()
+                },
+            ),
 
-            //
-            // you can't hide something behind a segment or a point or a char. don't be daft.
-            (incoming, Obj2::Multiline(_) | Obj2::Segment(_) | Obj2::Point(_) | Obj2::Text(_)) => {
-                Ok(vec![(**incoming).clone()])
-            }
+            // you can't hide something behind a segment or a point or a char
+            // or an arc. don't be daft.
+            Obj2::Point(_)
+            | Obj2::Segment(_)
+            | Obj2::Multiline(_)
+            | Obj2::Text(_)
+            | Obj2::CurveArc(_) => Ok(vec![incoming.clone()]),
         }
     }
 
     // Incorporates an object
     #[instrument(skip(self, incoming2))]
     pub fn add(&mut self, incoming2: (Obj2, Style)) -> Result<()> {
+        self.insert(incoming2, false)
+    }
+
+    // Incorporates a mask, which hides whatever is added behind it but isn't
+    // drawn itself.
+    #[instrument(skip(self, incoming))]
+    pub fn add_mask(&mut self, incoming: Obj2) -> Result<()> {
+        self.insert((incoming, Style::default()), true)
+    }
+
+    fn insert(&mut self, incoming2: (Obj2, Style), mask: bool) -> Result<()> {
+        let Some(incoming) = without_slivers(incoming2.0)? else {
+            return Ok(());
+        };
+        let incoming2 = (incoming, incoming2.1);
+
+        self.masks.resize(self.objects.len(), false);
+        if self.index.len() != self.objects.len() {
+            self.index = QuadTree::from_items(
+                self.objects
+                    .iter()
+                    .map(|(o, _)| Ok((o.bounds()?, ())))
+                    .collect::<Result<Vec<_>>>()?,
+            );
+        }
+
+        // only objects whose boxes intersect the incoming object's box can
+        // hide any of it. every fragment of it stays within that box, too.
+        let incoming_bounds = incoming2.0.bounds()?;
+        let candidates = self.index.query(&incoming_bounds);
+
         let mut incoming_os: Vec<(Obj2, Style)> = vec![incoming2.clone()];
-        for (existing_o, _) in &self.objects {
+        for (existing_o, _) in candidates.into_iter().map(|i| &self.objects[i]) {
             incoming_os = incoming_os
                 .iter()
                 .map(|(incoming_obj, _)| {
@@ -86,9 +139,15 @@ impl Occluder {
                 .collect::<Result<Vec<_>>>()
                 .context("collecting objects")?
                 .into_iter()
-                .map(|obj| (obj, incoming2.1))
-                .collect::<Vec<_>>();
+                .map(|obj| Ok(without_slivers(obj)?.map(|obj| (obj, incoming2.1))))
+                .flatten_ok()
+                .collect::<Result<Vec<_>>>()
+                .context("dropping slivers")?;
+        }
+        for (o, _) in &incoming_os {
+            self.index.insert(o.bounds()?, ());
         }
+        self.masks.extend(incoming_os.iter().map(|_| mask));
         self.objects.extend(incoming_os);
         Ok(())
     }
@@ -96,12 +155,16 @@ impl Occluder {
     // Exports the occluded 2d objects.
     #[instrument(skip(self))]
     pub fn export(mut self) -> Result<Vec<(Obj2, Style)>> {
+        self.masks.resize(self.objects.len(), false);
         // we store them front-to-back, but we want to render them to svg back-to-front.
         self.objects.reverse();
+        self.masks.reverse();
         let x: Vec<_> = self
             .objects
             .into_iter()
-            .map(export_obj)
+            .zip(self.masks)
+            .filter(|(_, mask)| !mask)
+            .map(|(sobj, _)| export_obj(sobj))
             .flatten_ok()
             .collect::<Result<Vec<_>>>()?
             .into_iter()
@@ -110,6 +173,75 @@ impl Occluder {
     }
 }
 
+// The ring around |obj|, if it is a polygon, with cavities or without.
+fn outline(obj: &Obj2) -> Option<&Polygon> {
+    match obj {
+        Obj2::Polygon(pg) => Some(pg),
+        Obj2::PolygonWithCavities(pwc) => Some(&pwc.outer),
+        _ => None,
+    }
+}
+
+// True if |a| and |b| are both convex, and some edge of either has them on
+// opposite sides of it, so that they at most touch.
+fn apart(a: &Polygon, b: &Polygon) -> Result<bool> {
+    if !a.is_convex() || !b.is_convex() {
+        return Ok(false);
+    }
+    // points on a shared edge don't project onto its normal quite equally.
+    let slack = Tolerance::for_bounds(&a.bounds()?.join(&b.bounds()?)).abs;
+    let extent = |pg: &Polygon, axis: &Point| {
+        pg.pts.iter().fold((f64::MAX, f64::MIN), |(lo, hi), pt| {
+            (lo.min(pt.dot(axis)), hi.max(pt.dot(axis)))
+        })
+    };
+    Ok(a.to_segments()
+        .iter()
+        .chain(b.to_segments().iter())
+        .any(|sg| {
+            let d = sg.f - sg.i;
+            let axis = Point(-d.y, d.x);
+            let ((a_lo, a_hi), (b_lo, b_hi)) = (extent(a, &axis), extent(b, &axis));
+            let slack = slack * d.norm();
+            a_hi <= b_lo + slack || b_hi <= a_lo + slack
+        }))
+}
+
+// Slivers (as are left where faces are split along each other's planes, or
+// cropped along each other's edges) are too thin to hide or show anything.
+fn is_sliver(pg: &Polygon) -> Result<bool> {
+    let b = pg.bounds()?;
+    let (w, h) = (b.x_max - b.x_min, b.y_max - b.y_min);
+    Ok(pg.area() <= SLIVER * (w * w + h * h))
+}
+
+// |obj|, without any sliver cavities, or None if it is a sliver itself.
+fn without_slivers(obj: Obj2) -> Result<Option<Obj2>> {
+    match obj {
+        Obj2::Polygon(pg) if is_sliver(&pg)? => Ok(None),
+        Obj2::PolygonWithCavities(pwc) => {
+            if is_sliver(&pwc.outer)? {
+                return Ok(None);
+            }
+            let mut inner = vec![];
+            for cavity in pwc.inner {
+                if !is_sliver(&cavity)? {
+                    inner.push(cavity);
+                }
+            }
+            Ok(Some(if inner.is_empty() {
+                Obj2::Polygon(pwc.outer)
+            } else {
+                Obj2::PolygonWithCavities(PolygonWithCavities {
+                    outer: pwc.outer,
+                    inner,
+                })
+            }))
+        }
+        obj => Ok(Some(obj)),
+    }
+}
+
 #[instrument]
 fn export_obj((sobj, style): (Obj2, Style)) -> Result<Vec<(Obj2, Style)>> {
     match style {
@@ -117,20 +249,168 @@ fn export_obj((sobj, style): (Obj2, Style)) -> Result<Vec<(Obj2, Style)>> {
         style @ Style {
             shading: Some(shade_config),
             ..
-        } => match sobj {
-            Obj2::Polygon(pg) => {
-                if shade_config.along_face {
-                    // TODO(https://github.com/ambuc/plotz_rs/issues/2): apply shade config here.
-                    Ok(vec![])
-                } else {
-                    Ok(shade_polygon(&shade_config, &pg)
-                        .unwrap()
-                        .into_iter()
-                        .map(|sg| (sg.into(), style))
-                        .collect::<Vec<_>>())
-                }
+        } => {
+            if shade_config.along_face {
+                // the face's hatching was added alongside it; see |Scene::project|.
+                Ok(vec![])
+            } else {
+                Ok(shade(&shade_config, &sobj)?
+                    .into_iter()
+                    .map(|sg| (sg.into(), style))
+                    .collect::<Vec<_>>())
             }
-            _ => Err(anyhow!("can't shade not a polygon")),
-        },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use plotz_geometry::{
+        group::Group,
+        shapes::{curve::CurveArc, point::Point, polygon::Rect, segment::Segment, text::Text},
+    };
+
+    fn square() -> Obj2 {
+        Obj2::Polygon(Rect((0, 0), (2, 2)).unwrap())
+    }
+
+    #[test]
+    fn test_hide_behind_polygon() -> Result<()> {
+        let hidden = |a: Obj2| Occluder::hide_a_behind_b(&a, &square());
+
+        assert!(hidden(Obj2::Point(Point(1, 1)))?.is_empty());
+        assert_eq!(hidden(Obj2::Point(Point(3, 1)))?.len(), 1);
+        let text = Obj2::Text(Text {
+            pt: Point(1, 1),
+            inner: "x".to_string(),
+            font_size: 1.0,
+        });
+        assert!(hidden(text)?.is_empty());
+        assert!(hidden(Obj2::CurveArc(CurveArc(Point(1, 1), 0.0..=1.0, 0.5)))?.is_empty());
+        assert_eq!(
+            hidden(Obj2::Segment(Segment((-1, 1), (1, 1))))?,
+            vec![Obj2::Segment(Segment((-1, 1), (0, 1)))]
+        );
+        let group = Obj2::Group(Group::new([
+            (Obj2::Point(Point(1, 1)), Style::default()),
+            (Obj2::Point(Point(3, 1)), Style::default()),
+        ]));
+        match &hidden(group)?[..] {
+            [Obj2::Group(g)] => assert_eq!(g.iter_objects().count(), 1),
+            x => panic!("{:?}", x),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_hide_polygon_behind_polygon() -> Result<()> {
+        let hidden = |a: Obj2, b: Obj2| Occluder::hide_a_behind_b(&a, &b);
+
+        // wholly behind the same face.
+        assert!(hidden(square(), square())?.is_empty());
+
+        // behind a smaller face, which cuts a hole in it.
+        let back = || Obj2::Polygon(Rect((0, 0), (4, 4)).unwrap());
+        match &hidden(back(), Obj2::Polygon(Rect((1, 1), (1, 1))?))?[..] {
+            [Obj2::PolygonWithCavities(pwc)] => assert_eq!(pwc.inner.len(), 1),
+            x => panic!("{:?}", x),
+        }
+
+        // or a notch, if it touches the edge.
+        match &hidden(back(), Obj2::Polygon(Rect((0, 1), (1, 1))?))?[..] {
+            [Obj2::Polygon(pg)] => assert_eq!(pg.area(), 15.0),
+            x => panic!("{:?}", x),
+        }
+
+        // but still a hole, if it only touches a corner.
+        let corner = Polygon([(0, 0), (2, 1), (1, 2)])?;
+        match &hidden(back(), Obj2::Polygon(corner))?[..] {
+            [Obj2::PolygonWithCavities(pwc)] => assert_eq!(pwc.inner.len(), 1),
+            x => panic!("{:?}", x),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_hide_polygon_behind_several_polygons() -> Result<()> {
+        // two faces in front, one overlapping the hole the other leaves.
+        let mut occluder = Occluder::default();
+        occluder.add((Obj2::Polygon(Rect((1, 1), (2, 2))?), Style::default()))?;
+        occluder.add((Obj2::Polygon(Rect((2, 2), (2, 2))?), Style::default()))?;
+        occluder.add((Obj2::Polygon(Rect((0, 0), (10, 10))?), Style::default()))?;
+        match &occluder.export()?[..] {
+            [(Obj2::PolygonWithCavities(pwc), _), _, _] => {
+                let holes: f64 = pwc.inner.iter().map(|pg| pg.area()).sum();
+                assert_eq!(pwc.outer.area() - holes, 100.0 - 7.0);
+            }
+            x => panic!("{:?}", x),
+        }
+        Ok(())
+    }
+
+    #[test]
+    fn test_without_slivers() -> Result<()> {
+        let pt = Point(-0.7, 3.5);
+        let pwc = Obj2::PolygonWithCavities(PolygonWithCavities {
+            outer: Rect((-2, 2), (4, 4))?,
+            inner: vec![Polygon([pt, pt, pt])?, Rect((-1, 3), (1, 1))?],
+        });
+        match without_slivers(pwc)? {
+            Some(Obj2::PolygonWithCavities(pwc)) => assert_eq!(pwc.inner.len(), 1),
+            x => panic!("{:?}", x),
+        }
+        let flat = Obj2::Polygon(Polygon([(0, 0), (1, 0), (2, 0)])?);
+        assert!(without_slivers(flat)?.is_none());
+        Ok(())
+    }
+
+    #[test]
+    fn test_apart() -> Result<()> {
+        // two halves of a square only touch.
+        let a = Polygon([(0, 0), (1, 0), (1, 1)])?;
+        let b = Polygon([(0, 0), (1, 1), (0, 1)])?;
+        assert!(apart(&a, &b)?);
+        assert!(!apart(&a, &Rect((0.5, 0), (1, 1))?)?);
+        // an L's notch can't be told apart from it this way.
+        let l = Polygon([(0, 0), (2, 0), (2, 1), (1, 1), (1, 2), (0, 2)])?;
+        assert!(!apart(&l, &Rect((1.5, 1.5), (1, 1))?)?);
+        Ok(())
+    }
+
+    #[test]
+    fn test_masks_hide_but_are_not_drawn() -> Result<()> {
+        let mut occluder = Occluder::default();
+        occluder.add_mask(square())?;
+        occluder.add((Obj2::Segment(Segment((1, 1), (3, 1))), Style::default()))?;
+        assert_eq!(
+            occluder.export()?,
+            vec![(Obj2::Segment(Segment((2, 1), (3, 1))), Style::default())]
+        );
+        Ok(())
+    }
+
+    #[test]
+    fn test_hide_behind_polygon_with_cavities() -> Result<()> {
+        // a frame with a window in it.
+        let frame = Obj2::PolygonWithCavities(PolygonWithCavities(
+            Rect((0, 0), (4, 4))?,
+            [Rect((1, 1), (2, 2))?],
+        )?);
+        let sg = Obj2::Segment(Segment((-1, 2), (5, 2)));
+        let mut lengths: Vec<f64> = Occluder::hide_a_behind_b(&sg, &frame)?
+            .into_iter()
+            .map(|o| match o {
+                Obj2::Segment(sg) => sg.length(),
+                x => panic!("{:?}", x),
+            })
+            .collect();
+        lengths.sort_by(f64::total_cmp);
+        assert_eq!(lengths, vec![1.0, 1.0, 2.0]);
+
+        // something behind a segment is never hidden.
+        let pt = Obj2::Point(Point(0, 2));
+        assert_eq!(Occluder::hide_a_behind_b(&pt, &sg)?, vec![pt]);
+        Ok(())
     }
 }
@@ -6,6 +6,7 @@ use crate::{
     shapes::{point3::Point3, polygon3::Polygon3},
 };
 
+// a parallelogram, wound so that its front faces along |d1| cross |d2|.
 fn make_planar_face(origin: Point3, d1: Point3, d2: Point3) -> Polygon3 {
     Polygon3([origin, origin + d1, origin + d1 + d2, origin + d2, origin])
 }
@@ -27,12 +28,13 @@ where
     let dy: Point3 = Point3(0.0, dy, 0.0);
     let dz: Point3 = Point3(0.0, 0.0, dz);
 
+    // each face's front faces outwards.
     Group3::<()>::new([
-        (Obj3::Polygon3(make_planar_face(root, dx, dy)), ()),
+        (Obj3::Polygon3(make_planar_face(root, dy, dx)), ()),
         (Obj3::Polygon3(make_planar_face(root, dx, dz)), ()),
-        (Obj3::Polygon3(make_planar_face(root, dy, dz)), ()),
+        (Obj3::Polygon3(make_planar_face(root, dz, dy)), ()),
         (Obj3::Polygon3(make_planar_face(root + dx, dy, dz)), ()),
-        (Obj3::Polygon3(make_planar_face(root + dy, dx, dz)), ()),
+        (Obj3::Polygon3(make_planar_face(root + dy, dz, dx)), ()),
         (Obj3::Polygon3(make_planar_face(root + dz, dx, dy)), ()),
     ])
 }
@@ -1,6 +1,12 @@
 pub mod cube3d;
 pub mod cuboid3d;
+pub mod plane3d;
 pub mod point3;
 pub mod polygon3;
+pub mod prism3d;
+pub mod pyramid3d;
 pub mod ray3;
+pub mod revolution3d;
 pub mod segment3;
+pub mod sphere3d;
+pub mod torus3d;
@@ -0,0 +1,57 @@
+//! A flat grid of faces in 3d.
+
+use crate::{
+    group3::Group3,
+    obj3::Obj3,
+    shapes::{point3::Point3, polygon3::Polygon3},
+};
+use anyhow::{anyhow, Result};
+
+// make a parallelogram spanning |d1| and |d2| from |origin|, divided into a
+// grid of |n1| by |n2| faces.
+#[allow(non_snake_case)]
+pub fn PlaneGrid(
+    origin: impl Into<Point3>,
+    (d1, d2): (Point3, Point3),
+    (n1, n2): (usize, usize),
+) -> Result<Group3<()>> {
+    if n1 == 0 || n2 == 0 {
+        return Err(anyhow!("need at least one face each way"));
+    }
+    let origin: Point3 = origin.into();
+    let at =
+        |i: usize, j: usize| origin + d1 * (i as f64 / n1 as f64) + d2 * (j as f64 / n2 as f64);
+    Ok(Group3::new((0..n1).flat_map(|i| {
+        (0..n2).map(move |j| {
+            (
+                Obj3::Polygon3(Polygon3([
+                    at(i, j),
+                    at(i + 1, j),
+                    at(i + 1, j + 1),
+                    at(i, j + 1),
+                    at(i, j),
+                ])),
+                (),
+            )
+        })
+    })))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::bounded3::Bounded3;
+
+    #[test]
+    fn test_plane_grid() -> Result<()> {
+        let grid = PlaneGrid((0, 0, 1), (Point3(2, 0, 0), Point3(0, 3, 0)), (4, 6))?;
+        assert_eq!(grid.iter_objects().count(), 24);
+        let bounds = grid.bounds3()?;
+        assert_eq!(
+            (bounds.x_max, bounds.y_max, bounds.z_min, bounds.z_max),
+            (2.0, 3.0, 1.0, 1.0)
+        );
+        assert!(PlaneGrid((0, 0, 0), (Point3(1, 0, 0), Point3(0, 1, 0)), (0, 1)).is_err());
+        Ok(())
+    }
+}
@@ -171,6 +171,30 @@ impl Point3 {
     pub fn dist(&self, other: &Point3) -> f64 {
         Segment3(*self, *other).abs()
     }
+
+    // https://en.wikipedia.org/wiki/Cross_product
+    pub fn cross(&self, other: &Point3) -> Point3 {
+        Point3(
+            (self.y * other.z) - (self.z * other.y),
+            (self.z * other.x) - (self.x * other.z),
+            (self.x * other.y) - (self.y * other.x),
+        )
+    }
+
+    /// The length of this point, as a vector from the origin.
+    pub fn norm(&self) -> f64 {
+        self.dot(self).sqrt()
+    }
+
+    /// This point scaled to unit length, as a vector from the origin. Errors
+    /// on the zero vector, which has no direction.
+    pub fn normalize(&self) -> Result<Point3> {
+        let n = self.norm();
+        if n == 0.0 {
+            return Err(anyhow!("can't normalize the zero vector"));
+        }
+        Ok(*self / n)
+    }
 }
 
 #[allow(non_snake_case)]
@@ -275,6 +299,22 @@ mod test {
         Ok(())
     }
 
+    #[test_case(Point3(1, 0, 0), Point3(0, 1, 0), Point3(0, 0, 1))]
+    #[test_case(Point3(0, 1, 0), Point3(0, 0, 1), Point3(1, 0, 0))]
+    #[test_case(Point3(0, 0, 1), Point3(1, 0, 0), Point3(0, 1, 0))]
+    #[test_case(Point3(0,1,0), Point3(1,0,0), Point3(0,0,-1))]
+    #[test_case(Point3(2, 0, 0), Point3(2, 0, 0), Point3(0, 0, 0))]
+    fn test_cross(a: Point3, b: Point3, expected: Point3) {
+        assert_eq!(a.cross(&b), expected);
+    }
+
+    #[test]
+    fn test_normalize() -> Result<()> {
+        assert_eq!(Point3(3, 0, 4).normalize()?, Point3(0.6, 0, 0.8));
+        assert!(Origin().normalize().is_err());
+        Ok(())
+    }
+
     #[test_case(Point3(0,1,0), 0.0*FRAC_PI_2, Point3(0,1,0))]
     #[test_case(Point3(0,1,0), 1.0*FRAC_PI_2, Point3(0,0,1))]
     #[test_case(Point3(0,1,0), 2.0*FRAC_PI_2, Point3(0,-1,0))]
@@ -3,11 +3,15 @@
 use crate::{
     bounded3::{streaming_bbox, Bounded3, Bounds3},
     obj3::ObjType3d,
-    shapes::point3::Point3,
+    shapes::{point3::Point3, segment3::Segment3},
     Object, Rotatable,
 };
 use anyhow::Result;
 use float_ord::FloatOrd;
+use plotz_geometry::{
+    shading::{shade_config::ShadeConfig, shade_polygon},
+    shapes::{point::Point, polygon::Polygon},
+};
 use std::{fmt::Debug, ops::*};
 
 use super::ray3::Ray3;
@@ -35,6 +39,16 @@ impl Polygon3 {
         Point3(sum_x, sum_y, sum_z) / num
     }
 
+    // The average of the ring's points, counting the point which closes it
+    // only once.
+    pub fn centroid(&self) -> Point3 {
+        let ring = match self.pts.split_last() {
+            Some((last, rest)) if !rest.is_empty() && *last == rest[0] => rest,
+            _ => &self.pts[..],
+        };
+        ring.iter().fold(Point3(0, 0, 0), |acc, pt| acc + *pt) / ring.len() as f64
+    }
+
     // The center of the object, projected along the view vector.
     pub fn dist_along(&self, view_vector: &Point3) -> f64 {
         view_vector.dot(&self.average_pt())
@@ -57,6 +71,66 @@ impl Polygon3 {
             .unwrap()
             .0
     }
+
+    // The unit normal of the polygon, by Newell's method. Errs if the polygon
+    // has no area. It points out of the polygon's front, i.e. the side from
+    // which its points run counterclockwise.
+    pub fn normal(&self) -> Result<Point3> {
+        newell_normal(&self.pts).normalize()
+    }
+
+    // Hatches the polygon in its own plane, rather than in the plane it is
+    // projected onto. The hatching is laid out in a frame on the face whose
+    // x axis is level (or along x, for a level face) and whose y axis is
+    // square to it; |config|'s angle and anchor are in that frame. The frame
+    // doesn't depend on which way the face is wound, so faces in the same
+    // plane share the same lines.
+    pub fn hatch(&self, config: &ShadeConfig) -> Result<Vec<Segment3>> {
+        // the normal, flipped if need be so that its largest component is
+        // positive.
+        let normal = self.normal()?;
+        let (x, y, z) = (normal.x.abs(), normal.y.abs(), normal.z.abs());
+        let largest = if x >= y && x >= z {
+            normal.x
+        } else if y >= z {
+            normal.y
+        } else {
+            normal.z
+        };
+        let normal = if largest < 0.0 { normal * -1.0 } else { normal };
+        let level = Point3(0, 0, 1).cross(&normal);
+        let u = if level.norm() > 1e-9 {
+            level.normalize()?
+        } else {
+            Point3(1, 0, 0)
+        };
+        let v = normal.cross(&u);
+        let origin = normal * normal.dot(&self.pts[0]);
+
+        let flat = Polygon(self.pts.iter().map(|pt| {
+            let d = *pt - origin;
+            Point(d.dot(&u), d.dot(&v))
+        }))?;
+        let lift = |pt: Point| origin + u * pt.x + v * pt.y;
+        Ok(shade_polygon(config, &flat)?
+            .into_iter()
+            .map(|sg| Segment3(lift(sg.i), lift(sg.f)))
+            .collect())
+    }
+}
+
+// The (unnormalized) normal of a ring of points, by Newell's method. Its
+// length is twice the area of the ring.
+pub(crate) fn newell_normal(pts: &[Point3]) -> Point3 {
+    let mut normal = Point3(0, 0, 0);
+    for (a, b) in pts.iter().zip(pts.iter().cycle().skip(1)) {
+        normal += Point3(
+            (a.y - b.y) * (a.z + b.z),
+            (a.z - b.z) * (a.x + b.x),
+            (a.x - b.x) * (a.y + b.y),
+        );
+    }
+    normal
 }
 
 // Constructor for multilines, which are by definition open. The first and last
@@ -107,3 +181,59 @@ impl Object for Polygon3 {
         Box::new(self.pts.iter_mut())
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_hatch_lies_on_face() -> Result<()> {
+        let config = ShadeConfig::builder().gap(0.5).angle(0.0).build();
+
+        // a wall, facing along x: its hatching is level, and stays on it.
+        let wall = Polygon3([
+            Point3(0, 0, 0),
+            Point3(0, 2, 0),
+            Point3(0, 2, 2),
+            Point3(0, 0, 2),
+            Point3(0, 0, 0),
+        ]);
+        assert_float_eq!(wall.normal()?.x.abs(), 1.0, abs <= 1e-9);
+        let hatch = wall.hatch(&config)?;
+        assert!(!hatch.is_empty());
+        for sg in &hatch {
+            assert_float_eq!(sg.i.x, 0.0, abs <= 1e-9);
+            assert_float_eq!(sg.f.x, 0.0, abs <= 1e-9);
+            assert_float_eq!(sg.i.z, sg.f.z, abs <= 1e-9);
+        }
+
+        // a floor: its hatching runs along x.
+        let floor = Polygon3([
+            Point3(0, 0, 1),
+            Point3(2, 0, 1),
+            Point3(2, 2, 1),
+            Point3(0, 2, 1),
+            Point3(0, 0, 1),
+        ]);
+        for sg in floor.hatch(&config)? {
+            assert_float_eq!(sg.i.z, 1.0, abs <= 1e-9);
+            assert_float_eq!(sg.i.y, sg.f.y, abs <= 1e-9);
+        }
+
+        // the same face, wound the other way, has the same hatching.
+        let config = ShadeConfig::builder().gap(0.5).angle(0.5).build();
+        let reversed = Polygon3(floor.pts.iter().rev().copied());
+        let dir = |sg: &Segment3| sg.f - sg.i;
+        let (a, b) = (floor.hatch(&config)?, reversed.hatch(&config)?);
+        assert_eq!(a.len(), b.len());
+        for (sa, sb) in a.iter().zip(&b) {
+            assert_float_eq!(dir(sa).cross(&dir(sb)).norm(), 0.0, abs <= 1e-9);
+        }
+
+        // a face with no area has no plane.
+        let line = Polygon3([Point3(0, 0, 0), Point3(1, 1, 1), Point3(0, 0, 0)]);
+        assert!(line.hatch(&config).is_err());
+        Ok(())
+    }
+}
@@ -0,0 +1,101 @@
+//! A prism in 3d.
+
+use crate::{
+    group3::Group3,
+    obj3::Obj3,
+    shapes::{point3::Point3, polygon3::Polygon3},
+};
+use anyhow::{anyhow, Result};
+use plotz_geometry::shapes::{point::Point, polygon::Polygon};
+use std::f64::consts::TAU;
+
+// The points of |base|, counterclockwise, laid flat in the plane of |root|.
+pub(crate) fn lay_flat(root: Point3, base: &Polygon) -> Vec<Point3> {
+    let mut pts: Vec<Point3> = base
+        .pts
+        .iter()
+        .map(|p| root + Point3(p.x, p.y, 0.0))
+        .collect();
+    if base.signed_area() < 0.0 {
+        pts.reverse();
+    }
+    pts
+}
+
+// The points of a regular polygon of |segments| sides about the origin.
+pub(crate) fn regular_polygon(radius: f64, segments: usize) -> Result<Polygon> {
+    if segments < 3 {
+        return Err(anyhow!("need at least three segments"));
+    }
+    Polygon((0..segments).map(|k| {
+        let phi = TAU * k as f64 / segments as f64;
+        Point(radius * phi.cos(), radius * phi.sin())
+    }))
+}
+
+// make a prism: |base|, laid flat with its origin at |root|, swept straight
+// up by |height|. one face for each edge of the base, and one for each end.
+#[allow(non_snake_case)]
+pub fn Prism(root: impl Into<Point3>, base: &Polygon, height: f64) -> Group3<()> {
+    let bottom = lay_flat(root.into(), base);
+    let top: Vec<Point3> = bottom
+        .iter()
+        .map(|p| *p + Point3(0.0, 0.0, height))
+        .collect();
+    let n = bottom.len();
+
+    let mut faces: Vec<Polygon3> = (0..n)
+        .map(|i| {
+            let j = (i + 1) % n;
+            Polygon3([bottom[i], bottom[j], top[j], top[i], bottom[i]])
+        })
+        .collect();
+    faces.push(Polygon3(bottom.iter().rev().chain(bottom.last()).copied()));
+    faces.push(Polygon3(top.iter().chain(top.first()).copied()));
+    Group3::new(faces.into_iter().map(|f| (Obj3::Polygon3(f), ())))
+}
+
+// make a cylinder: standing on a circle about |ctr|, of |segments| sides.
+#[allow(non_snake_case)]
+pub fn Cylinder(
+    ctr: impl Into<Point3>,
+    radius: f64,
+    height: f64,
+    segments: usize,
+) -> Result<Group3<()>> {
+    Ok(Prism(ctr, &regular_polygon(radius, segments)?, height))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::shapes::polygon3::newell_normal;
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_prism() -> Result<()> {
+        // a triangular prism, whose faces all face out.
+        let base = Polygon([(0, 0), (0, 1), (1, 0)])?;
+        let prism = Prism((0, 0, 0), &base, 2.0);
+        let ctr = Point3(1.0 / 3.0, 1.0 / 3.0, 1.0);
+        let mut area = 0.0;
+        for (o, _) in prism.iter_objects() {
+            let Obj3::Polygon3(pg) = o else {
+                panic!("{:?}", o);
+            };
+            let normal = newell_normal(&pg.pts);
+            assert!(normal.dot(&(pg.pts[0] - ctr)) > 0.0);
+            area += normal.norm() / 2.0;
+        }
+        assert_float_eq!(
+            area,
+            0.5 + 0.5 + 2.0 + 2.0 + 2.0 * 2.0_f64.sqrt(),
+            abs <= 1e-9
+        );
+
+        let cylinder = Cylinder((0, 0, 0), 1.0, 1.0, 12)?;
+        assert_eq!(cylinder.iter_objects().count(), 14);
+        assert!(Cylinder((0, 0, 0), 1.0, 1.0, 2).is_err());
+        Ok(())
+    }
+}
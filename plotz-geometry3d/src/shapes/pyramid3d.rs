@@ -0,0 +1,69 @@
+//! A pyramid in 3d.
+
+use crate::{
+    group3::Group3,
+    obj3::Obj3,
+    shapes::{
+        point3::Point3,
+        polygon3::Polygon3,
+        prism3d::{lay_flat, regular_polygon},
+    },
+};
+use anyhow::Result;
+use plotz_geometry::shapes::polygon::Polygon;
+
+// make a pyramid: |base|, laid flat with its origin at |root|, and a triangle
+// from each of its edges up to |apex| (relative to |root|).
+#[allow(non_snake_case)]
+pub fn Pyramid(root: impl Into<Point3>, base: &Polygon, apex: impl Into<Point3>) -> Group3<()> {
+    let root: Point3 = root.into();
+    let apex: Point3 = root + apex.into();
+    let bottom = lay_flat(root, base);
+    let n = bottom.len();
+
+    let mut faces: Vec<Polygon3> = (0..n)
+        .map(|i| {
+            let j = (i + 1) % n;
+            Polygon3([bottom[i], bottom[j], apex, bottom[i]])
+        })
+        .collect();
+    faces.push(Polygon3(bottom.iter().rev().chain(bottom.last()).copied()));
+    Group3::new(faces.into_iter().map(|f| (Obj3::Polygon3(f), ())))
+}
+
+// make a cone: standing on a circle about |ctr|, of |segments| sides, and
+// coming to a point |height| above it.
+#[allow(non_snake_case)]
+pub fn Cone(
+    ctr: impl Into<Point3>,
+    radius: f64,
+    height: f64,
+    segments: usize,
+) -> Result<Group3<()>> {
+    Ok(Pyramid(
+        ctr,
+        &regular_polygon(radius, segments)?,
+        Point3(0.0, 0.0, height),
+    ))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_pyramid() -> Result<()> {
+        let base = Polygon([(0, 0), (2, 0), (2, 2), (0, 2)])?;
+        let pyramid = Pyramid((1, 1, 1), &base, (1, 1, 3));
+        assert_eq!(pyramid.iter_objects().count(), 5);
+        let bounds = crate::bounded3::Bounded3::bounds3(&pyramid)?;
+        assert_eq!(
+            (bounds.x_min, bounds.x_max, bounds.z_min, bounds.z_max),
+            (1.0, 3.0, 1.0, 4.0)
+        );
+
+        let cone = Cone((0, 0, 0), 1.0, 2.0, 16)?;
+        assert_eq!(cone.iter_objects().count(), 17);
+        Ok(())
+    }
+}
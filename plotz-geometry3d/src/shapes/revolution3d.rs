@@ -0,0 +1,100 @@
+//! A surface of revolution in 3d.
+
+use crate::{
+    group3::Group3,
+    obj3::Obj3,
+    shapes::{point3::Point3, polygon3::Polygon3},
+};
+use anyhow::{anyhow, Result};
+use plotz_geometry::shapes::{multiline::Multiline, point::Point};
+use std::f64::consts::TAU;
+
+// A face from a ring of points, without any point repeated in a row (as where
+// the profile touches the axis); or none, if fewer than three points remain.
+fn face(pts: impl IntoIterator<Item = Point3>) -> Option<Polygon3> {
+    let mut pts: Vec<Point3> = pts.into_iter().collect();
+    pts.dedup();
+    if pts.len() > 1 && pts[0] == pts[pts.len() - 1] {
+        pts.pop();
+    }
+    if pts.len() < 3 {
+        return None;
+    }
+    pts.push(pts[0]);
+    Some(Polygon3(pts))
+}
+
+// Sweeps |profile| (of points (r, z), r from the axis and z along it) around
+// the vertical axis through |ctr|, in |segments| steps. If |closed|, the last
+// point of the profile joins back up to the first.
+pub(crate) fn revolve(
+    ctr: Point3,
+    profile: &[Point],
+    closed: bool,
+    segments: usize,
+) -> Result<Group3<()>> {
+    if segments < 3 {
+        return Err(anyhow!("need at least three segments"));
+    }
+    let at = |p: &Point, k: usize| {
+        let phi = TAU * (k % segments) as f64 / segments as f64;
+        ctr + Point3(p.x * phi.cos(), p.x * phi.sin(), p.y)
+    };
+    let edges = profile.iter().zip(profile.iter().skip(1)).chain(
+        closed
+            .then(|| profile.last().zip(profile.first()))
+            .flatten(),
+    );
+    let mut faces = vec![];
+    for (a, b) in edges {
+        for k in 0..segments {
+            faces.extend(face([at(a, k), at(a, k + 1), at(b, k + 1), at(b, k)]));
+        }
+    }
+    Ok(Group3::new(
+        faces.into_iter().map(|f| (Obj3::Polygon3(f), ())),
+    ))
+}
+
+// make a surface of revolution by sweeping |profile| around the vertical axis
+// through |ctr|, in |segments| steps. each point of the profile is (r, z): r
+// from the axis (which should be nonnegative), and z up it. where the profile
+// touches the axis, faces close up into triangles.
+#[allow(non_snake_case)]
+pub fn Revolution(
+    ctr: impl Into<Point3>,
+    profile: &Multiline,
+    segments: usize,
+) -> Result<Group3<()>> {
+    revolve(ctr.into(), &profile.pts, false, segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_revolution() -> Result<()> {
+        // a cone, point up, with a floor.
+        let profile = Multiline([(0, 0), (1, 0), (0, 1)]);
+        let cone: Vec<Polygon3> = Revolution((0, 0, 0), &profile, 8)?
+            .into_iter_objects()
+            .map(|(o, _)| match o {
+                Obj3::Polygon3(pg) => pg,
+                x => panic!("{:?}", x),
+            })
+            .collect();
+        // every face closes up into a triangle at the axis.
+        assert_eq!(cone.len(), 16);
+        for pg in &cone {
+            assert_eq!(pg.pts.len(), 4);
+            for pt in &pg.pts {
+                // either on the axis, or on the slope.
+                let r = pt.x.hypot(pt.y);
+                assert!(r < 1e-9 || (r - (1.0 - pt.z)).abs() < 1e-9);
+            }
+        }
+        assert!(Revolution((0, 0, 0), &profile, 2).is_err());
+        Ok(())
+    }
+}
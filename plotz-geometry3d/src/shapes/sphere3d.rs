@@ -0,0 +1,149 @@
+//! A sphere in 3d.
+
+use crate::{
+    group3::Group3,
+    obj3::Obj3,
+    shapes::{point3::Point3, polygon3::Polygon3, revolution3d::revolve},
+};
+use anyhow::{anyhow, Result};
+use plotz_geometry::shapes::point::Point;
+use std::{collections::HashMap, f64::consts::PI};
+
+// make a sphere of faces between lines of latitude and longitude: |segments|
+// around, and |rings| from pole to pole. faces at the poles are triangles.
+#[allow(non_snake_case)]
+pub fn UvSphere(
+    ctr: impl Into<Point3>,
+    radius: f64,
+    (segments, rings): (usize, usize),
+) -> Result<Group3<()>> {
+    if rings < 2 {
+        return Err(anyhow!("need at least two rings"));
+    }
+    let profile: Vec<Point> = (0..=rings)
+        .map(|j| {
+            let theta = PI * j as f64 / rings as f64;
+            // exactly on the axis at the poles, so that faces there close up.
+            let r = if j == 0 || j == rings {
+                0.0
+            } else {
+                radius * theta.sin()
+            };
+            Point(r, -radius * theta.cos())
+        })
+        .collect();
+    revolve(ctr.into(), &profile, false, segments)
+}
+
+// make a sphere of triangles, by splitting each face of an icosahedron
+// |subdivisions| times over. unlike a |UvSphere|, its faces are all about the
+// same size.
+#[allow(non_snake_case)]
+pub fn Icosphere(ctr: impl Into<Point3>, radius: f64, subdivisions: usize) -> Group3<()> {
+    let ctr: Point3 = ctr.into();
+    let t = (1.0 + 5.0_f64.sqrt()) / 2.0;
+    let mut pts: Vec<Point3> = [
+        (-1.0, t, 0.0),
+        (1.0, t, 0.0),
+        (-1.0, -t, 0.0),
+        (1.0, -t, 0.0),
+        (0.0, -1.0, t),
+        (0.0, 1.0, t),
+        (0.0, -1.0, -t),
+        (0.0, 1.0, -t),
+        (t, 0.0, -1.0),
+        (t, 0.0, 1.0),
+        (-t, 0.0, -1.0),
+        (-t, 0.0, 1.0),
+    ]
+    .into_iter()
+    .map(|(x, y, z)| Point3(x, y, z) / (1.0 + t * t).sqrt())
+    .collect();
+    let mut triangles: Vec<[usize; 3]> = vec![
+        [0, 11, 5],
+        [0, 5, 1],
+        [0, 1, 7],
+        [0, 7, 10],
+        [0, 10, 11],
+        [1, 5, 9],
+        [5, 11, 4],
+        [11, 10, 2],
+        [10, 7, 6],
+        [7, 1, 8],
+        [3, 9, 4],
+        [3, 4, 2],
+        [3, 2, 6],
+        [3, 6, 8],
+        [3, 8, 9],
+        [4, 9, 5],
+        [2, 4, 11],
+        [6, 2, 10],
+        [8, 6, 7],
+        [9, 8, 1],
+    ];
+
+    for _ in 0..subdivisions {
+        // each edge's midpoint, pushed out onto the sphere, shared between
+        // the two triangles on either side of it.
+        let mut midpoints: HashMap<(usize, usize), usize> = HashMap::new();
+        let mut midpoint = |a: usize, b: usize, pts: &mut Vec<Point3>| -> usize {
+            *midpoints.entry((a.min(b), a.max(b))).or_insert_with(|| {
+                let m = pts[a].avg(&pts[b]);
+                pts.push(m / m.norm());
+                pts.len() - 1
+            })
+        };
+        triangles = triangles
+            .into_iter()
+            .flat_map(|[a, b, c]| {
+                let ab = midpoint(a, b, &mut pts);
+                let bc = midpoint(b, c, &mut pts);
+                let ca = midpoint(c, a, &mut pts);
+                [[a, ab, ca], [b, bc, ab], [c, ca, bc], [ab, bc, ca]]
+            })
+            .collect();
+    }
+
+    Group3::new(triangles.into_iter().map(|[a, b, c]| {
+        let [a, b, c] = [a, b, c].map(|i| ctr + pts[i] * radius);
+        (Obj3::Polygon3(Polygon3([a, b, c, a])), ())
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use float_eq::assert_float_eq;
+
+    fn pts(g: Group3<()>) -> Vec<Point3> {
+        g.into_iter_objects()
+            .flat_map(|(o, _)| match o {
+                Obj3::Polygon3(pg) => pg.pts,
+                x => panic!("{:?}", x),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_uv_sphere() -> Result<()> {
+        let sphere = UvSphere((1, 2, 3), 2.0, (8, 4))?;
+        // two rings of triangles at the poles, and two of quads between.
+        assert_eq!(sphere.iter_objects().count(), 32);
+        for pt in pts(sphere) {
+            assert_float_eq!(pt.dist(&Point3(1, 2, 3)), 2.0, abs <= 1e-9);
+        }
+        assert!(UvSphere((0, 0, 0), 1.0, (8, 1)).is_err());
+        Ok(())
+    }
+
+    #[test]
+    fn test_icosphere() {
+        for (subdivisions, count) in [(0, 20), (1, 80), (2, 320)] {
+            let sphere = Icosphere((1, 2, 3), 2.0, subdivisions);
+            assert_eq!(sphere.iter_objects().count(), count);
+            for pt in pts(sphere) {
+                assert_float_eq!(pt.dist(&Point3(1, 2, 3)), 2.0, abs <= 1e-9);
+            }
+        }
+    }
+}
@@ -0,0 +1,55 @@
+//! A torus in 3d.
+
+use crate::{
+    group3::Group3,
+    shapes::{point3::Point3, revolution3d::revolve},
+};
+use anyhow::{anyhow, Result};
+use plotz_geometry::shapes::point::Point;
+use std::f64::consts::TAU;
+
+// make a torus, lying flat about |ctr|: a tube of radius |minor| whose middle
+// runs in a circle of radius |major|. it is |segments| faces around, and each
+// ring of faces is |sides| around the tube.
+#[allow(non_snake_case)]
+pub fn Torus(
+    ctr: impl Into<Point3>,
+    major: f64,
+    minor: f64,
+    (segments, sides): (usize, usize),
+) -> Result<Group3<()>> {
+    if sides < 3 {
+        return Err(anyhow!("need at least three sides"));
+    }
+    let profile: Vec<Point> = (0..sides)
+        .map(|k| {
+            let psi = TAU * k as f64 / sides as f64;
+            Point(major + minor * psi.cos(), minor * psi.sin())
+        })
+        .collect();
+    revolve(ctr.into(), &profile, true, segments)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::obj3::Obj3;
+    use float_eq::assert_float_eq;
+
+    #[test]
+    fn test_torus() -> Result<()> {
+        let torus = Torus((0, 0, 1), 3.0, 1.0, (12, 6))?;
+        assert_eq!(torus.iter_objects().count(), 72);
+        for (o, _) in torus.iter_objects() {
+            let Obj3::Polygon3(pg) = o else {
+                panic!("{:?}", o);
+            };
+            for pt in &pg.pts {
+                // every point is on the tube.
+                let from_middle = Point3(pt.x, pt.y, 0.0).norm() - 3.0;
+                assert_float_eq!(from_middle.hypot(pt.z - 1.0), 1.0, abs <= 1e-9);
+            }
+        }
+        Ok(())
+    }
+}